@@ -0,0 +1,28 @@
+//! Times the tree walker against the bytecode VM. Run with `cargo bench`.
+use loop_rs::Interpreter;
+use std::time::{Duration, Instant};
+
+const SCRIPTS: [(&str, &str); 3] = [
+    ("calls", "fn fib(n: i32) -> i32 {\n    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }\n}\nfib(20)"),
+    ("loop", "mut i := 0\nmut total := 0\nwhile i < 100000 {\n    i += 1\n    if i % 3 == 0 { total += i }\n}\ntotal"),
+    ("locals", "fn sum(n: i64) -> i64 {\n    mut total: i64 = 0\n    mut i: i64 = 0\n    while i < n {\n        let square := i * i\n        total += square\n        i += 1\n    }\n    total\n}\nsum(50000)"),
+];
+
+/// The fastest of a few runs of `source`.
+fn time(source: &str, bytecode: bool) -> Duration {
+    (0..5).map(|_| {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_bytecode(bytecode);
+        let start = Instant::now();
+        interpreter.eval_str(source).expect("the script runs");
+        start.elapsed()
+    }).min().unwrap_or_default()
+}
+
+fn main() {
+    for (name, source) in SCRIPTS {
+        let tree = time(source, false);
+        let vm = time(source, true);
+        println!("{:<8} tree walker {:>10.2?}  bytecode {:>10.2?}  ({:.2}x)", name, tree, vm, tree.as_secs_f64() / vm.as_secs_f64());
+    }
+}
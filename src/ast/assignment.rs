@@ -1,9 +1,16 @@
-use crate::ast::tuple::{Clonable, Tuple, TupleError, TupleLike};
-use crate::ast::{ASTNode};
+use crate::ast::tuple::{element_mut, Clonable, Tuple, TupleError, TupleLike};
+use crate::ast::{ASTNode, Node};
+use crate::ast::binary_operation::BinaryOperation;
+use crate::lexer::token::Operator;
 use crate::ast::value::Value;
 use crate::environment::environment::{Environment, RuntimeError};
 use crate::ast::type_node::Type;
 use crate::Error;
+use crate::checker::checker::TypeChecker;
+use crate::environment::frame::Address;
+use crate::environment::trace::summarize;
+use std::cell::Cell;
+use std::rc::Rc;
 
 impl Clonable for String {
     fn clone_element(&self) -> Self {
@@ -20,11 +27,69 @@ pub enum AssignmentError {
     ValuesAndNamesDontMatch(Tuple<String>, Value),
 }
 
+impl std::fmt::Display for AssignmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssignmentError::CannotAssignToEmptyName => write!(f, "nothing to assign to"),
+            AssignmentError::CannotAssignToTupleName => write!(f, "cannot assign to a tuple of names here"),
+            AssignmentError::CannotDeclareVariableWithEmptyName => write!(f, "nothing to declare"),
+            AssignmentError::CannotDeclareVariableWithTupleName => write!(f, "cannot declare a tuple of names here"),
+            AssignmentError::ValuesAndNamesDontMatch(names, value) => write!(f, "cannot unpack {} into {}", summarize(value), names),
+        }
+    }
+}
+
 
 #[derive(Debug)]
 pub struct VariableAssignment {
     pub name: Tuple<String>,
-    pub expr: Box<dyn ASTNode>,
+    pub expr: Rc<Node>,
+    /// Set by the resolver when `name` is a single variable declared in an
+    /// enclosing block.
+    pub address: Cell<Option<Address>>,
+}
+
+/// `name.0.1 := expr`, replacing one element of a `mut` tuple.
+#[derive(Debug)]
+pub struct TupleElementAssignment {
+    pub name: String,
+    pub path: Vec<usize>,
+    pub expr: Rc<Node>,
+}
+
+/// `name[index] := expr`, replacing an element of a `mut` list or setting a
+/// key of a `mut` map where it is. `grid[i][j] := expr` has an index for
+/// each pair of brackets.
+#[derive(Debug)]
+pub struct IndexAssignment {
+    pub name: String,
+    pub indexes: Vec<Rc<Node>>,
+    pub expr: Rc<Node>,
+}
+
+/// `name += expr` and the other compound assignments, with `path` holding
+/// the indexes of a `name.0.1 += expr`. A tuple of names takes the elements
+/// of a tuple value: `(a, b) += (1, 2)`.
+#[derive(Debug)]
+pub struct CompoundAssignment {
+    pub name: Tuple<String>,
+    pub path: Vec<usize>,
+    pub operator: Operator,
+    pub expr: Rc<Node>,
+    /// Like `VariableAssignment::address`.
+    pub address: Cell<Option<Address>>,
+}
+
+/// The operator `+=`, `-=`, `*=`, `/=` or `%=` applies, `None` for any other.
+pub fn compound_operator(operator: &Operator) -> Option<Operator> {
+    match operator {
+        Operator::PlusAssign => Some(Operator::Add),
+        Operator::MinusAssign => Some(Operator::Sub),
+        Operator::MulAssign => Some(Operator::Mul),
+        Operator::DivAssign => Some(Operator::Div),
+        Operator::ModAssign => Some(Operator::Mod),
+        _ => None,
+    }
 }
 
 #[derive(Debug)]
@@ -32,7 +97,7 @@ pub struct VariableDeclarationAssignment {
     pub mutable: bool,
     pub type_: Option<Type>,
     pub name: Tuple<String>,
-    pub expr: Box<dyn ASTNode>,
+    pub expr: Rc<Node>,
 }
 
 impl ASTNode for VariableAssignment {
@@ -40,16 +105,24 @@ impl ASTNode for VariableAssignment {
         format!("{} :=", self.name)
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
-        vec![self.expr.clone_to_node()]
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.expr.clone()]
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let new_assignment = VariableAssignment {
-            name: self.name.clone(),
-            expr: self.expr.clone_to_node(),
-        };
-        Box::new(new_assignment)
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let found = self.expr.check(checker);
+        match (&self.name, &found) {
+            (Tuple::Element(name), _) => checker.assign(name, found.clone()),
+            (Tuple::List(names), Type::Tuple(types)) if names.len() == types.len() => {
+                for (name, type_) in names.iter().zip(types) {
+                    if let Tuple::Element(name) = name {
+                        checker.assign(name, type_.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+        Type::Bool
     }
 
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
@@ -59,7 +132,7 @@ impl ASTNode for VariableAssignment {
                 return Err(Error::RuntimeError(RuntimeError::AssignmentError(AssignmentError::CannotAssignToEmptyName)));
             }
             (Tuple::Element(name), _) => {
-                env.assign(name, value.clone())?;
+                env.assign_at(name, self.address.get(), value.clone())?;
             }
             (Tuple::List(_), Value::Tuple(_)) => {
                 let name_value_pairs = match self.name.pair_up(value.to_tuple()) {
@@ -81,6 +154,140 @@ impl ASTNode for VariableAssignment {
 
 }
 
+impl ASTNode for TupleElementAssignment {
+    fn element(&self) -> String {
+        let path = self.path.iter().map(|index| index.to_string()).collect::<Vec<_>>();
+        format!("{}.{} :=", self.name, path.join("."))
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.expr.clone()]
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let found = self.expr.check(checker);
+        checker.assign_element(&self.name, &self.path, found);
+        Type::Bool
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let value = self.expr.eval(env)?;
+        let mut tuple = env.lookup(&self.name)?;
+        *element_mut(&mut tuple, &self.path)? = value;
+        // assigning the whole tuple checks that it is `mut` and of its type
+        env.assign(&self.name, tuple)?;
+        Ok(Value::Bool(true))
+    }
+}
+
+impl ASTNode for IndexAssignment {
+    fn element(&self) -> String {
+        format!("{}{} :=", self.name, "[]".repeat(self.indexes.len()))
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        let mut children = self.indexes.clone();
+        children.push(self.expr.clone());
+        children
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let found = self.expr.check(checker);
+        let indexes = self.indexes.iter().map(|index| index.check(checker)).collect();
+        checker.assign_index(&self.name, indexes, found);
+        Type::Bool
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let value = self.expr.eval(env)?;
+        let mut indexes = Vec::new();
+        for index in &self.indexes {
+            indexes.push(index.eval(env)?);
+        }
+        env.assign_index(&self.name, &indexes, value)?;
+        Ok(Value::Bool(true))
+    }
+}
+
+impl ASTNode for CompoundAssignment {
+    fn element(&self) -> String {
+        match &self.name {
+            Tuple::Element(name) if !self.path.is_empty() => {
+                let target = self.path.iter().fold(name.clone(), |target, index| format!("{}.{}", target, index));
+                format!("{} {:?}=", target, self.operator)
+            }
+            name => format!("{} {:?}=", name, self.operator),
+        }
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.expr.clone()]
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let found = self.expr.check(checker);
+        match (&self.name, found) {
+            (Tuple::Element(name), found) if !self.path.is_empty() => {
+                let current = self.path.iter().fold(checker.lookup(name), |type_, index| checker.tuple_element(type_, *index));
+                let result = checker.binary(&self.operator, current, found);
+                checker.assign_element(name, &self.path, result);
+            }
+            (Tuple::Element(name), found) => {
+                let current = checker.lookup(name);
+                let result = checker.binary(&self.operator, current, found);
+                checker.assign(name, result);
+            }
+            (Tuple::List(names), Type::Tuple(types)) if names.len() == types.len() => {
+                for (name, type_) in names.iter().zip(types) {
+                    if let Tuple::Element(name) = name {
+                        let current = checker.lookup(name);
+                        let result = checker.binary(&self.operator, current, type_);
+                        checker.assign(name, result);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Type::Bool
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let value = self.expr.eval(env)?;
+        match (&self.name, &value) {
+            (Tuple::Empty, _) => {
+                return Err(Error::RuntimeError(RuntimeError::AssignmentError(AssignmentError::CannotAssignToEmptyName)));
+            }
+            (Tuple::Element(name), _) if !self.path.is_empty() => {
+                let mut tuple = env.lookup_at(name, self.address.get())?;
+                let element = element_mut(&mut tuple, &self.path)?;
+                *element = BinaryOperation::apply(&self.operator, element.clone(), value, env.strict_float)?;
+                env.assign_at(name, self.address.get(), tuple)?;
+            }
+            (Tuple::Element(name), _) => {
+                let current = env.lookup_at(name, self.address.get())?;
+                let result = BinaryOperation::apply(&self.operator, current, value, env.strict_float)?;
+                env.assign_at(name, self.address.get(), result)?;
+            }
+            (Tuple::List(_), Value::Tuple(_)) => {
+                let name_value_pairs = self.name.pair_up(value.to_tuple()).map_err(Error::TupleError)?;
+                // every element is worked out before any is assigned
+                let mut results = Vec::new();
+                for (name, value) in name_value_pairs {
+                    let current = env.lookup(&name)?;
+                    results.push((name, BinaryOperation::apply(&self.operator, current, value, env.strict_float)?));
+                }
+                for (name, result) in results {
+                    env.assign(&name, result)?;
+                }
+            }
+            (Tuple::List(_), _) => {
+                return Err(Error::RuntimeError(RuntimeError::AssignmentError(AssignmentError::CannotAssignToTupleName)));
+            }
+        }
+        Ok(Value::Bool(true))
+    }
+}
+
 impl ASTNode for VariableDeclarationAssignment {
     fn element(&self) -> String {
         let type_ = match &self.type_ {
@@ -90,18 +297,14 @@ impl ASTNode for VariableDeclarationAssignment {
         format!("{} {} : {} =", if self.mutable { "mut" } else { "let" }, self.name, type_)
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
-        vec![self.expr.clone_to_node()]
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.expr.clone()]
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let new_assignment = VariableDeclarationAssignment {
-            mutable: self.mutable,
-            type_: self.type_.clone(),
-            name: self.name.clone(),
-            expr: self.expr.clone_to_node(),
-        };
-        Box::new(new_assignment)
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let found = self.expr.check(checker);
+        checker.declare_pattern(&self.name, self.type_.as_ref(), found, self.mutable);
+        Type::Bool
     }
 
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
@@ -2,7 +2,27 @@ use crate::ast::value::Value;
 use crate::environment::environment::{Environment, ReferenceOrValue};
 use crate::ast::tuple::Clonable;
 use crate::Error;
-use crate::lexer::token;
+use crate::lexer::{token, Span};
+use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::assignment::{CompoundAssignment, IndexAssignment, TupleElementAssignment, VariableAssignment, VariableDeclarationAssignment};
+use crate::ast::binary_operation::BinaryOperation;
+use crate::ast::cast::Cast;
+use crate::ast::conditional::{ElifBlock, ElseBlock, IfBlock};
+use crate::ast::error::{Except, Raise};
+use crate::ast::function::{Borrow, FnCall, FnDeclaration, MethodCall};
+use crate::ast::identifier::Identifier;
+use crate::ast::import::Import;
+use crate::ast::list::{Contains, Index, List, Slice};
+use crate::ast::literal::Literal;
+use crate::ast::loops::{For, Loop, While};
+use crate::ast::map::MapLiteral;
+use crate::ast::optional::{Coalesce, Propagate, Unwrap};
+use crate::ast::task::{Async, Await};
+use crate::ast::tuple::{TupleASTNode, TupleIndex};
+use crate::ast::unary_operation::UnaryOperation;
+use crate::ast::variable_declaration::VariableDeclaration;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub enum ASTError {
@@ -10,8 +30,17 @@ pub enum ASTError {
     InvalidIdentifierToken(token::Token),
 }
 
+impl std::fmt::Display for ASTError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ASTError::InvalidLiteralToken(token) => write!(f, "`{}` is not a literal", token.source()),
+            ASTError::InvalidIdentifierToken(token) => write!(f, "`{}` is not an identifier", token.source()),
+        }
+    }
+}
+
 pub trait ASTNode : std::fmt::Debug {
-    fn children(&self) -> Vec<Box<dyn ASTNode>>;
+    fn children(&self) -> Vec<Rc<Node>>;
     fn element(&self) -> String;
     fn print_tree(&self, indent: usize) -> String {
         let mut result = String::new();
@@ -45,39 +74,177 @@ pub trait ASTNode : std::fmt::Debug {
         }
     }
 
+    /// The static type of the node. Mismatches are reported to the checker.
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let mut type_ = Type::Any;
+        for child in self.children() {
+            type_ = child.check(checker);
+        }
+        type_
+    }
+
     fn get_reference(&self, env: &mut Environment) -> Result<ReferenceOrValue, Error> {
         Ok(ReferenceOrValue::from_value(self.eval(env)?))
     }
+}
+
+impl Clonable for Rc<Node> {
+    fn clone_element(&self) -> Rc<Node> {
+        Rc::clone(self)
+    }
+}
+
+macro_rules! nodes {
+    ($($kind:ident),+ $(,)?) => {
+        /// The kinds of node of the AST, for passes that need to know which
+        /// kind of node they have.
+        #[derive(Debug)]
+        pub enum NodeKind {
+            $($kind($kind),)+
+        }
+
+        $(impl From<$kind> for Node {
+            fn from(node: $kind) -> Self {
+                Node { kind: NodeKind::$kind(node), span: None }
+            }
+        })+
+
+        impl ASTNode for Node {
+            fn children(&self) -> Vec<Rc<Node>> {
+                match &self.kind {
+                    $(NodeKind::$kind(node) => node.children(),)+
+                }
+            }
+
+            fn element(&self) -> String {
+                match &self.kind {
+                    $(NodeKind::$kind(node) => node.element(),)+
+                }
+            }
+
+            fn print_tree(&self, indent: usize) -> String {
+                match &self.kind {
+                    $(NodeKind::$kind(node) => node.print_tree(indent),)+
+                }
+            }
+
+            fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+                let result = match &self.kind {
+                    $(NodeKind::$kind(node) => node.eval(env),)+
+                };
+                result.map_err(|e| self.locate(env, e))
+            }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode>;
+            fn check(&self, checker: &mut TypeChecker) -> Type {
+                let outer = checker.span;
+                checker.span = self.span.or(outer);
+                let type_ = match &self.kind {
+                    $(NodeKind::$kind(node) => node.check(checker),)+
+                };
+                checker.span = outer;
+                type_
+            }
+
+            fn get_reference(&self, env: &mut Environment) -> Result<ReferenceOrValue, Error> {
+                let result = match &self.kind {
+                    $(NodeKind::$kind(node) => node.get_reference(env),)+
+                };
+                result.map_err(|e| self.locate(env, e))
+            }
+        }
+    };
+}
+
+nodes!(
+    MultiExpression,
+    EmptyASTNode,
+    Literal,
+    Identifier,
+    BinaryOperation,
+    UnaryOperation,
+    TupleASTNode,
+    TupleIndex,
+    List,
+    Index,
+    Slice,
+    Contains,
+    MapLiteral,
+    VariableDeclaration,
+    VariableAssignment,
+    TupleElementAssignment,
+    IndexAssignment,
+    CompoundAssignment,
+    VariableDeclarationAssignment,
+    IfBlock,
+    ElifBlock,
+    ElseBlock,
+    Loop,
+    For,
+    While,
+    FnDeclaration,
+    FnCall,
+    MethodCall,
+    Borrow,
+    Cast,
+    Unwrap,
+    Propagate,
+    Coalesce,
+    Raise,
+    Except,
+    Import,
+    Async,
+    Await,
+);
 
+/// A node of the AST and where it was parsed from. Children are shared with
+/// `Rc`, so cloning a node or taking its children never copies a subtree.
+pub struct Node {
+    pub kind: NodeKind,
+    /// Where the node is in the source, if it was parsed from one.
+    pub span: Option<Span>,
 }
 
-impl Clonable for Box<dyn ASTNode> {
-    fn clone_element(&self) -> Box<dyn ASTNode> {
-        self.clone_to_node()
+impl std::fmt::Debug for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl Node {
+    pub fn new(node: impl Into<Node>) -> Rc<Node> {
+        Rc::new(node.into())
+    }
+
+    /// Whether evaluating the node declares a variable in the current scope.
+    pub fn declares(&self) -> bool {
+        match &self.kind {
+            NodeKind::VariableDeclaration(_) | NodeKind::VariableDeclarationAssignment(_) | NodeKind::FnDeclaration(_) | NodeKind::Import(_) => true,
+            NodeKind::MultiExpression(multi) => multi.children.iter().any(|child| child.declares()),
+            _ => false,
+        }
+    }
+
+    /// Records the node as where `error` was raised, unless a node inside it
+    /// already is or the error only unwinds to a call.
+    pub(crate) fn locate(&self, env: &Environment, error: Error) -> Error {
+        env.locate(self.span, error)
     }
 }
 
 #[derive(Debug)]
 pub struct MultiExpression {
-    pub children: Vec<Box<dyn ASTNode>>,
+    pub children: Vec<Rc<Node>>,
 }
 
 impl ASTNode for MultiExpression {
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
-        self.children.iter().map(|c| c.as_ref().clone_to_node()).collect()
+    fn children(&self) -> Vec<Rc<Node>> {
+        self.children.clone()
     }
 
     fn element(&self) -> String {
         "MultiExpression".to_string()
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let children = self.children.iter().map(|c| c.as_ref().clone_to_node()).collect();
-        Box::new(MultiExpression { children })
-    }
-
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
         let mut result = Value::None;
         for child in self.children.iter() {
@@ -85,6 +252,14 @@ impl ASTNode for MultiExpression {
         }
         Ok(result)
     }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let mut type_ = Type::Any;
+        for child in self.children.iter() {
+            type_ = child.check(checker);
+        }
+        type_
+    }
 }
 
 
@@ -98,7 +273,7 @@ impl EmptyASTNode {
 }
 
 impl ASTNode for EmptyASTNode {
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         vec![]
     }
 
@@ -106,11 +281,11 @@ impl ASTNode for EmptyASTNode {
         "Empty".to_string()
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(EmptyASTNode {})
-    }
-
     fn eval(&self, _env: &mut Environment) -> Result<Value, Error> {
         Ok(Value::None)
     }
+
+    fn check(&self, _checker: &mut TypeChecker) -> Type {
+        Type::Option(Box::new(Type::Any))
+    }
 }
\ No newline at end of file
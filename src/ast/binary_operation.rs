@@ -1,67 +1,79 @@
 use crate::ast::{ASTNode,value::Value};
 use crate::environment::environment::{Environment, RuntimeError};
+use crate::environment::trace::summarize;
 use crate::lexer::token::Operator;
 use crate::Error;
+use crate::ast::integer;
+use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::Node;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct BinaryOperation {
-    pub left: Box<dyn ASTNode>,
-    pub right: Box<dyn ASTNode>,
+    pub left: Rc<Node>,
+    pub right: Rc<Node>,
     pub operator: Operator,
 }
 
 #[derive(Debug)]
 pub enum BinaryOperationError {
     CannotPerform(Operator, Value, Value),
+    NoneOperand(Operator),
+}
+
+impl std::fmt::Display for BinaryOperationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinaryOperationError::CannotPerform(operator, left, right) => {
+                write!(f, "cannot apply `{}` to {} and {}", operator, summarize(left), summarize(right))
+            }
+            BinaryOperationError::NoneOperand(operator) => write!(f, "none used as an operand of `{}`", operator),
+        }
+    }
 }
 
 impl ASTNode for BinaryOperation {
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
-        vec![self.left.clone_to_node(), self.right.clone_to_node()]
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.left.clone(), self.right.clone()]
     }
 
     fn element(&self) -> String {
         format!("{:?}", self.operator)
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let left = self.left.clone_to_node();
-        let right = self.right.clone_to_node();
-        Box::new(BinaryOperation {
-            left,
-            right,
-            operator: self.operator.clone(),
-        })
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let left = self.left.check(checker);
+        let right = self.right.check(checker);
+        checker.binary(&self.operator, left, right)
     }
 
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
         let left = self.left.eval(env)?;
         let right = self.right.eval(env)?;
-        Ok(match (&left, &right) {
-                (Value::Int(l), Value::Int(r)) => {
-                    match self.operator {
-                        Operator::Add => Value::Int(l + r),
-                        Operator::Sub => Value::Int(l - r),
-                        Operator::Mul => Value::Int(l * r),
-                        Operator::Div => Value::Int(l / r),
-                        Operator::Mod => Value::Int(l % r),
-                        Operator::Pow => Value::Int(l.pow(*r as u32)),
-                        Operator::BitShiftLeft => Value::Int(l << r),
-                        Operator::BitShiftRight => Value::Int(l >> r),
-                        Operator::BitAnd => Value::Int(l & r),
-                        Operator::BitOr => Value::Int(l | r),
-                        Operator::BitXor => Value::Int(l ^ r),
-                        Operator::Gt => Value::Bool(l > r),
-                        Operator::Lt => Value::Bool(l < r),
-                        Operator::Gte => Value::Bool(l >= r),
-                        Operator::Lte => Value::Bool(l <= r),
-                        Operator::Eq => Value::Bool(*l == *r),
-                        Operator::Neq => Value::Bool(*l != *r),
-                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
-                    }
+        Self::apply(&self.operator, left, right, env.strict_float)
+    }
+}
+
+fn to_float(value: Value) -> Value {
+    match integer::as_integer(&value) {
+        Some((i, _)) => Value::Float(i as f64),
+        None => value,
+    }
+}
+
+impl BinaryOperation {
+    /// Applies `operator` to two values, the way `left operator right` and
+    /// `left operator= right` evaluate.
+    pub fn apply(operator: &Operator, left: Value, right: Value, strict_float: bool) -> Result<Value, Error> {
+        let result = match (&left, &right) {
+                (Value::Int(_) | Value::TypedInt(..), Value::Int(_) | Value::TypedInt(..)) => integer::binary(operator, &left, &right)?,
+                (Value::Float(_), Value::Int(_) | Value::TypedInt(..)) | (Value::Int(_) | Value::TypedInt(..), Value::Float(_)) => {
+                    // an integer mixed with a float is converted to a float
+                    return Self::apply(operator, to_float(left), to_float(right), strict_float);
                 }
                 (Value::Float(l), Value::Float(r)) => {
-                    match self.operator {
+                    match operator {
                         Operator::Add => Value::Float(l + r),
                         Operator::Sub => Value::Float(l - r),
                         Operator::Mul => Value::Float(l * r),
@@ -74,29 +86,44 @@ impl ASTNode for BinaryOperation {
                         Operator::Lte => Value::Bool(l <= r),
                         Operator::Eq => Value::Bool(*l == *r),
                         Operator::Neq => Value::Bool(*l != *r),
-                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left, right)))),
                     }
                 }
-                (Value::String(l, _), Value::String(r, _)) => {
-                    match self.operator {
-                        Operator::Add => Value::String(l.clone() + &r.clone(), false),
-                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
+                (Value::String(l, l_raw), Value::String(r, r_raw)) => {
+                    match operator {
+                        Operator::Add => Value::String(l.clone() + r, *l_raw && *r_raw),
+                        // compared by their text, raw or not
+                        Operator::Gt => Value::Bool(l > r),
+                        Operator::Lt => Value::Bool(l < r),
+                        Operator::Gte => Value::Bool(l >= r),
+                        Operator::Lte => Value::Bool(l <= r),
+                        Operator::Eq => Value::Bool(l == r),
+                        Operator::Neq => Value::Bool(l != r),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left, right)))),
                     }
                 }
+                (Value::String(s, raw), count @ (Value::Int(_) | Value::TypedInt(..)))
+                | (count @ (Value::Int(_) | Value::TypedInt(..)), Value::String(s, raw)) if *operator == Operator::Mul => {
+                    let count = integer::as_integer(count).map(|(count, _)| count).unwrap_or_default();
+                    if count < 0 {
+                        return Err(Error::RuntimeError(RuntimeError::NegativeRepetition(operator.clone(), vec![left.clone(), right.clone()])));
+                    }
+                    Value::String(s.repeat(count as usize), *raw)
+                }
                 (Value::Bool(l), Value::Bool(r)) => {
-                    match self.operator {
+                    match operator {
                         Operator::Eq => Value::Bool(*l == *r),
                         Operator::Neq => Value::Bool(*l != *r),
                         Operator::And => Value::Bool(*l && *r),
                         Operator::Or => Value::Bool(*l || *r),
-                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left, right)))),
                     }
                 }
                 (Value::Tuple(l), Value::Tuple(r)) => {
                     if l.len() != r.len() {
-                        return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right))));
+                        return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left, right))));
                     }
-                    match self.operator {
+                    match operator {
                         Operator::Eq => {
                             let mut result = true;
                             for (a, b) in l.iter().zip(r.iter()) {
@@ -111,31 +138,49 @@ impl ASTNode for BinaryOperation {
                             }
                             Value::Bool(result)
                         }
-                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left, right)))),
+                    }
+                }
+                (Value::Map(l), Value::Map(r)) => {
+                    match operator {
+                        Operator::Eq => Value::Bool(l == r),
+                        Operator::Neq => Value::Bool(l != r),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left, right)))),
+                    }
+                }
+                (Value::List(l), Value::List(r)) => {
+                    match operator {
+                        Operator::Eq => Value::Bool(l == r),
+                        Operator::Neq => Value::Bool(l != r),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left, right)))),
                     }
                 }
                 (Value::None, Value::None) => {
-                    match self.operator {
+                    match operator {
                         Operator::Eq => Value::Bool(true),
                         Operator::Neq => Value::Bool(false),
-                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::NoneOperand(operator.clone())))),
                     }
                 }
                 (Value::None, _) => {
-                    match self.operator {
+                    match operator {
                         Operator::Eq => Value::Bool(false),
                         Operator::Neq => Value::Bool(true),
-                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::NoneOperand(operator.clone())))),
                     }
                 }
                 (_, Value::None) => {
-                    match self.operator {
+                    match operator {
                         Operator::Eq => Value::Bool(false),
                         Operator::Neq => Value::Bool(true),
-                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
+                        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::NoneOperand(operator.clone())))),
                     }
                 }
-                _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(self.operator.clone(), left, right)))),
-            })
+                _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left, right)))),
+            };
+        match result {
+            Value::Float(f) if strict_float && !f.is_finite() => Err(Error::RuntimeError(RuntimeError::NonFiniteFloat(operator.clone(), vec![left, right]))),
+            result => Ok(result),
+        }
     }
 }
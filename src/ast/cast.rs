@@ -0,0 +1,128 @@
+use crate::ast::integer;
+use crate::ast::{ASTNode, value::Value};
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::Error;
+use crate::ast::type_node::Type;
+use crate::checker::checker::{TypeChecker, TypeCheckError};
+use crate::ast::Node;
+use std::rc::Rc;
+
+/// `value as type` fails when the value cannot be represented exactly,
+/// `value as! type` wraps integers around, truncates floats and rounds
+/// instead.
+#[derive(Debug)]
+pub struct Cast {
+    pub value: Rc<Node>,
+    pub type_: Type,
+    pub lossy: bool,
+}
+
+impl ASTNode for Cast {
+    fn element(&self) -> String {
+        format!("{} {:?}", if self.lossy { "as!" } else { "as" }, self.type_)
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.value.clone()]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let value = self.value.eval(env)?;
+        cast(value, &self.type_, self.lossy)
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let found = self.value.check(checker);
+        if !castable(&found) || !castable(&self.type_) {
+            checker.report(TypeCheckError::InvalidCast(found, self.type_.clone()));
+        }
+        self.type_.clone()
+    }
+}
+
+fn castable(type_: &Type) -> bool {
+    type_.is_integer() || type_.is_float() || matches!(type_, Type::Bool | Type::String | Type::Any)
+}
+
+fn invalid(value: Value, type_: &Type) -> Error {
+    Error::RuntimeError(RuntimeError::InvalidCast(value, type_.clone()))
+}
+
+fn lossy_cast(value: Value, type_: &Type) -> Error {
+    Error::RuntimeError(RuntimeError::LossyCast(value, type_.clone()))
+}
+
+/// Converts `value` to one of the numeric types, `bool` or `string`.
+pub fn cast(value: Value, type_: &Type, lossy: bool) -> Result<Value, Error> {
+    match type_ {
+        Type::String => match value {
+            Value::String(..) => Ok(value),
+            Value::Int(_) | Value::TypedInt(..) | Value::Float(_) | Value::Bool(_) => Ok(Value::String(value.to_string(), false)),
+            _ => Err(invalid(value, type_)),
+        },
+        Type::Bool => match &value {
+            Value::Bool(_) => Ok(value),
+            Value::String(s, _) => s.trim().parse::<bool>().map(Value::Bool).map_err(|_| invalid(value.clone(), type_)),
+            Value::Float(f) if lossy || *f == 0.0 || *f == 1.0 => Ok(Value::Bool(*f != 0.0)),
+            Value::Float(_) => Err(lossy_cast(value, type_)),
+            _ => match integer::as_integer(&value) {
+                Some((i, _)) if lossy || i == 0 || i == 1 => Ok(Value::Bool(i != 0)),
+                Some(_) => Err(lossy_cast(value, type_)),
+                None => Err(invalid(value, type_)),
+            },
+        },
+        _ if type_.is_integer() => {
+            let i = match &value {
+                Value::Bool(b) => *b as i128,
+                // truncated towards zero, saturating at the bounds of `i128`; NaN is 0
+                Value::Float(f) if lossy => *f as i128,
+                Value::Float(f) if f.is_finite() && f.fract() == 0.0 => *f as i128,
+                Value::Float(_) => return Err(lossy_cast(value, type_)),
+                Value::String(s, _) => match s.trim().parse::<i128>() {
+                    Ok(i) => i,
+                    Err(_) => return Err(invalid(value, type_)),
+                },
+                _ => match integer::as_integer(&value) {
+                    Some((i, _)) => i,
+                    None => return Err(invalid(value, type_)),
+                },
+            };
+            match (integer::fits(i, type_), lossy, &value) {
+                (true, _, _) => Ok(Value::TypedInt(i, type_.clone())),
+                (false, true, Value::Float(_)) => Ok(Value::TypedInt(integer::saturate(i, type_), type_.clone())),
+                (false, true, _) => Ok(Value::TypedInt(integer::wrap(i, type_), type_.clone())),
+                (false, false, _) => Err(lossy_cast(value, type_)),
+            }
+        }
+        _ if type_.is_float() => {
+            let f = match &value {
+                Value::Float(f) => *f,
+                Value::Bool(b) => *b as i64 as f64,
+                Value::String(s, _) => match s.trim().parse::<f64>() {
+                    Ok(f) => f,
+                    Err(_) => return Err(invalid(value, type_)),
+                },
+                _ => match integer::as_integer(&value) {
+                    Some((i, _)) if lossy || exact(i, type_) => i as f64,
+                    Some(_) => return Err(lossy_cast(value, type_)),
+                    None => return Err(invalid(value, type_)),
+                },
+            };
+            // a finite float out of the range of `f32` is infinity with `as!`, and an error with `as`
+            match type_ {
+                Type::F32 if !lossy && f.is_finite() && (f as f32).is_infinite() => Err(lossy_cast(value, type_)),
+                Type::F32 => Ok(Value::Float(f as f32 as f64)),
+                _ => Ok(Value::Float(f)),
+            }
+        }
+        _ => Err(invalid(value, type_)),
+    }
+}
+
+/// Whether `i` has an exact representation as a float of type `type_`.
+fn exact(i: i128, type_: &Type) -> bool {
+    match type_ {
+        Type::F32 => i as f32 as i128 == i,
+        _ => i as f64 as i128 == i,
+    }
+}
@@ -1,42 +1,57 @@
 use crate::ast::{ASTNode,Value, scope::Scope};
 use crate::Error;
-use crate::environment::environment::Environment;
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::{Node, NodeKind};
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct IfBlock {
-    pub condition: Box<dyn ASTNode>,
+    pub condition: Rc<Node>,
     pub content: Scope,
-    pub next_conditional: Option<Box<dyn Conditional>>,
+    pub next_conditional: Option<Rc<Node>>,
 }
 
 impl IfBlock {
-    pub fn new(condition: Box<dyn ASTNode>, content: Scope, next_conditional: Option<Box<dyn Conditional>>) -> Self {
+    pub fn new(condition: Rc<Node>, content: Scope, next_conditional: Option<Rc<Node>>) -> Self {
         Self { condition, content, next_conditional }
     }
 }
 
+/// Whether a condition is `true`. A condition that is not a bool raises.
+pub fn holds(condition: Value) -> Result<bool, Error> {
+    match condition {
+        Value::Bool(value) => Ok(value),
+        condition => Err(Error::RuntimeError(RuntimeError::ConditionNotBool(condition))),
+    }
+}
+
 pub trait Conditional: ASTNode {
     fn condition(&self, env: &mut Environment) -> Result<Value, Error>;
-    fn next_conditional(&self) -> Option<Box<dyn Conditional>>;
-    fn clone_conditional(&self) -> Box<dyn Conditional>;
-    fn content(&self) -> Scope;
+    fn next_conditional(&self) -> Option<&dyn Conditional>;
+    fn content(&self) -> &Scope;
 
     fn evaluate_conditional(&self, env: &mut Environment) -> Result<Option<Value>, Error> {
-        let mut current_conditional: Option<Box<dyn Conditional>> = Some(self.clone_conditional());
-        while let Some(conditional) = current_conditional {
-            match conditional.condition(env) {
-                Ok(Value::Bool(true)) => {
-                    let result = conditional.content().eval(env)?;
-                    return Ok(Some(result));
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-                _ => {}
-            }
-            current_conditional = conditional.next_conditional();
+        if holds(self.condition(env)?)? {
+            return Ok(Some(self.content().eval(env)?));
+        }
+        match self.next_conditional() {
+            Some(next) => next.evaluate_conditional(env),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Node {
+    /// The `if`, `elif` or `else` block of the node, if it is one.
+    pub fn as_conditional(&self) -> Option<&dyn Conditional> {
+        match &self.kind {
+            NodeKind::IfBlock(block) => Some(block),
+            NodeKind::ElifBlock(block) => Some(block),
+            NodeKind::ElseBlock(block) => Some(block),
+            _ => None,
         }
-        Ok(None)
     }
 }
 
@@ -45,17 +60,12 @@ impl Conditional for IfBlock {
         self.condition.eval(env)
     }
 
-    fn next_conditional(&self) -> Option<Box<dyn Conditional>> {
-        self.next_conditional.as_ref().map(|c| c.clone_conditional())
-    }
-
-    fn clone_conditional(&self) -> Box<dyn Conditional> {
-        let content = self.content.clone();
-        Box::new(IfBlock::new(self.condition.clone_to_node(), content, self.next_conditional.as_ref().map(|c| c.clone_conditional())))
+    fn next_conditional(&self) -> Option<&dyn Conditional> {
+        self.next_conditional.as_deref().and_then(Node::as_conditional)
     }
 
-    fn content(&self) -> Scope {
-        self.content.clone()
+    fn content(&self) -> &Scope {
+        &self.content
     }
 }
 
@@ -64,7 +74,17 @@ impl ASTNode for IfBlock {
         "If".to_string()
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let condition = self.condition.check(checker);
+        checker.condition(condition);
+        let content = checker.check_scope(&self.content);
+        match &self.next_conditional {
+            Some(next) if next.check(checker) == content => content,
+            _ => Type::Any,
+        }
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
         self.content.children()
     }
 
@@ -75,10 +95,6 @@ impl ASTNode for IfBlock {
             Err(e) => Err(e),
         }
     }
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let content = self.content.clone();
-        Box::new(IfBlock::new(self.condition.clone_to_node(), content, self.next_conditional.as_ref().map(|c| c.clone_conditional())))
-    }
 
     fn print_tree(&self, indent: usize) -> String {
         let mut result = String::new();
@@ -117,17 +133,12 @@ impl Conditional for ElseBlock {
         Ok(Value::Bool(true))
     }
 
-    fn next_conditional(&self) -> Option<Box<dyn Conditional>> {
+    fn next_conditional(&self) -> Option<&dyn Conditional> {
         None
     }
 
-    fn clone_conditional(&self) -> Box<dyn Conditional> {
-        let content = self.content.clone();
-        Box::new(ElseBlock::new(content))
-    }
-
-    fn content(&self) -> Scope {
-        self.content.clone()
+    fn content(&self) -> &Scope {
+        &self.content
     }
 }
 
@@ -136,7 +147,11 @@ impl ASTNode for ElseBlock {
         "Else".to_string()
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        checker.check_scope(&self.content)
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
         self.content.children()
     }
 
@@ -150,11 +165,6 @@ impl ASTNode for ElseBlock {
         }
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let content = self.content.clone();
-        Box::new(ElseBlock::new(content))
-    }
-
     fn print_tree(&self, indent: usize) -> String {
         let mut result = String::new();
         result.push('|');
@@ -172,13 +182,13 @@ impl ASTNode for ElseBlock {
 
 #[derive(Debug)]
 pub struct ElifBlock {
-    pub condition: Box<dyn ASTNode>,
+    pub condition: Rc<Node>,
     pub content: Scope,
-    pub next_conditional: Option<Box<dyn Conditional>>,
+    pub next_conditional: Option<Rc<Node>>,
 }
 
 impl ElifBlock {
-    pub fn new(condition: Box<dyn ASTNode>, content: Scope, next_conditional: Option<Box<dyn Conditional>>) -> Self {
+    pub fn new(condition: Rc<Node>, content: Scope, next_conditional: Option<Rc<Node>>) -> Self {
         Self { condition, content, next_conditional }
     }
 }
@@ -188,17 +198,12 @@ impl Conditional for ElifBlock {
         self.condition.eval(env)
     }
 
-    fn next_conditional(&self) -> Option<Box<dyn Conditional>> {
-        self.next_conditional.as_ref().map(|c| c.clone_conditional())
+    fn next_conditional(&self) -> Option<&dyn Conditional> {
+        self.next_conditional.as_deref().and_then(Node::as_conditional)
     }
 
-    fn clone_conditional(&self) -> Box<dyn Conditional> {
-        let content = self.content.clone();
-        Box::new(ElifBlock::new(self.condition.clone_to_node(), content, self.next_conditional.as_ref().map(|c| c.clone_conditional())))
-    }
-
-    fn content(&self) -> Scope {
-        self.content.clone()
+    fn content(&self) -> &Scope {
+        &self.content
     }
 }
 
@@ -207,7 +212,17 @@ impl ASTNode for ElifBlock {
         "Elif".to_string()
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let condition = self.condition.check(checker);
+        checker.condition(condition);
+        let content = checker.check_scope(&self.content);
+        match &self.next_conditional {
+            Some(next) if next.check(checker) == content => content,
+            _ => Type::Any,
+        }
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
         self.content.children()
     }
 
@@ -238,10 +253,5 @@ impl ASTNode for ElifBlock {
         }
         result
     }
-
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let content = self.content.clone();
-        Box::new(ElifBlock::new(self.condition.clone_to_node(), content, self.next_conditional.as_ref().map(|c| c.clone_conditional())))
-    }
 }
 
@@ -0,0 +1,241 @@
+use crate::ast::binary_operation::BinaryOperationError;
+use crate::ast::literal::Literal;
+use crate::ast::scope::Scope;
+use crate::ast::unary_operation::UnaryOperationError;
+use crate::ast::type_node::Type;
+use crate::ast::{ASTNode, Value};
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::lexer::token::Operator;
+use crate::Error;
+use crate::checker::checker::TypeChecker;
+use crate::ast::Node;
+use std::rc::Rc;
+
+/// `error Kind(payload)`, or `error value` which re-raises an error value
+/// and otherwise raises it as the payload of an `Error`.
+#[derive(Debug)]
+pub struct Raise {
+    pub kind: Option<String>,
+    pub payload: Option<Rc<Node>>,
+}
+
+/// One arm of an `except` block. A handler without a kind catches every error.
+#[derive(Debug, Clone)]
+pub struct Handler {
+    pub kind: Option<String>,
+    pub binding: Option<String>,
+    pub body: Scope,
+}
+
+#[derive(Debug, Clone)]
+pub struct Except {
+    pub body: Scope,
+    pub handlers: Vec<Handler>,
+}
+
+/// The kind and payload a Loop `except` sees for an error, if it can be caught.
+pub fn catchable(error: &Error) -> Option<(String, Value)> {
+    match error {
+        Error::RuntimeError(RuntimeError::Raised(kind, payload)) => Some((kind.clone(), payload.clone())),
+        Error::RuntimeError(e) => Some((runtime_kind(e).to_string(), runtime_payload(e))),
+        Error::TupleError(e) => Some(("TupleError".to_string(), text(e))),
+        Error::TypeError(e) => Some(("TypeError".to_string(), text(e))),
+        _ => None,
+    }
+}
+
+fn runtime_kind(error: &RuntimeError) -> &'static str {
+    match error {
+        RuntimeError::VariableNotFound(_) => "VariableNotFound",
+        RuntimeError::VariableNotInitialized(_) => "VariableNotInitialized",
+        RuntimeError::ValueOutOfBounds(..) => "ValueOutOfBounds",
+        RuntimeError::ValueNotOfType(..) => "ValueNotOfType",
+        RuntimeError::TupleLengthMismatch(..) => "TupleLengthMismatch",
+        RuntimeError::CannotAssignToImmutableVariable(_) => "CannotAssignToImmutableVariable",
+        RuntimeError::CannotInferType(_) => "CannotInferType",
+        RuntimeError::TypeNotImplemented(_) => "TypeNotImplemented",
+        RuntimeError::ValueNotOfTupleType(..) => "ValueNotOfTupleType",
+        RuntimeError::BinaryOperationError(_) => "BinaryOperationError",
+        RuntimeError::AssignmentError(_) => "AssignmentError",
+        RuntimeError::NoVariableAtHeapIndex(_) => "NoVariableAtHeapIndex",
+        RuntimeError::FunctionNotFound(_) => "FunctionNotFound",
+        RuntimeError::InvalidFunctionCall => "InvalidFunctionCall",
+        RuntimeError::BorrowMismatch(..) => "BorrowMismatch",
+        RuntimeError::CannotDeclareAnonymousFunction => "CannotDeclareAnonymousFunction",
+        RuntimeError::UnaryOperationError(_) => "UnaryOperationError",
+        RuntimeError::ModuleNotFound(_) => "ModuleNotFound",
+        RuntimeError::ModuleMemberNotFound(..) => "ModuleMemberNotFound",
+        RuntimeError::UnsupportedImportScheme(_) => "UnsupportedImportScheme",
+        RuntimeError::ArgumentCountMismatch(..) => "ArgumentCountMismatch",
+        RuntimeError::UnwrapNone(_) => "UnwrapNone",
+        RuntimeError::Raised(..) => "Error",
+        RuntimeError::TaskNotFound(_) => "TaskNotFound",
+        RuntimeError::TaskAwaitsItself(_) => "TaskAwaitsItself",
+        RuntimeError::ChannelNotFound(_) => "ChannelNotFound",
+        RuntimeError::ChannelEmpty(_) => "ChannelEmpty",
+        RuntimeError::GenericBindingConflict(..) => "GenericBindingConflict",
+        RuntimeError::IntegerOverflow(..) => "IntegerOverflow",
+        RuntimeError::DivisionByZero(..) => "DivisionByZero",
+        RuntimeError::InvalidShift(..) => "InvalidShift",
+        RuntimeError::NegativeExponent(..) => "NegativeExponent",
+        RuntimeError::NegativeRepetition(..) => "NegativeRepetition",
+        RuntimeError::NonFiniteFloat(..) => "NonFiniteFloat",
+        RuntimeError::InvalidCast(..) => "InvalidCast",
+        RuntimeError::LossyCast(..) => "LossyCast",
+        RuntimeError::IndexOutOfBounds(..) => "IndexOutOfBounds",
+        RuntimeError::KeyNotFound(_) => "KeyNotFound",
+        RuntimeError::UnhashableKey(_) => "UnhashableKey",
+        RuntimeError::UnhashableType(_) => "UnhashableType",
+        RuntimeError::StackOverflow(_) => "StackOverflow",
+        RuntimeError::ConditionNotBool(_) => "ConditionNotBool",
+        RuntimeError::TemporaryReceiver(_) => "TemporaryReceiver",
+    }
+}
+
+/// Names are strings, faults in operations a tuple of the operator and its
+/// operands, and the values an error is about are themselves.
+fn runtime_payload(error: &RuntimeError) -> Value {
+    match error {
+        RuntimeError::VariableNotFound(name)
+        | RuntimeError::VariableNotInitialized(name)
+        | RuntimeError::CannotAssignToImmutableVariable(name)
+        | RuntimeError::FunctionNotFound(name)
+        | RuntimeError::ModuleNotFound(name)
+        | RuntimeError::UnsupportedImportScheme(name)
+        | RuntimeError::UnwrapNone(name)
+        | RuntimeError::StackOverflow(name)
+        | RuntimeError::TemporaryReceiver(name) => text(name),
+        RuntimeError::ValueOutOfBounds(value, type_) | RuntimeError::ValueNotOfType(value, type_) => {
+            Value::Tuple(vec![text(value), text(type_)])
+        }
+        RuntimeError::CannotInferType(value) => text(value),
+        RuntimeError::ValueNotOfTupleType(value, types) => Value::Tuple(vec![text(value), text(Type::Tuple(types.clone()))]),
+        RuntimeError::TypeNotImplemented(type_) | RuntimeError::UnhashableType(type_) => text(type_),
+        RuntimeError::TupleLengthMismatch(expected, found) => Value::Tuple(vec![count(*expected), count(*found)]),
+        RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator, left, right)) => {
+            operation(operator, &[left.clone(), right.clone()])
+        }
+        RuntimeError::BinaryOperationError(BinaryOperationError::NoneOperand(operator)) => text(operator),
+        RuntimeError::UnaryOperationError(UnaryOperationError::CannotPerform(operator, operand)) => operation(operator, std::slice::from_ref(operand)),
+        RuntimeError::AssignmentError(e) => text(e),
+        RuntimeError::NoVariableAtHeapIndex(index) => count(*index),
+        RuntimeError::InvalidFunctionCall | RuntimeError::CannotDeclareAnonymousFunction => Value::None,
+        RuntimeError::BorrowMismatch(function, param) => Value::Tuple(vec![text(function), text(param)]),
+        RuntimeError::ModuleMemberNotFound(module, member) => Value::Tuple(vec![text(module), text(member)]),
+        RuntimeError::ArgumentCountMismatch(function, expected, found) => Value::Tuple(vec![text(function), count(*expected), count(*found)]),
+        RuntimeError::Raised(_, payload) => payload.clone(),
+        RuntimeError::TaskNotFound(id) | RuntimeError::TaskAwaitsItself(id) => Value::Task(*id),
+        RuntimeError::ChannelNotFound(id) | RuntimeError::ChannelEmpty(id) => Value::Channel(*id),
+        RuntimeError::GenericBindingConflict(function, variable, first, second) => {
+            Value::Tuple(vec![text(function), text(variable), text(first), text(second)])
+        }
+        RuntimeError::IntegerOverflow(operator, operands, _)
+        | RuntimeError::DivisionByZero(operator, operands)
+        | RuntimeError::InvalidShift(operator, operands, _)
+        | RuntimeError::NegativeExponent(operator, operands)
+        | RuntimeError::NegativeRepetition(operator, operands)
+        | RuntimeError::NonFiniteFloat(operator, operands) => operation(operator, operands),
+        RuntimeError::InvalidCast(value, _) | RuntimeError::LossyCast(value, _) => value.clone(),
+        RuntimeError::IndexOutOfBounds(index, length) => Value::Tuple(vec![Literal::int(*index), count(*length)]),
+        RuntimeError::KeyNotFound(value) | RuntimeError::UnhashableKey(value) | RuntimeError::ConditionNotBool(value) => value.clone(),
+    }
+}
+
+fn text(value: impl std::fmt::Display) -> Value {
+    Value::String(value.to_string(), false)
+}
+
+fn count(n: usize) -> Value {
+    Value::Int(n as i64)
+}
+
+/// `('/', 1, 0)` for `1 / 0`.
+fn operation(operator: &Operator, operands: &[Value]) -> Value {
+    let mut values = vec![text(operator)];
+    values.extend(operands.iter().cloned());
+    Value::Tuple(values)
+}
+
+impl ASTNode for Raise {
+    fn element(&self) -> String {
+        format!("error {}", self.kind.clone().unwrap_or_default())
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        self.payload.iter().cloned().collect()
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let payload = match &self.payload {
+            Some(payload) => payload.eval(env)?,
+            None => Value::None,
+        };
+        let (kind, payload) = match (&self.kind, payload) {
+            (Some(kind), payload) => (kind.clone(), payload),
+            (None, Value::Error(kind, payload)) => (kind, *payload),
+            (None, payload) => ("Error".to_string(), payload),
+        };
+        Err(Error::RuntimeError(RuntimeError::Raised(kind, payload)))
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        if let Some(payload) = &self.payload {
+            payload.check(checker);
+        }
+        Type::Any
+    }
+}
+
+impl ASTNode for Except {
+    fn element(&self) -> String {
+        format!("except {}", self.handlers.iter().map(|h| h.kind.clone().unwrap_or("error".to_string())).collect::<Vec<String>>().join(", "))
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        self.body.children()
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let error = match self.body.eval(env) {
+            Err(error) => error,
+            result => return result,
+        };
+        let (kind, payload) = match catchable(&error) {
+            Some(caught) => caught,
+            None => return Err(error),
+        };
+        let handler = match self.handlers.iter().find(|h| h.kind.as_ref().is_none_or(|k| *k == kind)) {
+            Some(handler) => handler,
+            None => return Err(error),
+        };
+        // the error is handled, so the calls it left are not part of the next one's trace
+        env.take_trace();
+        let mut handler_env = env.new_child();
+        if let Some(binding) = &handler.binding {
+            // a handler for a specific kind only needs the payload
+            let value = match handler.kind {
+                Some(_) => payload,
+                None => Value::Error(kind, Box::new(payload)),
+            };
+            handler_env.declare_assign(binding.clone(), value, false, Some(Type::Any))?;
+        }
+        let result = handler.body.eval(&mut handler_env);
+        handler_env.free();
+        result
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let body = checker.check_scope(&self.body);
+        let mut same = true;
+        for handler in &self.handlers {
+            let type_ = checker.scoped(|checker| {
+                if let Some(binding) = &handler.binding {
+                    checker.declare(binding, Type::Any, false);
+                }
+                checker.check_scope(&handler.body)
+            });
+            same = same && type_ == body;
+        }
+        if same { body } else { Type::Any }
+    }
+}
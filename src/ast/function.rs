@@ -1,38 +1,59 @@
 use crate::ast::{ASTNode, tuple::Clonable, scope::Scope, value::Value};
 use crate::environment::environment::{Environment, ReferenceOrValue, RuntimeError};
+use crate::environment::frame::Address;
+use crate::environment::function::TailCall;
+use crate::environment::variable::Variable;
+use crate::environment::list::bind;
+use crate::environment::{list, map, string};
+use crate::lexer::Position;
 use crate::Error;
 use std::collections::HashMap;
 use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::{Node, NodeKind};
+use std::cell::Cell;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct FnDeclaration {
     pub name: Option<String>,
-    pub params: HashMap<String, Type>,
+    pub params: Vec<(String, Type)>,
+    pub references: Vec<String>,
     pub return_type: Option<Type>,
-    pub body: Scope,
+    /// Shared with the `Value::Fn` of every declaration of the function.
+    pub body: Rc<Scope>,
 }
 
 impl FnDeclaration {
     pub fn signature(&self) -> FnSignature {
-        FnSignature { params: self.params.clone(), return_type: self.return_type.clone() }
+        FnSignature { params: self.params.clone(), references: self.references.clone(), return_type: self.return_type.clone() }
     }
 
     pub fn from_signature(name: Option<String>, signature: FnSignature, body: Scope) -> Self {
-        Self { name, params: signature.params, return_type: signature.return_type, body }
+        Self { name, params: signature.params, references: signature.references, return_type: signature.return_type, body: Rc::new(body) }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct FnSignature {
-    pub params: HashMap<String, Type>,
+    pub params: Vec<(String, Type)>,
+    /// The `mut` params, which take a variable of the caller and assign to it.
+    pub references: Vec<String>,
     pub return_type: Option<Type>,
 }
 
+impl FnSignature {
+    pub fn takes_reference(&self, param: &str) -> bool {
+        self.references.iter().any(|reference| reference == param)
+    }
+}
+
 impl Clonable for FnDeclaration {
     fn clone_element(&self) -> Self {
         Self {
             name: self.name.clone().map(|n| n.clone()),
             params: self.params.clone(),
+            references: self.references.clone(),
             return_type: self.return_type.clone(),
             body: self.body.clone(),
         }
@@ -41,100 +62,350 @@ impl Clonable for FnDeclaration {
 
 impl ASTNode for FnDeclaration {
     fn element(&self) -> String {
-        format!("fn {} ({}) -> {:?}", self.name.clone().unwrap_or("".to_string()), self.params.keys().map(|k| 
-            format!("{}: {:?}", k, self.params.get(k).unwrap())
+        format!("fn {} ({}) -> {:?}", self.name.clone().unwrap_or("".to_string()), self.params.iter().map(|(k, t)| 
+            format!("{}{}: {:?}", if self.references.contains(k) {"mut "} else {""}, k, t)
         ).collect::<Vec<String>>().join(", "), self.return_type)
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         self.body.children()
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(self.clone_element())
-    }
-
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
         env.declare_function(self.clone_element())?;
         Ok(Value::Bool(true))
     }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let signature = self.signature();
+        let name = self.name.clone().unwrap_or_default();
+        // declared first so that the body can call itself
+        checker.declare(&name, Type::FnType(Box::new(signature.clone())), false);
+        checker.check_function(&name, &signature, &self.body);
+        Type::Bool
+    }
+}
+
+
+
+/// An argument passed as `mut x`, which lets the function assign to `x`.
+#[derive(Debug, Clone)]
+pub struct Borrow {
+    pub name: String,
+}
+
+impl Clonable for Borrow {
+    fn clone_element(&self) -> Self {
+        self.clone()
+    }
 }
 
+impl ASTNode for Borrow {
+    fn element(&self) -> String {
+        format!("mut {}", self.name)
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        env.lookup(&self.name)
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        checker.borrow(&self.name)
+    }
 
+    fn get_reference(&self, env: &mut Environment) -> Result<ReferenceOrValue, Error> {
+        let variable = env.get_variable(&self.name)?;
+        if !variable.mutable {
+            return Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(self.name.clone())));
+        }
+        Ok(ReferenceOrValue::Borrow(variable, self.name.clone()))
+    }
+}
+
+/// Call arguments in source order, each with its param name when given.
+pub type CallArgs = Vec<(Option<String>, Rc<Node>)>;
 
 #[derive(Debug)]
 pub struct FnCall {
     pub name: String,
-    pub params: HashMap<Option<String>, Box<dyn ASTNode>>
+    pub params: CallArgs,
+    /// Set by the resolver when the call is the value of the function it is in.
+    pub tail: Cell<bool>,
+    pub position: Option<Position>,
+    /// Set by the resolver, like `Identifier::address`.
+    pub address: Cell<Option<Address>>,
 }
 
 impl Clonable for FnCall {
     fn clone_element(&self) -> Self {
         Self {
             name: self.name.clone(),
-            params: self.params.iter().map(|(k, v)| (k.clone(), v.clone_to_node())).collect(),
+            params: self.params.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            tail: self.tail.clone(),
+            position: self.position,
+            address: self.address.clone(),
+        }
+    }
+}
+
+/// The kinds of values that have methods.
+pub(crate) enum Receiver {
+    List,
+    Map,
+    String,
+}
+
+impl Receiver {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::List(_) => Some(Receiver::List),
+            Value::Map(_) => Some(Receiver::Map),
+            Value::String(..) => Some(Receiver::String),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Receiver::List => "list",
+            Receiver::Map => "map",
+            Receiver::String => "string",
+        }
+    }
+
+    fn method_params(&self, method: &str) -> Option<&'static [&'static str]> {
+        match self {
+            Receiver::List => list::method_params(method),
+            Receiver::Map => map::method_params(method),
+            Receiver::String => string::method_params(method),
+        }
+    }
+
+    fn mutates(&self, method: &str) -> bool {
+        match self {
+            Receiver::List => list::mutates(method),
+            Receiver::Map => map::mutates(method),
+            Receiver::String => false,
         }
     }
 }
 
 impl ASTNode for FnCall {
     fn element(&self) -> String {
-        format!("{} ({})", self.name, self.params.keys().map(|k| 
+        format!("{} ({})", self.name, self.params.iter().map(|(k, _)| 
             format!("{}", if k.is_some() {k.clone().unwrap()} else {"<>".to_string()})
         ).collect::<Vec<String>>().join(", "))
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
-        self.params.values().map(|p| p.clone_to_node()).collect()
+    fn children(&self) -> Vec<Rc<Node>> {
+        self.params.iter().map(|(_, p)| p.clone()).collect()
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(self.clone_element())
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        self.call(env, |env, i| self.params[i].1.get_reference(env))
     }
 
-    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
-        let mut references = HashMap::new();
-        let func = match env.get_variable(&self.name)?.type_ {
-            Type::FnType(sig) => *sig,
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let args = self.params.iter().map(|(param, value)| (param.clone(), value.check(checker))).collect();
+        let borrows = self.params.iter().map(|(_, value)| matches!(&value.kind, NodeKind::Borrow(_))).collect();
+        checker.call(&self.name, args, borrows)
+    }
+}
+
+impl FnCall {
+    /// The variable, method and kind of receiver of a method call like
+    /// `xs.push(1)`, which has no function by its name.
+    fn method_call(&self, env: &mut Environment) -> Option<(String, String, Receiver)> {
+        let (target, method) = self.name.rsplit_once('.')?;
+        let receiver = env.inspect(target, None, Receiver::of);
+        Some((target.to_string(), method.to_string(), receiver.ok()??))
+    }
+
+    /// Makes the call, getting the argument at each index from `arg` once
+    /// the function is found.
+    pub fn call(&self, env: &mut Environment, mut arg: impl FnMut(&mut Environment, usize) -> Result<ReferenceOrValue, Error>) -> Result<Value, Error> {
+        let mut callee = self.callee(env, None)?;
+        for i in 0..self.params.len() {
+            let value = arg(env, i)?;
+            self.pass(&mut callee, env, i, value)?;
+        }
+        self.finish(env, callee)
+    }
+
+    /// Finds what the call is made to, `function` when it is already known.
+    /// The function is looked up once, and the call made with what is found.
+    pub(crate) fn callee(&self, env: &mut Environment, function: Option<Variable>) -> Result<Callee, Error> {
+        let function = match function.map_or_else(|| env.variable_at(&self.name, self.address.get()), Ok) {
+            Ok(function) => function,
+            Err(e) => {
+                let (target, method, receiver) = match self.method_call(env) {
+                    Some(method_call) => method_call,
+                    None => return Err(e),
+                };
+                return Ok(Callee::Method { target, method, receiver, args: Vec::new() });
+            }
+        };
+        let params = match &function.type_ {
+            Type::FnType(signature) => signature.params.len(),
             _ => return Err(Error::RuntimeError(RuntimeError::FunctionNotFound(self.name.clone())))
         };
-        for (param, value) in self.params.iter() {
-            let value = value.get_reference(env)?;
-            let param = match param {
-                Some(p) => p,
-                None => {
-                    if func.params.keys().len() == 1 {
-                        let params: Vec<&String> = func.params.keys().collect();
-                        params[0]
-                    } else {
-                        let param = match &value {
-                            ReferenceOrValue::Reference(_, name) => {
-                                let mut param = None;
-                                for p in func.params.keys() {
-                                    if name.starts_with(p) {
-                                        if param.is_none() {
-                                            param = Some(p);
-                                        } else {
-                                            return Err(Error::RuntimeError(RuntimeError::InvalidFunctionCall));
-                                        }
+        // Fully positional calls bind arguments in the order of the params.
+        let positional = self.params.len() == params && self.params.iter().all(|(name, _)| name.is_none());
+        Ok(Callee::Function { function, positional, args: HashMap::new() })
+    }
+
+    /// Passes `value` as the argument at index `i`.
+    pub(crate) fn pass(&self, callee: &mut Callee, env: &mut Environment, i: usize, value: ReferenceOrValue) -> Result<(), Error> {
+        let (func, positional, references) = match callee {
+            Callee::Function { function: Variable { type_: Type::FnType(signature), .. }, positional, args } => (signature, *positional, args),
+            Callee::Function { .. } => return Err(Error::RuntimeError(RuntimeError::FunctionNotFound(self.name.clone()))),
+            Callee::Method { args, .. } => {
+                args.push((self.params[i].0.clone(), value.eval(env)?));
+                return Ok(());
+            }
+        };
+        let param = match &self.params[i].0 {
+            Some(p) => p,
+            None => {
+                if positional {
+                    &func.params[i].0
+                } else if func.params.len() == 1 {
+                    &func.params[0].0
+                } else {
+                    let param = match &value {
+                        ReferenceOrValue::Reference(_, name) | ReferenceOrValue::Borrow(_, name) => {
+                            let mut param = None;
+                            for (p, _) in func.params.iter() {
+                                if name.starts_with(p) {
+                                    if param.is_none() {
+                                        param = Some(p);
+                                    } else {
+                                        return Err(Error::RuntimeError(RuntimeError::InvalidFunctionCall));
                                     }
                                 }
-                                if param.is_none() {
-                                    return Err(Error::RuntimeError(RuntimeError::InvalidFunctionCall));
-                                }
-                                param.unwrap()
                             }
-                            _ => {
-                                return Err(Error::RuntimeError(RuntimeError::InvalidFunctionCall))
+                            if param.is_none() {
+                                return Err(Error::RuntimeError(RuntimeError::InvalidFunctionCall));
                             }
-                        };
-                        param
-                    }
+                            param.unwrap()
+                        }
+                        _ => {
+                            return Err(Error::RuntimeError(RuntimeError::InvalidFunctionCall))
+                        }
+                    };
+                    param
                 }
+            }
+        };
+        references.insert(param.clone(), value);
+        Ok(())
+    }
+
+    /// Makes the call with the arguments passed to `callee`.
+    pub(crate) fn finish(&self, env: &mut Environment, callee: Callee) -> Result<Value, Error> {
+        let (function, references) = match callee {
+            Callee::Function { function, args, .. } => (function, args),
+            Callee::Method { target, method, receiver, args } => {
+                return match receiver {
+                    Receiver::List => env.call_list_method(&target, &method, args),
+                    Receiver::Map => env.call_map_method(&target, &method, args),
+                    Receiver::String => env.call_string_method(&target, &method, args),
+                };
+            }
+        };
+        if self.tail.get() {
+            // the variables passed are read now, as the frames they are in end with the call
+            let mut args = HashMap::new();
+            for (param, arg) in references {
+                let arg = match arg {
+                    ReferenceOrValue::Reference(..) => ReferenceOrValue::Value(arg.eval(env)?),
+                    arg => arg,
+                };
+                args.insert(param, arg);
+            }
+            let call = TailCall {
+                name: self.name.clone(),
+                address: self.address.get(),
+                args,
+                function,
+                env: env.clone(),
+                return_type: None,
+                position: self.position,
             };
-            references.insert(param.clone(), value);
+            return Err(Error::TailCall(Box::new(call)));
+        }
+        env.call_at(&self.name, function, references, self.position)
+    }
+}
+
+/// What a call is made to, and the arguments passed to it so far.
+pub(crate) enum Callee {
+    Function { function: Variable, positional: bool, args: HashMap<String, ReferenceOrValue> },
+    Method { target: String, method: String, receiver: Receiver, args: Vec<(Option<String>, Value)> },
+}
+
+/// A call of a method of a value that is not in a variable, like
+/// `'abc'.upper()` or `xs[0].len()`. Calls on a variable are `FnCall`s.
+#[derive(Debug)]
+pub struct MethodCall {
+    pub receiver: Rc<Node>,
+    pub method: String,
+    pub params: CallArgs,
+}
+
+impl ASTNode for MethodCall {
+    fn element(&self) -> String {
+        format!(".{} ({})", self.method, self.params.iter().map(|(k, _)|
+            k.clone().unwrap_or("<>".to_string())
+        ).collect::<Vec<String>>().join(", "))
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        let mut children = vec![self.receiver.clone()];
+        children.extend(self.params.iter().map(|(_, p)| p.clone()));
+        children
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let value = self.receiver.eval(env)?;
+        let mut args = Vec::new();
+        for (param, arg) in self.params.iter() {
+            args.push((param.clone(), arg.eval(env)?));
         }
-        let result = env.call(&self.name, references)?;
-        Ok(result)
+        self.apply(value, args)
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let receiver = self.receiver.check(checker);
+        let args = self.params.iter().map(|(param, value)| (param.clone(), value.check(checker))).collect();
+        let borrows = self.params.iter().map(|(_, value)| matches!(&value.kind, NodeKind::Borrow(_))).collect();
+        checker.method(None, &self.method, receiver, args, borrows)
     }
-}
\ No newline at end of file
+}
+
+impl MethodCall {
+    /// Calls the method on `value` with the arguments evaluated.
+    pub fn apply(&self, value: Value, args: Vec<(Option<String>, Value)>) -> Result<Value, Error> {
+        let receiver = match Receiver::of(&value) {
+            Some(receiver) => receiver,
+            None => return Err(Error::RuntimeError(RuntimeError::FunctionNotFound(format!("{}.{}", value, self.method)))),
+        };
+        let call = format!("{}.{}", receiver.name(), self.method);
+        let params = receiver.method_params(&self.method).ok_or_else(|| Error::RuntimeError(RuntimeError::FunctionNotFound(call.clone())))?;
+        let args = bind(&call, params, args)?;
+        // the change would be lost with the value
+        if receiver.mutates(&self.method) {
+            return Err(Error::RuntimeError(RuntimeError::TemporaryReceiver(call)));
+        }
+        match value {
+            Value::List(mut values) => list::apply_method(&mut values, Type::Any, &self.method, args),
+            Value::Map(mut map) => map::apply_method(&mut map, Type::Any, Type::Any, &self.method, args),
+            Value::String(s, raw) => string::apply_method(&s, raw, &self.method, args),
+            _ => Err(Error::RuntimeError(RuntimeError::FunctionNotFound(call))),
+        }
+    }
+}
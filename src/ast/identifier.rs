@@ -1,19 +1,29 @@
 use crate::ast::tuple::Clonable;
 use crate::ast::{ASTNode,Value, ASTError};
 use crate::environment::environment::{Environment, ReferenceOrValue};
+use crate::environment::frame::Address;
 use crate::lexer::token;
 use crate::Error;
+use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::Node;
+use std::cell::Cell;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
-pub struct Identifier(String);
+pub struct Identifier {
+    pub name: String,
+    /// Set by the resolver when the variable is declared in an enclosing block.
+    pub address: Cell<Option<Address>>,
+}
 
 impl Identifier {
 
     pub fn from_token(token: token::Token) -> Result<Self, Error> {
-        Ok(Self(match token {
-            token::Token::Identifier(name) => name,
-            _ => return Err(Error::ASTError(ASTError::InvalidIdentifierToken(token))),
-        }))
+        match token {
+            token::Token::Identifier(name) => Ok(Self { name, address: Cell::default() }),
+            _ => Err(Error::ASTError(ASTError::InvalidIdentifierToken(token))),
+        }
     }
 }
 
@@ -25,22 +35,22 @@ impl Clonable for Identifier {
 
 impl ASTNode for Identifier {
     fn element(&self) -> String {
-        self.0.clone()
+        self.name.clone()
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         vec![]
     }
 
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
-        env.lookup(&self.0)
+        env.lookup_at(&self.name, self.address.get())
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(Identifier(self.0.clone()))
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        checker.lookup(&self.name)
     }
 
     fn get_reference(&self, env: &mut Environment) -> Result<ReferenceOrValue, Error> {
-        ReferenceOrValue::from_reference(env, &self.0)
+        ReferenceOrValue::from_reference(env, &self.name)
     }
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+use crate::ast::{ASTNode, Value};
+use crate::environment::environment::Environment;
+use crate::Error;
+use crate::ast::Node;
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub path: String,
+    pub alias: Option<String>,
+    pub names: Option<Vec<String>>,
+}
+
+impl ASTNode for Import {
+    fn element(&self) -> String {
+        match (&self.names, &self.alias) {
+            (Some(names), _) => format!("from '{}' import {}", self.path, names.join(", ")),
+            (None, Some(alias)) => format!("import '{}' as {}", self.path, alias),
+            (None, None) => format!("import '{}'", self.path),
+        }
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        env.import(&self.path, self.alias.as_deref(), self.names.as_deref())?;
+        Ok(Value::Bool(true))
+    }
+}
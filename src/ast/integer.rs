@@ -0,0 +1,205 @@
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::RuntimeError;
+use crate::lexer::token::Operator;
+use crate::ast::binary_operation::BinaryOperationError;
+use crate::Error;
+
+// Integers of a declared width are `Value::TypedInt`, kept in an `i128` that
+// holds every `i64` and `u64` and checked against the range of their type
+// after each operation. Integer literals are untyped `Value::Int`s until they
+// are stored, and take the type of the other operand in an operation.
+
+/// The smallest and largest value of an integer type.
+pub fn range(type_: &Type) -> Option<(i128, i128)> {
+    match type_ {
+        Type::U8 => Some((0, u8::MAX as i128)),
+        Type::U16 => Some((0, u16::MAX as i128)),
+        Type::U32 => Some((0, u32::MAX as i128)),
+        Type::U64 => Some((0, u64::MAX as i128)),
+        Type::I16 => Some((i16::MIN as i128, i16::MAX as i128)),
+        Type::I32 => Some((i32::MIN as i128, i32::MAX as i128)),
+        Type::I64 => Some((i64::MIN as i128, i64::MAX as i128)),
+        _ => None,
+    }
+}
+
+pub fn fits(value: i128, type_: &Type) -> bool {
+    matches!(range(type_), Some((min, max)) if value >= min && value <= max)
+}
+
+fn bits(type_: &Type) -> u32 {
+    match type_ {
+        Type::U8 => 8,
+        Type::U16 | Type::I16 => 16,
+        Type::U32 | Type::I32 => 32,
+        _ => 64,
+    }
+}
+
+fn signed(type_: &Type) -> bool {
+    matches!(type_, Type::I16 | Type::I32 | Type::I64)
+}
+
+/// The type two integers of different types are converted to: the wider of
+/// the two when both are signed or both unsigned, otherwise the smallest
+/// signed type that holds both.
+pub fn promote(a: &Type, b: &Type) -> Type {
+    if a == b {
+        return a.clone();
+    }
+    let (wide, narrow) = if bits(a) >= bits(b) { (a, b) } else { (b, a) };
+    if signed(a) == signed(b) || (signed(wide) && bits(wide) > bits(narrow)) {
+        return wide.clone();
+    }
+    match bits(wide) {
+        8 => Type::I16,
+        16 => Type::I32,
+        _ => Type::I64,
+    }
+}
+
+/// Truncates `value` to the bits of `type_`, the way fixed-width integers wrap around.
+pub fn wrap(value: i128, type_: &Type) -> i128 {
+    let bits = bits(type_);
+    let truncated = value & ((1i128 << bits) - 1);
+    if signed(type_) && truncated >= 1i128 << (bits - 1) {
+        truncated - (1i128 << bits)
+    } else {
+        truncated
+    }
+}
+
+/// Clamps `value` to the range of `type_`.
+pub fn saturate(value: i128, type_: &Type) -> i128 {
+    match range(type_) {
+        Some((min, max)) => value.clamp(min, max),
+        None => value,
+    }
+}
+
+/// The integer and type of an integer value. Untyped integers have no type.
+pub fn as_integer(value: &Value) -> Option<(i128, Option<Type>)> {
+    match value {
+        Value::Int(i) => Some((*i as i128, None)),
+        Value::TypedInt(i, type_) => Some((*i, Some(type_.clone()))),
+        _ => None,
+    }
+}
+
+pub fn from_integer(value: i128, type_: Option<Type>) -> Value {
+    match type_ {
+        Some(type_) => Value::TypedInt(value, type_),
+        None => Value::Int(value as i64),
+    }
+}
+
+/// The operands of an operation between two integers, converted to their
+/// common type. Two untyped integers are computed as `i64` and stay untyped.
+pub fn operands(left: &Value, right: &Value) -> Result<Option<(i128, i128, Option<Type>)>, Error> {
+    let ((l, left_type), (r, right_type)) = match (as_integer(left), as_integer(right)) {
+        (Some(l), Some(r)) => (l, r),
+        _ => return Ok(None),
+    };
+    let type_ = match (left_type, right_type) {
+        (Some(a), Some(b)) => Some(promote(&a, &b)),
+        (Some(type_), None) | (None, Some(type_)) => Some(type_),
+        (None, None) => None,
+    };
+    if let Some(type_) = &type_ {
+        for (value, operand) in [(l, left), (r, right)] {
+            if !fits(value, type_) {
+                return Err(Error::RuntimeError(RuntimeError::ValueOutOfBounds(operand.to_string(), type_.clone())));
+            }
+        }
+    }
+    Ok(Some((l, r, type_)))
+}
+
+/// `left operator right` between two integers, failing with `IntegerOverflow`
+/// when the result does not fit their type.
+pub fn binary(operator: &Operator, left: &Value, right: &Value) -> Result<Value, Error> {
+    let (l, r, type_) = match operands(left, right)? {
+        Some(operands) => operands,
+        None => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left.clone(), right.clone())))),
+    };
+    let bounds = type_.clone().unwrap_or(Type::I64);
+    let operands = || vec![left.clone(), right.clone()];
+    let shift = match operator {
+        Operator::BitShiftLeft | Operator::BitShiftRight => match u32::try_from(r).ok().filter(|r| *r < bits(&bounds)) {
+            Some(shift) => shift,
+            None => return Err(Error::RuntimeError(RuntimeError::InvalidShift(operator.clone(), operands(), bounds))),
+        },
+        _ => 0,
+    };
+    match operator {
+        Operator::Div | Operator::Mod if r == 0 => return Err(Error::RuntimeError(RuntimeError::DivisionByZero(operator.clone(), operands()))),
+        Operator::Pow if r < 0 => return Err(Error::RuntimeError(RuntimeError::NegativeExponent(operator.clone(), operands()))),
+        _ => {}
+    }
+    let result = match operator {
+        Operator::Gt => return Ok(Value::Bool(l > r)),
+        Operator::Lt => return Ok(Value::Bool(l < r)),
+        Operator::Gte => return Ok(Value::Bool(l >= r)),
+        Operator::Lte => return Ok(Value::Bool(l <= r)),
+        Operator::Eq => return Ok(Value::Bool(l == r)),
+        Operator::Neq => return Ok(Value::Bool(l != r)),
+        Operator::Add => l.checked_add(r),
+        Operator::Sub => l.checked_sub(r),
+        Operator::Mul => l.checked_mul(r),
+        Operator::Div => l.checked_div(r),
+        Operator::Mod => l.checked_rem(r),
+        Operator::Pow => u32::try_from(r).ok().and_then(|r| l.checked_pow(r)),
+        // bits shifted out are dropped, as with fixed-width integers
+        Operator::BitShiftLeft => Some(wrap(l << shift, &bounds)),
+        Operator::BitShiftRight => Some(l >> shift),
+        Operator::BitAnd => Some(l & r),
+        Operator::BitOr => Some(l | r),
+        Operator::BitXor => Some(l ^ r),
+        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), left.clone(), right.clone())))),
+    };
+    match result.filter(|result| fits(*result, &bounds)) {
+        Some(result) => Ok(from_integer(result, type_)),
+        None => Err(Error::RuntimeError(RuntimeError::IntegerOverflow(operator.clone(), operands(), bounds))),
+    }
+}
+
+/// `operator operand` on an integer.
+pub fn unary(operator: &Operator, operand: &Value) -> Option<Result<Value, Error>> {
+    let (value, type_) = as_integer(operand)?;
+    let bounds = type_.clone().unwrap_or(Type::I64);
+    let result = match operator {
+        Operator::Sub => -value,
+        Operator::BitNot if signed(&bounds) => !value,
+        Operator::BitNot => range(&bounds).map(|(_, max)| max - value)?,
+        _ => return None,
+    };
+    Some(if fits(result, &bounds) {
+        Ok(from_integer(result, type_))
+    } else {
+        Err(Error::RuntimeError(RuntimeError::IntegerOverflow(operator.clone(), vec![operand.clone()], bounds)))
+    })
+}
+
+/// `a operator b` wrapping around or saturating at the bounds of the
+/// operands' type instead of failing, for `Add`, `Sub` and `Mul`.
+pub fn bounded(operator: &Operator, a: &Value, b: &Value, saturating: bool) -> Result<Value, Error> {
+    let (l, r, type_) = match operands(a, b)? {
+        Some(operands) => operands,
+        None => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), a.clone(), b.clone())))),
+    };
+    let bounds = type_.clone().unwrap_or(Type::I64);
+    let (exact, wrapped) = match operator {
+        Operator::Add => (l.checked_add(r), l.wrapping_add(r)),
+        Operator::Sub => (l.checked_sub(r), l.wrapping_sub(r)),
+        Operator::Mul => (l.checked_mul(r), l.wrapping_mul(r)),
+        _ => return Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::CannotPerform(operator.clone(), a.clone(), b.clone())))),
+    };
+    let result = match (exact, saturating) {
+        (Some(exact), true) => saturate(exact, &bounds),
+        // only a product of two `u64`s overflows an `i128`
+        (None, true) => if (l > 0) == (r > 0) { saturate(i128::MAX, &bounds) } else { saturate(i128::MIN, &bounds) },
+        (_, false) => wrap(wrapped, &bounds),
+    };
+    Ok(from_integer(result, type_))
+}
@@ -0,0 +1,230 @@
+use crate::ast::integer;
+use crate::ast::{ASTNode, Value};
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::Error;
+use crate::ast::type_node::Type;
+use crate::checker::checker::{assignable, TypeChecker, TypeCheckError};
+use crate::ast::{Node, NodeKind};
+use std::rc::Rc;
+
+/// `[a, b, c]`
+#[derive(Debug)]
+pub struct List {
+    pub elements: Vec<Rc<Node>>,
+}
+
+/// `list[index]`, counting from the end for a negative index, or `map[key]`.
+/// Strings are indexed by character.
+#[derive(Debug)]
+pub struct Index {
+    pub value: Rc<Node>,
+    pub index: Rc<Node>,
+}
+
+/// `list[start:end]`: a new list of the elements from `start` up to `end`.
+/// Either bound may be left out, and bounds past either end are clamped.
+/// Slicing a string gives a string.
+#[derive(Debug)]
+pub struct Slice {
+    pub value: Rc<Node>,
+    pub start: Option<Rc<Node>>,
+    pub end: Option<Rc<Node>>,
+}
+
+/// `value in list`, `key in map`, or `part in string`
+#[derive(Debug)]
+pub struct Contains {
+    pub value: Rc<Node>,
+    pub list: Rc<Node>,
+}
+
+fn items(value: Value) -> Result<Vec<Value>, Error> {
+    match value {
+        Value::List(values) => Ok(values),
+        value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::List(Box::new(Type::Any))))),
+    }
+}
+
+/// Whether `value` is in `list`, a list, map or string.
+pub fn contains(list: Value, value: Value) -> Result<Value, Error> {
+    match list {
+        Value::Map(map) => Ok(Value::Bool(map.contains(&value)?)),
+        Value::String(s, _) => match value {
+            Value::String(part, _) => Ok(Value::Bool(s.contains(&part))),
+            value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::String))),
+        },
+        list => Ok(Value::Bool(items(list)?.contains(&value))),
+    }
+}
+
+fn offset(index: &Value) -> Result<i128, Error> {
+    match integer::as_integer(index) {
+        Some((i, _)) => Ok(i),
+        None => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(index.to_string(), Type::I64))),
+    }
+}
+
+/// The position of `index` in a list of `len` elements, counting from the
+/// end when it is negative.
+pub fn position(index: &Value, len: usize) -> Result<usize, Error> {
+    let i = offset(index)?;
+    let resolved = if i < 0 { i + len as i128 } else { i };
+    if resolved < 0 || resolved >= len as i128 {
+        return Err(Error::RuntimeError(RuntimeError::IndexOutOfBounds(i, len)));
+    }
+    Ok(resolved as usize)
+}
+
+/// The element of a list, map or string at `index`.
+pub fn element(value: &Value, index: Value) -> Result<Value, Error> {
+    match value {
+        Value::Map(map) => match map.get(&index)? {
+            Some(value) => Ok(value.clone()),
+            None => Err(Error::RuntimeError(RuntimeError::KeyNotFound(index))),
+        },
+        Value::String(s, raw) => {
+            let i = position(&index, s.chars().count())?;
+            Ok(Value::String(s.chars().nth(i).map(String::from).unwrap_or_default(), *raw))
+        }
+        Value::List(values) => Ok(values[position(&index, values.len())?].clone()),
+        value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::List(Box::new(Type::Any))))),
+    }
+}
+
+/// Like `position`, but clamped to `0..=len` for the bounds of a slice.
+fn bound(index: &Value, len: usize) -> Result<usize, Error> {
+    let i = offset(index)?;
+    let resolved = if i < 0 { i + len as i128 } else { i };
+    Ok(resolved.clamp(0, len as i128) as usize)
+}
+
+impl ASTNode for List {
+    fn element(&self) -> String {
+        "[]".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        self.elements.clone()
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let mut values = Vec::new();
+        for element in &self.elements {
+            values.push(element.eval(env)?);
+        }
+        Ok(Value::List(values))
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let mut element = Type::Any;
+        for node in &self.elements {
+            let found = node.check(checker);
+            match element.join(&found) {
+                Some(joined) => element = joined,
+                None => checker.report(TypeCheckError::ListElementMismatch(element.clone(), found)),
+            }
+        }
+        Type::List(Box::new(element))
+    }
+}
+
+impl ASTNode for Index {
+    fn element(&self) -> String {
+        "index".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.value.clone(), self.index.clone()]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        // a variable is indexed where it is rather than copied whole
+        if let NodeKind::Identifier(identifier) = &self.value.kind {
+            let index = self.index.eval(env)?;
+            let element = env.inspect(&identifier.name, identifier.address.get(), |value| element(value, index));
+            return element.map_err(|e| self.value.locate(env, e))?;
+        }
+        let value = self.value.eval(env)?;
+        let index = self.index.eval(env)?;
+        element(&value, index)
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let collection = self.value.check(checker);
+        let index = self.index.check(checker);
+        checker.lookup_index(collection, index)
+    }
+}
+
+impl ASTNode for Slice {
+    fn element(&self) -> String {
+        "slice".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        let mut children = vec![self.value.clone()];
+        children.extend(self.start.iter().chain(self.end.iter()).cloned());
+        children
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let value = self.value.eval(env)?;
+        let len = match &value {
+            Value::String(s, _) => s.chars().count(),
+            Value::List(values) => values.len(),
+            value => return Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::List(Box::new(Type::Any))))),
+        };
+        let start = match &self.start {
+            Some(start) => bound(&start.eval(env)?, len)?,
+            None => 0,
+        };
+        let end = match &self.end {
+            Some(end) => bound(&end.eval(env)?, len)?.max(start),
+            None => len,
+        };
+        match value {
+            Value::String(s, raw) => Ok(Value::String(s.chars().skip(start).take(end - start).collect(), raw)),
+            value => Ok(Value::List(items(value)?[start..end].to_vec())),
+        }
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let sliced = match self.value.check(checker) {
+            Type::String => Type::String,
+            found => Type::List(Box::new(checker.element_of(found))),
+        };
+        for bound in self.start.iter().chain(self.end.iter()) {
+            let index = bound.check(checker);
+            checker.index(index);
+        }
+        sliced
+    }
+}
+
+impl ASTNode for Contains {
+    fn element(&self) -> String {
+        "in".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.value.clone(), self.list.clone()]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let value = self.value.eval(env)?;
+        contains(self.list.eval(env)?, value)
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let found = self.value.check(checker);
+        let element = match self.list.check(checker) {
+            Type::Map(key, _) => *key,
+            Type::String => Type::String,
+            list => checker.element_of(list),
+        };
+        if !assignable(&element, &found) {
+            checker.report(TypeCheckError::ListElementMismatch(element, found));
+        }
+        Type::Bool
+    }
+}
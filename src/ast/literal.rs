@@ -2,6 +2,11 @@ use crate::ast::{ASTNode,Value, ASTError};
 use crate::environment::environment::Environment;
 use crate::lexer::token;
 use crate::Error;
+use crate::ast::integer;
+use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::Node;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Literal(pub Value);
@@ -10,7 +15,7 @@ impl Literal {
 
     pub fn from_token(token: token::Token) -> Result<Self, Error> {
         Ok(Self(match token {
-            token::Token::Literal(token::Literal::Int(value)) => Value::Int(value),
+            token::Token::Literal(token::Literal::Int(value)) => Self::int(value),
             token::Token::Literal(token::Literal::Float(value)) => Value::Float(value),
             token::Token::Literal(token::Literal::String(value, raw)) => Value::String(value, raw),
             token::Token::Literal(token::Literal::Bool(value)) => Value::Bool(value),
@@ -18,6 +23,14 @@ impl Literal {
             _ => return Err(Error::ASTError(ASTError::InvalidLiteralToken(token))),
         }))
     }
+
+    /// An untyped integer, or a `u64` when it is too large for an `i64`.
+    pub fn int(value: i128) -> Value {
+        match i64::try_from(value) {
+            Ok(value) => Value::Int(value),
+            Err(_) => Value::TypedInt(value, Type::U64),
+        }
+    }
 }
 
 impl ASTNode for Literal {
@@ -25,7 +38,7 @@ impl ASTNode for Literal {
         format!("{:?}", self.0)
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         vec![]
     }
 
@@ -33,8 +46,17 @@ impl ASTNode for Literal {
         _env.interpolate(self.0.clone())
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(Literal(self.0.clone()))
+    fn check(&self, _checker: &mut TypeChecker) -> Type {
+        match self.0 {
+            Value::Int(i) if integer::fits(i as i128, &Type::I32) => Type::I32,
+            Value::Int(_) => Type::I64,
+            Value::TypedInt(_, ref type_) => type_.clone(),
+            Value::Float(_) => Type::F32,
+            Value::String(_, _) => Type::String,
+            Value::Bool(_) => Type::Bool,
+            Value::None => Type::Option(Box::new(Type::Any)),
+            _ => Type::Any,
+        }
     }
 }
 
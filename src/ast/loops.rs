@@ -1,6 +1,11 @@
 use crate::ast::{ASTNode,Value, scope::Scope};
+use crate::ast::conditional::holds;
 use crate::environment::environment::Environment;
 use crate::Error;
+use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::Node;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Loop {
@@ -19,12 +24,13 @@ impl ASTNode for Loop {
         "Loop".to_string()
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         self.content.children()
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(Loop::new(self.content.clone()))
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        checker.check_scope(&self.content);
+        Type::Any
     }
 
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
@@ -36,18 +42,18 @@ impl ASTNode for Loop {
 
 #[derive(Debug)]
 pub struct For {
-    pub range_expr: Box<dyn ASTNode>,
+    pub range_expr: Rc<Node>,
     pub content: Scope
 }
 
 #[derive(Debug)]
 pub struct While {
-    pub condition: Box<dyn ASTNode>,
+    pub condition: Rc<Node>,
     pub content: Scope
 }
 
 impl For {
-    pub fn new(range_expr: Box<dyn ASTNode>, content: Scope) -> Self {
+    pub fn new(range_expr: Rc<Node>, content: Scope) -> Self {
         Self { range_expr, content }
     }
 }
@@ -57,17 +63,19 @@ impl ASTNode for For {
         "For".to_string()
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         self.content.children()
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(For::new(self.range_expr.clone_to_node(), self.content.clone()))
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        self.range_expr.check(checker);
+        checker.check_scope(&self.content);
+        Type::Any
     }
 }
 
 impl While {
-    pub fn new(condition: Box<dyn ASTNode>, content: Scope) -> Self {
+    pub fn new(condition: Rc<Node>, content: Scope) -> Self {
         Self { condition, content }
     }
 }
@@ -77,22 +85,23 @@ impl ASTNode for While {
         "While".to_string()
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         self.content.children()
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(While::new(self.condition.clone_to_node(), self.content.clone()))
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let condition = self.condition.check(checker);
+        checker.condition(condition);
+        checker.check_scope(&self.content);
+        Type::Any
     }
 
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
-        let mut current_value = self.condition.eval(env)?;
         let mut result = Value::None;
-        while current_value == Value::Bool(true) {
+        while holds(self.condition.eval(env)?)? {
             result = self.content.eval(env)?;
-            current_value = self.condition.eval(env)?;
         }
         Ok(result)
     }
-    
+
 }
\ No newline at end of file
@@ -0,0 +1,164 @@
+use crate::ast::integer;
+use crate::ast::{ASTNode, Value};
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::Error;
+use crate::ast::type_node::Type;
+use crate::checker::checker::{TypeChecker, TypeCheckError};
+use std::collections::HashMap;
+use crate::ast::Node;
+use std::rc::Rc;
+
+/// The hashable form of a map key. Integers of any type hash by value, the
+/// way they compare, and strings by their text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Int(i128),
+    String(String),
+    Bool(bool),
+    None,
+    Tuple(Vec<Key>),
+}
+
+impl Key {
+    /// Fails with `UnhashableKey` for floats, lists, maps and the other
+    /// values that cannot be keys.
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        match value {
+            Value::Int(_) | Value::TypedInt(..) => Ok(Key::Int(integer::as_integer(value).map(|(i, _)| i).unwrap_or_default())),
+            Value::String(s, _) => Ok(Key::String(s.clone())),
+            Value::Bool(b) => Ok(Key::Bool(*b)),
+            Value::None => Ok(Key::None),
+            Value::Tuple(values) => values.iter().map(Key::from_value).collect::<Result<_, _>>().map(Key::Tuple),
+            _ => Err(Error::RuntimeError(RuntimeError::UnhashableKey(value.clone()))),
+        }
+    }
+}
+
+/// A hash map that keeps its entries in insertion order.
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    entries: Vec<(Value, Value)>,
+    positions: HashMap<Key, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_entries(entries: Vec<(Value, Value)>) -> Result<Self, Error> {
+        let mut map = Self::new();
+        for (key, value) in entries {
+            map.insert(key, value)?;
+        }
+        Ok(map)
+    }
+
+    pub fn entries(&self) -> &[(Value, Value)] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &Value) -> Result<Option<&Value>, Error> {
+        let key = Key::from_value(key)?;
+        Ok(self.positions.get(&key).map(|i| &self.entries[*i].1))
+    }
+
+    pub fn get_mut(&mut self, key: &Value) -> Result<Option<&mut Value>, Error> {
+        let key = Key::from_value(key)?;
+        Ok(self.positions.get(&key).map(|i| &mut self.entries[*i].1))
+    }
+
+    pub fn contains(&self, key: &Value) -> Result<bool, Error> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    /// Sets the value of `key`, keeping its place if it was already set, and
+    /// returns the value it replaces.
+    pub fn insert(&mut self, key: Value, value: Value) -> Result<Option<Value>, Error> {
+        match self.positions.get(&Key::from_value(&key)?) {
+            Some(i) => Ok(Some(std::mem::replace(&mut self.entries[*i].1, value))),
+            None => {
+                self.positions.insert(Key::from_value(&key)?, self.entries.len());
+                self.entries.push((key, value));
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &Value) -> Result<Option<Value>, Error> {
+        let i = match self.positions.remove(&Key::from_value(key)?) {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        let (_, value) = self.entries.remove(i);
+        for position in self.positions.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Ok(Some(value))
+    }
+
+    /// Applies `f` to every key and value. `f` must not change how the keys hash.
+    pub fn map_entries(self, f: impl Fn(Value, Value) -> (Value, Value)) -> Self {
+        let entries = self.entries.into_iter().map(|(key, value)| f(key, value)).collect();
+        Self { entries, positions: self.positions }
+    }
+}
+
+/// Maps are equal when they have the same entries, in any order.
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.entries.iter().all(|(key, value)| matches!(other.get(key), Ok(Some(v)) if v == value))
+    }
+}
+
+/// `{key: value, ...}`
+#[derive(Debug)]
+pub struct MapLiteral {
+    pub entries: Vec<(Rc<Node>, Rc<Node>)>,
+}
+
+impl ASTNode for MapLiteral {
+    fn element(&self) -> String {
+        "{}".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        self.entries.iter().flat_map(|(key, value)| [key.clone(), value.clone()]).collect()
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let mut map = Map::new();
+        for (key, value) in &self.entries {
+            let key = key.eval(env)?;
+            map.insert(key, value.eval(env)?)?;
+        }
+        Ok(Value::Map(Box::new(map)))
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let (mut key_type, mut value_type) = (Type::Any, Type::Any);
+        for (key, value) in &self.entries {
+            for (node, type_) in [(key, &mut key_type), (value, &mut value_type)] {
+                let found = node.check(checker);
+                match type_.join(&found) {
+                    Some(joined) => *type_ = joined,
+                    None => checker.report(TypeCheckError::MapEntryMismatch(type_.clone(), found)),
+                }
+            }
+        }
+        if !key_type.is_hashable() {
+            checker.report(TypeCheckError::UnhashableKey(key_type.clone()));
+        }
+        Type::Map(Box::new(key_type), Box::new(value_type))
+    }
+}
@@ -10,8 +10,16 @@ pub mod loops;
 pub mod function;
 pub mod variable_declaration;
 pub mod type_node;
+pub mod integer;
+pub mod cast;
 pub mod tuple;
+pub mod list;
+pub mod map;
 pub mod scope;
+pub mod import;
+pub mod optional;
+pub mod error;
+pub mod task;
 
 pub use ast::*;
 pub use value::*;
\ No newline at end of file
@@ -0,0 +1,105 @@
+use crate::ast::{ASTNode, value::Value};
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::Error;
+use crate::ast::type_node::Type;
+use crate::checker::checker::{TypeChecker, TypeCheckError};
+use crate::ast::Node;
+use std::rc::Rc;
+
+/// `value!`: the value, or a runtime error if it is `none`.
+#[derive(Debug)]
+pub struct Unwrap {
+    pub value: Rc<Node>,
+}
+
+/// `value?`: the value, or an early return of `none` from the enclosing function.
+#[derive(Debug)]
+pub struct Propagate {
+    pub value: Rc<Node>,
+}
+
+/// `value ?? default`: `default` is only evaluated when `value` is `none`.
+#[derive(Debug)]
+pub struct Coalesce {
+    pub value: Rc<Node>,
+    pub default: Rc<Node>,
+}
+
+impl ASTNode for Unwrap {
+    fn element(&self) -> String {
+        "!".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.value.clone()]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        match self.value.eval(env)? {
+            Value::None => Err(Error::RuntimeError(RuntimeError::UnwrapNone(self.value.element()))),
+            value => Ok(value),
+        }
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        unwrapped(self.value.check(checker))
+    }
+}
+
+impl ASTNode for Propagate {
+    fn element(&self) -> String {
+        "?".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.value.clone()]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        match self.value.eval(env)? {
+            Value::None => Err(Error::Return(Value::None)),
+            value => Ok(value),
+        }
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let type_ = self.value.check(checker);
+        // the enclosing function returns none, so it has to return an optional
+        if let Some((name, Some(return_type))) = checker.current_function() {
+            if !matches!(return_type, Type::Option(_) | Type::Any) {
+                checker.report(TypeCheckError::ReturnMismatch(name, return_type, Type::Option(Box::new(Type::Any))));
+            }
+        }
+        unwrapped(type_)
+    }
+}
+
+impl ASTNode for Coalesce {
+    fn element(&self) -> String {
+        "??".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.value.clone(), self.default.clone()]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        match self.value.eval(env)? {
+            Value::None => self.default.eval(env),
+            value => Ok(value),
+        }
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let value = unwrapped(self.value.check(checker));
+        let default = self.default.check(checker);
+        if value == Type::Any { default } else { value }
+    }
+}
+
+fn unwrapped(type_: Type) -> Type {
+    match type_ {
+        Type::Option(inner) => *inner,
+        type_ => type_,
+    }
+}
@@ -1,15 +1,17 @@
 use crate::ast::{ASTNode, Value};
 use crate::Error;
 use crate::environment::environment::Environment;
-
-#[derive(Debug)]
-pub struct Scope(Vec<Box<dyn ASTNode>>);
-
-
-impl Clone for Scope {
-    fn clone(&self) -> Self {
-        Self(self.0.iter().map(|c| c.clone_to_node()).collect())
-    }
+use crate::ast::Node;
+use crate::vm::bytecode::Chunk;
+use std::cell::OnceCell;
+use std::rc::Rc;
+
+/// A block of statements. Cloning one shares its nodes.
+#[derive(Debug, Clone)]
+pub struct Scope {
+    nodes: Vec<Rc<Node>>,
+    /// Compiled the first time the bytecode VM runs the block.
+    chunk: OnceCell<Rc<Chunk>>,
 }
 
 impl PartialEq for Scope {
@@ -19,29 +21,43 @@ impl PartialEq for Scope {
 }
 
 impl Scope {
-    pub fn new(children: Vec<Box<dyn ASTNode>>) -> Self {
-        Self(children)
+    pub fn new(children: Vec<Rc<Node>>) -> Self {
+        Self { nodes: children, chunk: OnceCell::new() }
     }
 
     pub fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
-        if self.0.len() > 0 {
-            let mut local_env = env.new_child();
-            for child in 0..self.0.len() - 1 {
-                self.0[child].eval(&mut local_env)?;
+        if !self.declares() {
+            let mut result = Value::None;
+            for child in &self.nodes {
+                result = child.eval(env)?;
             }
-            let result = self.0[self.0.len() - 1].eval(&mut local_env)?;
-            local_env.free();
-            Ok(result)
-        } else {
-            Ok(Value::None)
+            return Ok(result);
+        }
+        let mut local_env = env.new_child();
+        for child in 0..self.nodes.len() - 1 {
+            self.nodes[child].eval(&mut local_env)?;
         }
+        let result = self.nodes[self.nodes.len() - 1].eval(&mut local_env)?;
+        local_env.free();
+        Ok(result)
+    }
+
+    /// Whether the block declares a variable, and so runs in a frame of its
+    /// own. Blocks that do not run in the enclosing one.
+    pub fn declares(&self) -> bool {
+        self.nodes.iter().any(|node| node.declares())
+    }
+
+    /// The block compiled with `compile`, which is only done once.
+    pub fn chunk(&self, compile: impl FnOnce(&Scope) -> Chunk) -> Rc<Chunk> {
+        Rc::clone(self.chunk.get_or_init(|| Rc::new(compile(self))))
     }
 
-    pub fn clone(&self) -> Self {
-        Self(self.0.iter().map(|c| c.clone_to_node()).collect())
+    pub fn nodes(&self) -> &[Rc<Node>] {
+        &self.nodes
     }
 
-    pub fn children(&self) -> Vec<Box<dyn ASTNode>> {
-        self.0.iter().map(|c| c.as_ref().clone_to_node()).collect()
+    pub fn children(&self) -> Vec<Rc<Node>> {
+        self.nodes.clone()
     }
 }
\ No newline at end of file
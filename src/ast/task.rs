@@ -0,0 +1,84 @@
+use crate::ast::scope::Scope;
+use crate::ast::type_node::Type;
+use crate::ast::{ASTNode, Value};
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::environment::task::await_task;
+use crate::Error;
+use crate::checker::checker::{assignable, TypeChecker, TypeCheckError};
+use crate::ast::Node;
+use std::rc::Rc;
+
+/// `async expr` or `async { block }`: a task that runs the body once what is
+/// running suspends.
+/// The body of an `async fn` is one of these, checked against the declared
+/// return type once the task finishes.
+#[derive(Debug, Clone)]
+pub struct Async {
+    pub body: Scope,
+    pub return_type: Option<Type>,
+}
+
+/// `await task`: the task's value, once it has run.
+#[derive(Debug)]
+pub struct Await {
+    pub value: Rc<Node>,
+}
+
+impl ASTNode for Async {
+    fn element(&self) -> String {
+        "async".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        self.body.children()
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let captured = env.capture();
+        let id = env.tasks.borrow_mut().spawn(self.body.clone(), self.return_type.clone(), captured);
+        Ok(Value::Task(id))
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        // `?` in the body ends the task rather than the enclosing function
+        let name = checker.current_function().map(|(name, _)| name).unwrap_or_default();
+        let found = checker.within_function(&name, self.return_type.clone(), |checker| checker.check_scope(&self.body));
+        match &self.return_type {
+            Some(expected) => {
+                if !self.body.children().is_empty() && !assignable(expected, &found) {
+                    checker.report(TypeCheckError::ReturnMismatch(name, expected.clone(), found));
+                }
+                Type::Task(Box::new(expected.clone()))
+            }
+            None => Type::Task(Box::new(found)),
+        }
+    }
+}
+
+impl ASTNode for Await {
+    fn element(&self) -> String {
+        "await".to_string()
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.value.clone()]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        match self.value.eval(env)? {
+            Value::Task(id) => await_task(&env.tasks, id),
+            value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::Task(Box::new(Type::Any))))),
+        }
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        match self.value.check(checker) {
+            Type::Task(inner) => *inner,
+            Type::Any => Type::Any,
+            type_ => {
+                checker.report(TypeCheckError::NotATask(type_));
+                Type::Any
+            }
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use crate::ast::{ASTNode, EmptyASTNode, Value};
-use crate::environment::environment::Environment;
+use crate::environment::environment::{Environment, RuntimeError};
 use crate::Error;
+use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::Node;
+use std::rc::Rc;
 
 
 #[derive(Debug, Clone)]
@@ -66,6 +70,14 @@ pub enum TupleError {
     CannotPairUp,
 }
 
+impl std::fmt::Display for TupleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TupleError::CannotPairUp => write!(f, "the names and values of the tuple do not pair up"),
+        }
+    }
+}
+
 pub trait Clonable {
     fn clone_element(&self) -> Self;
 }
@@ -254,43 +266,38 @@ impl<T: Clonable> Tuple<T> {
 
 #[derive(Debug)]
 pub struct TupleASTNode {
-    pub children: Vec<Box<dyn ASTNode>>,
+    pub children: Vec<Rc<Node>>,
 }
 
 impl TupleASTNode {
-    pub fn new(children: Vec<Box<dyn ASTNode>>) -> Self {
+    pub fn new(children: Vec<Rc<Node>>) -> Self {
         Self { children }
     }
 
-    pub fn from_tuple(tuple: Tuple<Box<dyn ASTNode>>) -> Box<dyn ASTNode> {
+    pub fn from_tuple(tuple: Tuple<Rc<Node>>) -> Rc<Node> {
         match tuple {
-            Tuple::Empty => Box::new(EmptyASTNode::new()),
-            Tuple::Element(element) => element.clone_to_node(),
+            Tuple::Empty => Node::new(EmptyASTNode::new()),
+            Tuple::Element(element) => element.clone(),
             Tuple::List(elements) => {
                 let mut children = Vec::new();
                 for element in elements {
                     children.push(TupleASTNode::from_tuple(element));
                 }
-                Box::new(TupleASTNode::new(children))
+                Node::new(TupleASTNode::new(children))
             }
         }
     }
 }
 
 impl ASTNode for TupleASTNode {
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
-        self.children.iter().map(|c| c.as_ref().clone_to_node()).collect()
+    fn children(&self) -> Vec<Rc<Node>> {
+        self.children.clone()
     }
 
     fn element(&self) -> String {
         "Tuple".to_string()
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let children = self.children.iter().map(|c| c.as_ref().clone_to_node()).collect();
-        Box::new(TupleASTNode { children })
-    }
-
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
         let mut values = Vec::new();
         for child in self.children.iter() {
@@ -298,8 +305,56 @@ impl ASTNode for TupleASTNode {
         }
         Ok(Value::Tuple(values))
     }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        Type::Tuple(self.children.iter().map(|child| child.check(checker)).collect())
+    }
+}
+
+/// `tuple.0`
+#[derive(Debug)]
+pub struct TupleIndex {
+    pub value: Rc<Node>,
+    pub index: usize,
+}
+
+/// The element at `path` in a tuple, one index per level of nesting.
+pub fn element_mut<'a>(value: &'a mut Value, path: &[usize]) -> Result<&'a mut Value, Error> {
+    let (index, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(value),
+    };
+    match value {
+        Value::Tuple(values) => {
+            let len = values.len();
+            match values.get_mut(*index) {
+                Some(element) => element_mut(element, rest),
+                None => Err(Error::RuntimeError(RuntimeError::IndexOutOfBounds(*index as i128, len))),
+            }
+        }
+        value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::Tuple(vec![])))),
+    }
 }
 
+impl ASTNode for TupleIndex {
+    fn element(&self) -> String {
+        format!(".{}", self.index)
+    }
+
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.value.clone()]
+    }
+
+    fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
+        let mut value = self.value.eval(env)?;
+        Ok(std::mem::replace(element_mut(&mut value, &[self.index])?, Value::None))
+    }
+
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let found = self.value.check(checker);
+        checker.tuple_element(found, self.index)
+    }
+}
 
 pub trait TupleLike<T: Clonable> {
     fn to_tuple(&self) -> Tuple<T>;
@@ -4,12 +4,23 @@ use crate::ast::{ASTNode,Value};
 use crate::environment::environment::{Environment};
 use crate::lexer::token;
 use crate::Error;
+use std::collections::HashMap;
+use crate::ast::Node;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub enum TypeError {
     CannotMakeTupleType
 }
 
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeError::CannotMakeTupleType => write!(f, "an empty tuple is not a type"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Type {
     U8,
@@ -27,10 +38,59 @@ pub enum Type {
     UserDefined(String),
     Option(Box<Type>),
     Tuple(Vec<Type>),
+    List(Box<Type>),
+    /// Key type, value type.
+    Map(Box<Type>, Box<Type>),
     FnType(Box<FnSignature>),
+    Task(Box<Type>),
+    Channel,
+    Error,
     Any,
 }
 
+/// Types as they are written in Loop source.
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::U8 => write!(f, "u8"),
+            Type::U16 => write!(f, "u16"),
+            Type::U32 => write!(f, "u32"),
+            Type::U64 => write!(f, "u64"),
+            Type::I16 => write!(f, "i16"),
+            Type::I32 => write!(f, "i32"),
+            Type::I64 => write!(f, "i64"),
+            Type::F32 => write!(f, "f32"),
+            Type::F64 => write!(f, "f64"),
+            Type::String => write!(f, "string"),
+            Type::Bool => write!(f, "bool"),
+            Type::Generic(c) => write!(f, "{}", c),
+            Type::UserDefined(name) => write!(f, "{}", name),
+            Type::Option(inner) => write!(f, "{}?", inner),
+            Type::Tuple(elements) => {
+                let elements = elements.iter().map(|element| format!("{}", element)).collect::<Vec<_>>();
+                write!(f, "({})", elements.join(", "))
+            }
+            Type::List(element) => write!(f, "[{}]", element),
+            Type::Map(key, value) => write!(f, "{{{}: {}}}", key, value),
+            Type::FnType(signature) => {
+                let params = signature.params.iter().map(|(param, type_)| match signature.takes_reference(param) {
+                    true => format!("mut {}: {}", param, type_),
+                    false => format!("{}: {}", param, type_),
+                }).collect::<Vec<_>>();
+                write!(f, "fn({})", params.join(", "))?;
+                match &signature.return_type {
+                    Some(return_type) => write!(f, " -> {}", return_type),
+                    None => Ok(()),
+                }
+            }
+            Type::Task(value) => write!(f, "task<{}>", value),
+            Type::Channel => write!(f, "channel"),
+            Type::Error => write!(f, "error"),
+            Type::Any => write!(f, "any"),
+        }
+    }
+}
+
 impl Clonable for Type {
     fn clone_element(&self) -> Self {
         self.clone()
@@ -39,9 +99,108 @@ impl Clonable for Type {
 
 impl Type {
 
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::U8 | Type::U16 | Type::U32 | Type::U64 | Type::I16 | Type::I32 | Type::I64)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+
+    /// Binds the type variables in `self` so that it matches `found`. A
+    /// variable bound twice to incompatible types fails with the variable and
+    /// both bindings. Other mismatches are left to the type checks.
+    pub fn unify(&self, found: &Type, bindings: &mut HashMap<char, Type>) -> Result<(), (char, Type, Type)> {
+        match (self, found) {
+            (_, Type::Any) => Ok(()),
+            (Type::Generic(name), found) => {
+                let bound = match bindings.get(name) {
+                    Some(bound) => match bound.join(found) {
+                        Some(joined) => joined,
+                        None => return Err((*name, bound.clone(), found.clone())),
+                    },
+                    None => found.clone(),
+                };
+                bindings.insert(*name, bound);
+                Ok(())
+            }
+            (Type::Option(inner), Type::Option(found)) => inner.unify(found, bindings),
+            (Type::Option(inner), found) => inner.unify(found, bindings),
+            (Type::Tuple(types), Type::Tuple(founds)) if types.len() == founds.len() => {
+                types.iter().zip(founds).try_for_each(|(type_, found)| type_.unify(found, bindings))
+            }
+            (Type::Task(inner), Type::Task(found)) => inner.unify(found, bindings),
+            (Type::List(inner), Type::List(found)) => inner.unify(found, bindings),
+            (Type::Map(key, value), Type::Map(found_key, found_value)) => {
+                key.unify(found_key, bindings)?;
+                value.unify(found_value, bindings)
+            }
+            (Type::FnType(signature), Type::FnType(found)) if signature.params.len() == found.params.len() => {
+                for ((_, type_), (_, found)) in signature.params.iter().zip(&found.params) {
+                    type_.unify(found, bindings)?;
+                }
+                match (&signature.return_type, &found.return_type) {
+                    (Some(type_), Some(found)) => type_.unify(found, bindings),
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// A type both `self` and `other` fit in, if any: integers of any size
+    /// join, and integers join floats as the float.
+    pub fn join(&self, other: &Type) -> Option<Type> {
+        match (self, other) {
+            _ if self == other => Some(self.clone()),
+            (Type::Any, _) => Some(other.clone()),
+            (_, Type::Any) => Some(self.clone()),
+            _ if self.is_integer() && other.is_integer() => Some(self.clone()),
+            _ if self.is_float() && (other.is_integer() || other.is_float()) => Some(self.clone()),
+            _ if self.is_integer() && other.is_float() => Some(other.clone()),
+            (Type::Option(inner), Type::Option(other)) => inner.join(other).map(|t| Type::Option(Box::new(t))),
+            (Type::Option(inner), other) | (other, Type::Option(inner)) => inner.join(other).map(|t| Type::Option(Box::new(t))),
+            (Type::Tuple(types), Type::Tuple(others)) if types.len() == others.len() => {
+                types.iter().zip(others).map(|(t, o)| t.join(o)).collect::<Option<Vec<_>>>().map(Type::Tuple)
+            }
+            (Type::List(inner), Type::List(other)) => inner.join(other).map(|t| Type::List(Box::new(t))),
+            (Type::Map(key, value), Type::Map(other_key, other_value)) => Some(Type::Map(Box::new(key.join(other_key)?), Box::new(value.join(other_value)?))),
+            _ => None,
+        }
+    }
+
+    /// Replaces the type variables in `self` by their bindings, and unbound
+    /// ones by `Any`.
+    pub fn substitute(&self, bindings: &HashMap<char, Type>) -> Type {
+        match self {
+            Type::Generic(name) => bindings.get(name).cloned().unwrap_or(Type::Any),
+            Type::Option(inner) => Type::Option(Box::new(inner.substitute(bindings))),
+            Type::Tuple(types) => Type::Tuple(types.iter().map(|t| t.substitute(bindings)).collect()),
+            Type::Task(inner) => Type::Task(Box::new(inner.substitute(bindings))),
+            Type::List(inner) => Type::List(Box::new(inner.substitute(bindings))),
+            Type::Map(key, value) => Type::Map(Box::new(key.substitute(bindings)), Box::new(value.substitute(bindings))),
+            Type::FnType(signature) => Type::FnType(Box::new(FnSignature {
+                params: signature.params.iter().map(|(name, t)| (name.clone(), t.substitute(bindings))).collect(),
+                references: signature.references.clone(),
+                return_type: signature.return_type.as_ref().map(|t| t.substitute(bindings)),
+            })),
+            type_ => type_.clone(),
+        }
+    }
+
+    /// Whether values of this type can be map keys.
+    pub fn is_hashable(&self) -> bool {
+        match self {
+            Type::String | Type::Bool | Type::Generic(_) | Type::Any => true,
+            Type::Option(inner) => inner.is_hashable(),
+            Type::Tuple(types) => types.iter().all(|t| t.is_hashable()),
+            type_ => type_.is_integer(),
+        }
+    }
+
     pub fn is_basic(&self) -> bool {
         match self {
-            Type::Generic(_) | Type::UserDefined(_) => false,
+            Type::Generic(_) | Type::UserDefined(_) | Type::List(_) | Type::Map(..) => false,
             Type::Option(t) => t.is_basic(),
             Type::Tuple(types) => types.iter().all(|t| t.is_basic()),
             _ => true,
@@ -98,17 +257,13 @@ impl ASTNode for Type {
         format!("{:?}", self)
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         vec![]
     }
 
     fn eval(&self, _env: &mut Environment) -> Result<Value, Error> {
         Ok(Value::None)
     }
-
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(self.clone())
-    }
 }
 
 
@@ -1,11 +1,17 @@
 use crate::ast::{ASTNode,value::Value};
 use crate::environment::environment::{Environment, RuntimeError};
+use crate::environment::trace::summarize;
 use crate::lexer::token::Operator;
 use crate::Error;
+use crate::ast::integer;
+use crate::ast::type_node::Type;
+use crate::checker::checker::TypeChecker;
+use crate::ast::Node;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct UnaryOperation {
-    pub operand: Box<dyn ASTNode>,
+    pub operand: Rc<Node>,
     pub operator: Operator,
 }
 
@@ -14,46 +20,53 @@ pub enum UnaryOperationError {
     CannotPerform(Operator, Value),
 }
 
+impl std::fmt::Display for UnaryOperationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnaryOperationError::CannotPerform(operator, operand) => write!(f, "cannot apply `{}` to {}", operator, summarize(operand)),
+        }
+    }
+}
+
 impl ASTNode for UnaryOperation {
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
-        vec![self.operand.clone_to_node()]
+    fn children(&self) -> Vec<Rc<Node>> {
+        vec![self.operand.clone()]
     }
 
     fn element(&self) -> String {
         format!("{:?}", self.operator)
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        let operand = self.operand.clone_to_node();
-        Box::new(UnaryOperation {
-            operand,
-            operator: self.operator.clone(),
-        })
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let operand = self.operand.check(checker);
+        checker.unary(&self.operator, operand)
     }
 
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
         let operand = self.operand.eval(env)?;
+        Self::apply(&self.operator, operand)
+    }
+}
+
+impl UnaryOperation {
+    pub fn apply(operator: &Operator, operand: Value) -> Result<Value, Error> {
+        if let Some(result) = integer::unary(operator, &operand) {
+            return result;
+        }
         Ok(match operand {
-                Value::Int(l) => {
-                    match self.operator {
-                        Operator::Sub => Value::Int(-l),
-                        Operator::BitNot => Value::Int(!l),
-                        _ => return Err(Error::RuntimeError(RuntimeError::UnaryOperationError(UnaryOperationError::CannotPerform(self.operator.clone(), operand)))),
-                    }
-                }
                 Value::Float(l) => {
-                    match self.operator {
+                    match operator {
                         Operator::Sub => Value::Float(-l),
-                        _ => return Err(Error::RuntimeError(RuntimeError::UnaryOperationError(UnaryOperationError::CannotPerform(self.operator.clone(), operand)))),
+                        _ => return Err(Error::RuntimeError(RuntimeError::UnaryOperationError(UnaryOperationError::CannotPerform(operator.clone(), operand)))),
                     }
                 }
                 Value::Bool(l) => {
-                    match self.operator {
+                    match operator {
                         Operator::Not => Value::Bool(!l),
-                        _ => return Err(Error::RuntimeError(RuntimeError::UnaryOperationError(UnaryOperationError::CannotPerform(self.operator.clone(), operand)))),
+                        _ => return Err(Error::RuntimeError(RuntimeError::UnaryOperationError(UnaryOperationError::CannotPerform(operator.clone(), operand)))),
                     }
                 }
-                _ => return Err(Error::RuntimeError(RuntimeError::UnaryOperationError(UnaryOperationError::CannotPerform(self.operator.clone(), operand)))),
+                _ => return Err(Error::RuntimeError(RuntimeError::UnaryOperationError(UnaryOperationError::CannotPerform(operator.clone(), operand)))),
             })
     }
 }
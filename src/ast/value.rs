@@ -1,22 +1,41 @@
+use crate::ast::integer;
+use crate::ast::map::Map;
 use crate::ast::scope::Scope;
+use crate::ast::type_node::Type;
+use crate::environment::environment::RuntimeError;
+use crate::environment::native::NativeFn;
+use crate::Error;
 use crate::ast::tuple::{Clonable, Tuple, TupleLike};
+use std::rc::Rc;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Value {
+    /// An integer literal, until it is stored with a type.
     Int(i64),
+    /// An integer of one of the integer types, always within its range.
+    TypedInt(i128, Type),
     Float(f64),
     String(String, bool),
     Bool(bool),
     Tuple(Vec<Value>),
+    /// A growable list in one heap slot. Assigning or passing it copies it,
+    /// unless it is passed to a `mut` param.
+    List(Vec<Value>),
+    /// A map in insertion order, stored and copied like a list.
+    Map(Box<Map>),
     None,
-    Error(String),
-    Fn(Box<Scope>),
+    Error(String, Box<Value>),
+    Fn(Rc<Scope>),
+    NativeFn(NativeFn),
+    Task(usize),
+    Channel(usize),
 }
 
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Int(i) => write!(f, "{}", i),
+            Value::TypedInt(i, _) => write!(f, "{}", i),
             Value::Float(fl) => write!(f, "{}", fl),
             Value::String(s, _) => write!(f, "{}", s),
             Value::Bool(b) => write!(f, "{}", b),
@@ -30,8 +49,46 @@ impl std::fmt::Display for Value {
                 result.push_str(&values[values.len() - 1].to_string());
                 write!(f, "({})", result)
             }
-            Value::Error(e) => write!(f, "error({})", e),
+            Value::List(values) => {
+                let values = values.iter().map(|value| value.to_string()).collect::<Vec<_>>();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Value::Map(map) => {
+                let entries = map.entries().iter().map(|(key, value)| format!("{}: {}", key, value)).collect::<Vec<_>>();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+            Value::Error(kind, payload) => match payload.as_ref() {
+                Value::None => write!(f, "{}", kind),
+                payload => write!(f, "{}({})", kind, payload),
+            },
             Value::Fn(_) => write!(f, "fn(..)"),
+            Value::NativeFn(native) => write!(f, "fn {}(..)", native.name),
+            Value::Task(id) => write!(f, "task#{}", id),
+            Value::Channel(id) => write!(f, "channel#{}", id),
+        }
+    }
+}
+
+/// Integers are equal when their values are, whatever their types, and strings when their contents are, raw or not.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Int(_) | Value::TypedInt(..), Value::Int(_) | Value::TypedInt(..)) => {
+                integer::as_integer(self).map(|(i, _)| i) == integer::as_integer(other).map(|(i, _)| i)
+            }
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::String(a, _), Value::String(b, _)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Tuple(a), Value::Tuple(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::None, Value::None) => true,
+            (Value::Error(a, a_payload), Value::Error(b, b_payload)) => a == b && a_payload == b_payload,
+            (Value::Fn(a), Value::Fn(b)) => a == b,
+            (Value::NativeFn(a), Value::NativeFn(b)) => a == b,
+            (Value::Task(a), Value::Task(b)) => a == b,
+            (Value::Channel(a), Value::Channel(b)) => a == b,
+            _ => false,
         }
     }
 }
@@ -61,4 +118,125 @@ impl TupleLike<Value> for Value {
             _ => Tuple::Element(self.clone()),
         }
     }
-}
\ No newline at end of file
+}
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value, false)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string(), false)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::None, Into::into)
+    }
+}
+
+fn not_of_type(value: Value, type_: Type) -> Error {
+    Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), type_))
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Int(i) => Ok(i),
+            Value::TypedInt(i, _) => i64::try_from(i).map_err(|_| not_of_type(value, Type::I64)),
+            _ => Err(not_of_type(value, Type::I64)),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Float(f) => Ok(f),
+            Value::Int(i) => Ok(i as f64),
+            Value::TypedInt(i, _) => Ok(i as f64),
+            _ => Err(not_of_type(value, Type::F64)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            _ => Err(not_of_type(value, Type::Bool)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::String(s, _) => Ok(s),
+            _ => Err(not_of_type(value, Type::String)),
+        }
+    }
+}
+
+macro_rules! impl_tuple_conversions {
+    ($len:expr, $($name:ident),+) => {
+        impl<$($name: Into<Value>),+> From<($($name,)+)> for Value {
+            #[allow(non_snake_case)]
+            fn from(($($name,)+): ($($name,)+)) -> Self {
+                Value::Tuple(vec![$($name.into()),+])
+            }
+        }
+
+        impl<$($name: TryFrom<Value, Error = Error>),+> TryFrom<Value> for ($($name,)+) {
+            type Error = Error;
+
+            fn try_from(value: Value) -> Result<Self, Error> {
+                match value {
+                    Value::Tuple(values) if values.len() == $len => {
+                        let mut values = values.into_iter();
+                        Ok(($($name::try_from(values.next().unwrap())?,)+))
+                    }
+                    _ => Err(not_of_type(value, Type::Tuple(vec![Type::Any; $len]))),
+                }
+            }
+        }
+    };
+}
+
+impl_tuple_conversions!(2, A, B);
+impl_tuple_conversions!(3, A, B, C);
+impl_tuple_conversions!(4, A, B, C, D);
@@ -1,9 +1,11 @@
-use crate::ast::{ASTNode};
+use crate::ast::{ASTNode, Node};
 use crate::ast::value::Value;
 use crate::environment::environment::Environment;
 use crate::ast::type_node::Type;
 use crate::ast::tuple::{Tuple, TupleLike};
 use crate::Error;
+use crate::checker::checker::TypeChecker;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct VariableDeclaration {
@@ -18,16 +20,26 @@ impl ASTNode for VariableDeclaration {
         format!("{} {} : {:?}", mutable, self.name, self.type_)
     }
 
-    fn children(&self) -> Vec<Box<dyn ASTNode>> {
+    fn children(&self) -> Vec<Rc<Node>> {
         vec![]
     }
 
-    fn clone_to_node(&self) -> Box<dyn ASTNode> {
-        Box::new(VariableDeclaration {
-            mutable: self.mutable,
-            type_: self.type_.clone(),
-            name: self.name.clone(),
-        })
+    fn check(&self, checker: &mut TypeChecker) -> Type {
+        let types = self.name.apply_structure(self.type_.to_tuple()).ok()
+            .and_then(|types| self.name.pair_up_left(types).ok());
+        match types {
+            Some(pairs) => {
+                for (name, type_) in pairs {
+                    checker.declare_uninitialized(&name, Type::from_tuple(type_).unwrap_or(Type::Any), self.mutable);
+                }
+            }
+            None => {
+                if let Tuple::Element(name) = &self.name {
+                    checker.declare_uninitialized(name, self.type_.clone(), self.mutable);
+                }
+            }
+        }
+        Type::Bool
     }
 
     fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
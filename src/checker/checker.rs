@@ -0,0 +1,596 @@
+use crate::ast::function::FnSignature;
+use crate::ast::scope::Scope;
+use crate::ast::tuple::Tuple;
+use crate::ast::type_node::Type;
+use crate::ast::ASTNode;
+use crate::environment::environment::Environment;
+use crate::environment::{list, map, string};
+use crate::lexer::token::Operator;
+use crate::lexer::Span;
+use crate::Error;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeCheckError {
+    /// Variable, declared type, type of the value.
+    DeclarationMismatch(String, Type, Type),
+    AssignmentMismatch(String, Type, Type),
+    CannotAssignToImmutableVariable(String),
+    /// Function, param, param type, type of the argument.
+    ArgumentMismatch(String, String, Type, Type),
+    UnknownArgument(String, String),
+    MissingArgument(String, String),
+    /// Function and param, like `RuntimeError::BorrowMismatch`.
+    BorrowMismatch(String, String),
+    ReturnMismatch(String, Type, Type),
+    NotAFunction(String, Type),
+    ConditionNotBool(Type),
+    OperandMismatch(Operator, Type, Type),
+    InvalidOperand(Operator, Type),
+    NotATask(Type),
+    /// Function, type variable, first binding, conflicting binding.
+    GenericBindingConflict(String, char, Type, Type),
+    InvalidCast(Type, Type),
+    NotAList(Type),
+    InvalidIndex(Type),
+    /// Element type of the list, type of the value.
+    ListElementMismatch(Type, Type),
+    /// Key or value type of the map, type of the key or value.
+    MapEntryMismatch(Type, Type),
+    UnhashableKey(Type),
+    NotATuple(Type),
+    /// Type of the tuple, index past its last element.
+    NoTupleElement(Type, usize),
+    /// Like `RuntimeError::TemporaryReceiver`.
+    TemporaryReceiver(String),
+    /// Names of a tuple pattern, how many there are, type of the value.
+    TuplePatternMismatch(String, usize, Type),
+}
+
+impl std::fmt::Display for TypeCheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeCheckError::DeclarationMismatch(name, declared, found) => write!(f, "`{}` is declared {} but given {}", name, declared, found),
+            TypeCheckError::AssignmentMismatch(name, declared, found) => write!(f, "`{}` is {} but assigned {}", name, declared, found),
+            TypeCheckError::CannotAssignToImmutableVariable(name) => write!(f, "cannot assign twice to immutable variable `{}`", name),
+            TypeCheckError::ArgumentMismatch(function, param, expected, found) => {
+                write!(f, "param `{}` of `{}` is {} but given {}", param, function, expected, found)
+            }
+            TypeCheckError::UnknownArgument(function, param) => write!(f, "`{}` has no param `{}`", function, param),
+            TypeCheckError::MissingArgument(function, param) => write!(f, "missing argument `{}` of `{}`", param, function),
+            TypeCheckError::BorrowMismatch(function, param) => write!(f, "`mut` does not match param `{}` of `{}`", param, function),
+            TypeCheckError::ReturnMismatch(function, declared, found) => write!(f, "`{}` returns {} but gives {}", function, declared, found),
+            TypeCheckError::NotAFunction(name, type_) => write!(f, "`{}` is {}, not a function", name, type_),
+            TypeCheckError::ConditionNotBool(type_) => write!(f, "condition is {}, not bool", type_),
+            TypeCheckError::OperandMismatch(operator, left, right) => write!(f, "cannot apply `{}` to {} and {}", operator, left, right),
+            TypeCheckError::InvalidOperand(operator, type_) => write!(f, "cannot apply `{}` to {}", operator, type_),
+            TypeCheckError::NotATask(type_) => write!(f, "cannot await {}, which is not a task", type_),
+            TypeCheckError::GenericBindingConflict(function, variable, first, second) => {
+                write!(f, "`{}` binds {} to both {} and {}", function, variable, first, second)
+            }
+            TypeCheckError::InvalidCast(from, to) => write!(f, "cannot cast {} to {}", from, to),
+            TypeCheckError::NotAList(type_) => write!(f, "{} is not a list", type_),
+            TypeCheckError::InvalidIndex(type_) => write!(f, "cannot index with {}", type_),
+            TypeCheckError::ListElementMismatch(element, found) => write!(f, "list of {} given {}", element, found),
+            TypeCheckError::MapEntryMismatch(expected, found) => write!(f, "map entry of {} given {}", expected, found),
+            TypeCheckError::UnhashableKey(type_) => write!(f, "{} cannot be a map key", type_),
+            TypeCheckError::NotATuple(type_) => write!(f, "{} is not a tuple", type_),
+            TypeCheckError::NoTupleElement(type_, index) => write!(f, "{} has no element {}", type_, index),
+            TypeCheckError::TemporaryReceiver(call) => write!(f, "`{}` changes its receiver, which has to be a variable", call),
+            TypeCheckError::TuplePatternMismatch(names, count, found) => write!(f, "`{}` takes {} values but is given {}", names, count, found),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    type_: Type,
+    mutable: bool,
+    initialized: bool,
+}
+
+/// Infers the type of every expression of a program without running it and
+/// collects every mismatch against declared types and function signatures.
+/// Anything it cannot know, such as imported modules, is `Type::Any` and
+/// left to the checks done at runtime.
+pub struct TypeChecker {
+    scopes: Vec<HashMap<String, Binding>>,
+    functions: Vec<(String, Option<Type>)>,
+    /// The mismatches found, with the innermost node they were found in
+    /// that has a span.
+    pub errors: Vec<(TypeCheckError, Option<Span>)>,
+    /// The span of the node being checked.
+    pub span: Option<Span>,
+}
+
+/// Checks `ast` against the variables already defined in `env`.
+pub fn check(env: &Environment, ast: &dyn ASTNode) -> Result<(), Error> {
+    let mut checker = TypeChecker::new(env);
+    ast.check(&mut checker);
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::TypeCheckError(checker.errors))
+    }
+}
+
+impl TypeChecker {
+    pub fn new(env: &Environment) -> Self {
+        let globals = env.all_variables().into_iter()
+            .map(|(name, variable)| (name, Binding { type_: variable.type_, mutable: variable.mutable, initialized: variable.initialized }))
+            .collect();
+        Self { scopes: vec![globals], functions: Vec::new(), errors: Vec::new(), span: None }
+    }
+
+    pub fn report(&mut self, error: TypeCheckError) {
+        self.errors.push((error, self.span));
+    }
+
+    pub fn declare(&mut self, name: &str, type_: Type, mutable: bool) {
+        self.bind(name, Binding { type_, mutable, initialized: true });
+    }
+
+    pub fn declare_uninitialized(&mut self, name: &str, type_: Type, mutable: bool) {
+        self.bind(name, Binding { type_, mutable, initialized: false });
+    }
+
+    fn bind(&mut self, name: &str, binding: Binding) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), binding);
+        }
+    }
+
+    fn binding(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name))
+    }
+
+    /// The type of a variable, or `Any` when it is not known statically.
+    pub fn lookup(&mut self, name: &str) -> Type {
+        self.binding(name).map(|binding| binding.type_.clone()).unwrap_or(Type::Any)
+    }
+
+    /// The type of a variable passed as `mut`, which it has to be declared as.
+    pub fn borrow(&mut self, name: &str) -> Type {
+        let binding = match self.binding(name) {
+            Some(binding) => binding.clone(),
+            None => return Type::Any,
+        };
+        if !binding.mutable {
+            self.report(TypeCheckError::CannotAssignToImmutableVariable(name.to_string()));
+        }
+        binding.type_
+    }
+
+    pub fn assign(&mut self, name: &str, found: Type) {
+        let binding = match self.binding(name) {
+            Some(binding) => binding,
+            None => return,
+        };
+        let error = if binding.initialized && !binding.mutable {
+            Some(TypeCheckError::CannotAssignToImmutableVariable(name.to_string()))
+        } else if !assignable(&binding.type_, &found) {
+            Some(TypeCheckError::AssignmentMismatch(name.to_string(), binding.type_.clone(), found))
+        } else {
+            None
+        };
+        binding.initialized = true;
+        if let Some(error) = error {
+            self.report(error);
+        }
+    }
+
+    /// Checks `name.0.1 := value` against the type of the element at `path`.
+    pub fn assign_element(&mut self, name: &str, path: &[usize], found: Type) {
+        let binding = match self.binding(name) {
+            Some(binding) => binding.clone(),
+            None => return,
+        };
+        if !binding.mutable {
+            self.report(TypeCheckError::CannotAssignToImmutableVariable(name.to_string()));
+        }
+        let expected = path.iter().fold(binding.type_, |type_, index| self.tuple_element(type_, *index));
+        if !assignable(&expected, &found) {
+            let target = path.iter().fold(name.to_string(), |target, index| format!("{}.{}", target, index));
+            self.report(TypeCheckError::AssignmentMismatch(target, expected, found));
+        }
+    }
+
+    /// Checks `name[i][j] := value` against the type of the element it sets.
+    pub fn assign_index(&mut self, name: &str, indexes: Vec<Type>, found: Type) {
+        let binding = match self.binding(name) {
+            Some(binding) => binding.clone(),
+            None => return,
+        };
+        if !binding.mutable {
+            self.report(TypeCheckError::CannotAssignToImmutableVariable(name.to_string()));
+        }
+        let target = format!("{}{}", name, "[]".repeat(indexes.len()));
+        let expected = indexes.into_iter().fold(binding.type_, |collection, index| self.lookup_index(collection, index));
+        if !assignable(&expected, &found) {
+            self.report(TypeCheckError::AssignmentMismatch(target, expected, found));
+        }
+    }
+
+    /// Declares the names of a `let` or `mut`, destructuring tuples the way
+    /// `declare_assign` does at runtime.
+    pub fn declare_pattern(&mut self, names: &Tuple<String>, declared: Option<&Type>, found: Type, mutable: bool) {
+        match names {
+            Tuple::Empty => {}
+            Tuple::Element(name) => match declared {
+                Some(declared) => {
+                    if !assignable(declared, &found) {
+                        self.report(TypeCheckError::DeclarationMismatch(name.clone(), declared.clone(), found));
+                    }
+                    self.declare(name, declared.clone(), mutable);
+                }
+                None => self.declare(name, found, mutable),
+            },
+            Tuple::List(elements) => {
+                let founds = match &found {
+                    Type::Tuple(types) if types.len() == elements.len() => types.clone(),
+                    Type::Any => vec![Type::Any; elements.len()],
+                    _ => {
+                        self.report(TypeCheckError::TuplePatternMismatch(pattern_names(elements), elements.len(), found));
+                        vec![Type::Any; elements.len()]
+                    }
+                };
+                for (i, (element, found)) in elements.iter().zip(founds).enumerate() {
+                    let declared = match declared {
+                        Some(Type::Tuple(types)) => types.get(i),
+                        declared => declared,
+                    };
+                    self.declare_pattern(element, declared, found, mutable);
+                }
+            }
+        }
+    }
+
+    /// Checks a block in its own scope and returns the type of its last expression.
+    pub fn check_scope(&mut self, scope: &Scope) -> Type {
+        self.scoped(|checker| {
+            let mut type_ = Type::Any;
+            for child in scope.children() {
+                type_ = child.check(checker);
+            }
+            type_
+        })
+    }
+
+    pub fn scoped<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(HashMap::new());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    /// Checks a function body against its signature, with the params in scope.
+    pub fn check_function(&mut self, name: &str, signature: &FnSignature, body: &Scope) {
+        let found = self.within_function(name, signature.return_type.clone(), |checker| {
+            for (param, type_) in &signature.params {
+                checker.declare(param, type_.clone(), signature.takes_reference(param));
+            }
+            checker.check_scope(body)
+        });
+        if let Some(expected) = &signature.return_type {
+            if !body.children().is_empty() && !assignable(expected, &found) {
+                self.report(TypeCheckError::ReturnMismatch(name.to_string(), expected.clone(), found));
+            }
+        }
+    }
+
+    /// Runs `f` in a new scope, as the body of the function `name`.
+    pub fn within_function<T>(&mut self, name: &str, return_type: Option<Type>, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.functions.push((name.to_string(), return_type));
+        let result = self.scoped(f);
+        self.functions.pop();
+        result
+    }
+
+    /// The name and declared return type of the function being checked.
+    pub fn current_function(&self) -> Option<(String, Option<Type>)> {
+        self.functions.last().cloned()
+    }
+
+    pub fn condition(&mut self, found: Type) {
+        if !matches!(found, Type::Bool | Type::Any) {
+            self.report(TypeCheckError::ConditionNotBool(found));
+        }
+    }
+
+    /// Checks the arguments of a call, bound to params the way `FnCall` binds
+    /// them at runtime, and returns the type of the result.
+    /// Checks a call with `args`, of which the ones in `borrows` are passed as `mut`.
+    pub fn call(&mut self, name: &str, args: Vec<(Option<String>, Type)>, borrows: Vec<bool>) -> Type {
+        if self.binding(name).is_none() {
+            if let Some((target, method)) = name.rsplit_once('.') {
+                if let collection @ (Type::List(_) | Type::Map(..) | Type::String) = self.lookup(target) {
+                    return self.method(Some(target), method, collection, args, borrows);
+                }
+            }
+        }
+        let signature = match self.lookup(name) {
+            Type::FnType(signature) => *signature,
+            Type::Any => return Type::Any,
+            type_ => {
+                self.report(TypeCheckError::NotAFunction(name.to_string(), type_));
+                return Type::Any;
+            }
+        };
+        let positional = args.len() == signature.params.len() && args.iter().all(|(param, _)| param.is_none());
+        let mut bound = Vec::new();
+        let mut bindings = HashMap::new();
+        let mut complete = true;
+        for (i, ((param, found), borrowed)) in args.into_iter().zip(borrows).enumerate() {
+            let param = match param {
+                Some(param) => param,
+                None if positional => signature.params[i].0.clone(),
+                None if signature.params.len() == 1 => signature.params[0].0.clone(),
+                // matched by the name of the variable passed, only known at runtime
+                None => {
+                    complete = false;
+                    continue;
+                }
+            };
+            match signature.params.iter().find(|(p, _)| *p == param) {
+                Some((_, expected)) => {
+                    if borrowed != signature.takes_reference(&param) {
+                        self.report(TypeCheckError::BorrowMismatch(name.to_string(), param.clone()));
+                    }
+                    if let Err((variable, first, conflicting)) = expected.unify(&found, &mut bindings) {
+                        self.report(TypeCheckError::GenericBindingConflict(name.to_string(), variable, first, conflicting));
+                    } else if borrowed && found != Type::Any && expected.substitute(&bindings) != found {
+                        // the function assigns values of the param type to the variable
+                        self.report(TypeCheckError::ArgumentMismatch(name.to_string(), param.clone(), expected.clone(), found));
+                    } else if !assignable(&expected.substitute(&bindings), &found) {
+                        self.report(TypeCheckError::ArgumentMismatch(name.to_string(), param.clone(), expected.clone(), found));
+                    }
+                    bound.push(param);
+                }
+                None => self.report(TypeCheckError::UnknownArgument(name.to_string(), param)),
+            }
+        }
+        if complete {
+            for (param, _) in &signature.params {
+                if !bound.contains(param) {
+                    self.report(TypeCheckError::MissingArgument(name.to_string(), param.clone()));
+                }
+            }
+        }
+        signature.return_type.map(|type_| type_.substitute(&bindings)).unwrap_or(Type::Any)
+    }
+
+    /// Checks a call of a method of a list, map or string of type `collection`.
+    /// The methods that change it need it in `target`, a `mut` variable.
+    pub fn method(&mut self, target: Option<&str>, method: &str, collection: Type, args: Vec<(Option<String>, Type)>, borrows: Vec<bool>) -> Type {
+        let name = match (target, &collection) {
+            (Some(target), _) => format!("{}.{}", target, method),
+            (None, Type::Map(..)) => format!("map.{}", method),
+            (None, Type::String) => format!("string.{}", method),
+            (None, _) => format!("list.{}", method),
+        };
+        match collection {
+            Type::List(_) | Type::Map(..) | Type::String => {}
+            Type::Any | Type::Generic(_) => return Type::Any,
+            found => {
+                self.report(TypeCheckError::NotAFunction(name, found));
+                return Type::Any;
+            }
+        }
+        if let Some(i) = borrows.iter().position(|borrow| *borrow) {
+            let param = args[i].0.clone().unwrap_or_default();
+            self.report(TypeCheckError::BorrowMismatch(name.clone(), param));
+        }
+        let (params, mutates) = match &collection {
+            Type::Map(..) => (map::method_params(method), map::mutates(method)),
+            Type::String => (string::method_params(method), false),
+            _ => (list::method_params(method), list::mutates(method)),
+        };
+        let params = match params {
+            Some(params) => params,
+            None => {
+                self.report(TypeCheckError::NotAFunction(name, Type::Any));
+                return Type::Any;
+            }
+        };
+        match target {
+            Some(target) if mutates && self.binding(target).is_some_and(|binding| !binding.mutable) => {
+                self.report(TypeCheckError::CannotAssignToImmutableVariable(target.to_string()));
+            }
+            None if mutates => self.report(TypeCheckError::TemporaryReceiver(name.clone())),
+            _ => {}
+        }
+        let (key, element) = match &collection {
+            Type::List(element) => (Type::I64, *element.clone()),
+            Type::Map(key, value) => (*key.clone(), *value.clone()),
+            Type::String => (Type::String, Type::String),
+            _ => (Type::Any, Type::Any),
+        };
+        for (i, (param, found)) in args.into_iter().enumerate() {
+            let param = match param.or_else(|| params.get(i).map(|p| p.to_string())) {
+                Some(param) if params.contains(&param.as_str()) => param,
+                Some(param) => {
+                    self.report(TypeCheckError::UnknownArgument(name.clone(), param));
+                    continue;
+                }
+                None => continue,
+            };
+            let expected = match param.as_str() {
+                "value" => element.clone(),
+                "values" => Type::List(Box::new(Type::String)),
+                _ => key.clone(),
+            };
+            if !assignable(&expected, &found) {
+                self.report(TypeCheckError::ArgumentMismatch(name.clone(), param, expected, found));
+            }
+        }
+        match (&collection, method) {
+            (_, "len") => Type::I64,
+            (_, "contains") => Type::Bool,
+            (Type::String, "split") => Type::List(Box::new(Type::String)),
+            (Type::String, "find") => Type::Option(Box::new(Type::I64)),
+            (Type::String, "starts_with") => Type::Bool,
+            (Type::String, _) => Type::String,
+            (Type::List(_), "remove") => element,
+            (_, "pop" | "get" | "remove") => Type::Option(Box::new(element)),
+            (_, "keys") => Type::List(Box::new(key)),
+            (_, "values") => Type::List(Box::new(element)),
+            (_, "entries") => Type::List(Box::new(Type::Tuple(vec![key, element]))),
+            _ => Type::Option(Box::new(Type::Any)),
+        }
+    }
+
+    /// The type of the elements of a list of type `found`.
+    pub fn element_of(&mut self, found: Type) -> Type {
+        match found {
+            Type::List(element) => *element,
+            Type::Any | Type::Generic(_) => Type::Any,
+            found => {
+                self.report(TypeCheckError::NotAList(found));
+                Type::Any
+            }
+        }
+    }
+
+    pub fn index(&mut self, found: Type) {
+        if !found.is_integer() && found != Type::Any {
+            self.report(TypeCheckError::InvalidIndex(found));
+        }
+    }
+
+    /// The type of the element at `index` of a tuple of type `found`.
+    pub fn tuple_element(&mut self, found: Type, index: usize) -> Type {
+        match found {
+            Type::Tuple(types) => match types.get(index) {
+                Some(type_) => type_.clone(),
+                None => {
+                    self.report(TypeCheckError::NoTupleElement(Type::Tuple(types), index));
+                    Type::Any
+                }
+            },
+            Type::Any | Type::Generic(_) => Type::Any,
+            found => {
+                self.report(TypeCheckError::NotATuple(found));
+                Type::Any
+            }
+        }
+    }
+
+    /// The type of `collection[index]` for a list, map or string.
+    pub fn lookup_index(&mut self, collection: Type, index: Type) -> Type {
+        match collection {
+            Type::String => {
+                self.index(index);
+                Type::String
+            }
+            Type::Map(key, value) => {
+                if !assignable(&key, &index) {
+                    self.report(TypeCheckError::InvalidIndex(index));
+                }
+                *value
+            }
+            collection => {
+                self.index(index);
+                self.element_of(collection)
+            }
+        }
+    }
+
+    pub fn binary(&mut self, operator: &Operator, left: Type, right: Type) -> Type {
+        let comparison = matches!(operator, Operator::Eq | Operator::Neq | Operator::Gt | Operator::Gte | Operator::Lt | Operator::Lte | Operator::And | Operator::Or);
+        let (l, r) = match (category(&left), category(&right)) {
+            (Some(l), Some(r)) => (l, r),
+            _ => return if comparison { Type::Bool } else { Type::Any },
+        };
+        let numeric = matches!(l, Category::Int | Category::Float) && matches!(r, Category::Int | Category::Float);
+        let allowed = match operator {
+            Operator::Eq | Operator::Neq => l == r || numeric,
+            Operator::Add | Operator::Gt | Operator::Gte | Operator::Lt | Operator::Lte => numeric || (l == Category::String && r == Category::String),
+            // repetition
+            Operator::Mul if l == Category::String || r == Category::String => matches!((&l, &r), (Category::String, Category::Int) | (Category::Int, Category::String)),
+            Operator::Sub | Operator::Mul | Operator::Div | Operator::Mod | Operator::Pow => numeric,
+            Operator::BitAnd | Operator::BitOr | Operator::BitXor | Operator::BitShiftLeft | Operator::BitShiftRight => l == Category::Int && r == Category::Int,
+            Operator::And | Operator::Or => l == Category::Bool && r == Category::Bool,
+            _ => true,
+        };
+        if !allowed {
+            self.report(TypeCheckError::OperandMismatch(operator.clone(), left.clone(), right.clone()));
+        }
+        if comparison {
+            Type::Bool
+        } else if matches!(r, Category::Float | Category::String) && l == Category::Int {
+            right
+        } else {
+            left
+        }
+    }
+
+    pub fn unary(&mut self, operator: &Operator, operand: Type) -> Type {
+        let allowed = match (operator, category(&operand)) {
+            (_, None) => return operand,
+            (Operator::Sub, Some(category)) => matches!(category, Category::Int | Category::Float),
+            (Operator::BitNot, Some(category)) => category == Category::Int,
+            (Operator::Not, Some(category)) => category == Category::Bool,
+            _ => true,
+        };
+        if !allowed {
+            self.report(TypeCheckError::InvalidOperand(operator.clone(), operand.clone()));
+        }
+        operand
+    }
+}
+
+/// The names of a tuple pattern as written, like `a, (b, c)`.
+fn pattern_names(elements: &[Tuple<String>]) -> String {
+    elements.iter().map(|element| match element {
+        Tuple::Empty => "()".to_string(),
+        Tuple::Element(name) => name.clone(),
+        Tuple::List(inner) => format!("({})", pattern_names(inner)),
+    }).collect::<Vec<String>>().join(", ")
+}
+
+/// The kinds of values binary operations are defined between.
+#[derive(Debug, PartialEq)]
+enum Category {
+    Int,
+    Float,
+    String,
+    Bool,
+    Tuple,
+    List,
+    Map,
+}
+
+fn category(type_: &Type) -> Option<Category> {
+    match type_ {
+        Type::U8 | Type::U16 | Type::U32 | Type::U64 | Type::I16 | Type::I32 | Type::I64 => Some(Category::Int),
+        Type::F32 | Type::F64 => Some(Category::Float),
+        Type::String => Some(Category::String),
+        Type::Bool => Some(Category::Bool),
+        Type::Tuple(_) => Some(Category::Tuple),
+        Type::List(_) => Some(Category::List),
+        Type::Map(..) => Some(Category::Map),
+        _ => None,
+    }
+}
+
+/// Whether a value of type `found` may be stored where `expected` is declared.
+/// Integer ranges and the other checks that need the value are left to `check_type`.
+pub fn assignable(expected: &Type, found: &Type) -> bool {
+    match (expected, found) {
+        (Type::Any, _) | (_, Type::Any) => true,
+        (Type::Generic(_) | Type::UserDefined(_), _) | (_, Type::Generic(_) | Type::UserDefined(_)) => true,
+        (Type::Option(_), Type::Option(inner)) if **inner == Type::Any => true,
+        (Type::Option(expected), Type::Option(found)) => assignable(expected, found),
+        (Type::Option(expected), found) => assignable(expected, found),
+        (Type::Tuple(expected), Type::Tuple(found)) => expected.len() == found.len() && expected.iter().zip(found).all(|(e, f)| assignable(e, f)),
+        (Type::Task(expected), Type::Task(found)) => assignable(expected, found),
+        (Type::List(expected), Type::List(found)) => assignable(expected, found),
+        (Type::Map(key, value), Type::Map(found_key, found_value)) => assignable(key, found_key) && assignable(value, found_value),
+        (Type::FnType(_), Type::FnType(_)) => true,
+        (expected, found) => match (category(expected), category(found)) {
+            (Some(Category::Int), Some(Category::Int)) => true,
+            (Some(Category::Float), Some(Category::Int | Category::Float)) => true,
+            _ => expected == found,
+        },
+    }
+}
@@ -0,0 +1,274 @@
+#[cfg(test)]
+mod test_checker {
+    use crate::ast::type_node::Type;
+    use crate::checker::checker::{check, TypeCheckError};
+    use crate::lexer::token::Operator;
+    use crate::lexer::Lexer;
+    use crate::parser::parser::Parser;
+    use crate::{Error, Interpreter};
+
+    fn errors(interpreter: &Interpreter, source: &str) -> Vec<TypeCheckError> {
+        let mut lexer = Lexer::new(source.to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let ast = Parser::new(lexer.tokens.clone()).parse().unwrap();
+        match check(&interpreter.env, ast.as_ref()) {
+            Ok(()) => vec![],
+            Err(Error::TypeCheckError(errors)) => errors.into_iter().map(|(e, _)| e).collect(),
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_valid_program() {
+        let interpreter = Interpreter::new();
+        let source = "fn add(a: i32, b: i32) -> i32 { a + b }\n\
+            let x: i64 = add(1, 2)\n\
+            mut y: f64 = 5\n\
+            y := y * 2.5\n\
+            fn first(p: i32?) -> i32? { p? }\n\
+            let z := first(p: none) ?? 0\n\
+            if x > 2 { print(z) }\n\
+            async fn later(n: i32) -> i32 { n }\n\
+            let w := await later(3) + 1\n\
+            let max: u64 = 18446744073709551615\n\
+            let min: i64 = -9223372036854775808";
+        assert_eq!(errors(&interpreter, source), vec![]);
+    }
+
+    #[test]
+    fn test_reports_every_mismatch() {
+        let interpreter = Interpreter::new();
+        let source = "let s: string = 5\n\
+            fn greet(name: string) -> string { 'hi ' + name }\n\
+            greet(name: 3)\n\
+            fn bad() -> bool { 42 }\n\
+            if false {\nlet never: bool = 'x'\n}\n\
+            let k := 3\n\
+            k := 4\n\
+            if 1 { 2 }\n\
+            let q := 1 + 'a'";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::DeclarationMismatch("s".to_string(), Type::String, Type::I32),
+            TypeCheckError::ArgumentMismatch("greet".to_string(), "name".to_string(), Type::String, Type::I32),
+            TypeCheckError::ReturnMismatch("bad".to_string(), Type::Bool, Type::I32),
+            TypeCheckError::DeclarationMismatch("never".to_string(), Type::Bool, Type::String),
+            TypeCheckError::CannotAssignToImmutableVariable("k".to_string()),
+            TypeCheckError::ConditionNotBool(Type::I32),
+            TypeCheckError::OperandMismatch(Operator::Add, Type::I32, Type::String),
+        ]);
+    }
+
+    #[test]
+    fn test_calls_and_optionals() {
+        let interpreter = Interpreter::new();
+        let source = "fn area(w: i32, h: i32) -> i32 { w * h }\n\
+            area(w: 1, depth: 2)\n\
+            fn unwrap_or_zero(v: i32?) -> i32 { v? }\n\
+            let n := 4\n\
+            n(2)\n\
+            await n";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::UnknownArgument("area".to_string(), "depth".to_string()),
+            TypeCheckError::MissingArgument("area".to_string(), "h".to_string()),
+            TypeCheckError::ReturnMismatch("unwrap_or_zero".to_string(), Type::I32, Type::Option(Box::new(Type::Any))),
+            TypeCheckError::NotAFunction("n".to_string(), Type::I32),
+            TypeCheckError::NotATask(Type::I32),
+        ]);
+    }
+
+    #[test]
+    fn test_uses_existing_globals() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("mut count := 1").unwrap();
+        assert_eq!(errors(&interpreter, "count := 2\nsleep(ms: 5)"), vec![]);
+        assert_eq!(errors(&interpreter, "count := 'many'"), vec![
+            TypeCheckError::AssignmentMismatch("count".to_string(), Type::I32, Type::String),
+        ]);
+        assert_eq!(errors(&interpreter, "sleep(ms: 'long')"), vec![
+            TypeCheckError::ArgumentMismatch("sleep".to_string(), "ms".to_string(), Type::I64, Type::String),
+        ]);
+    }
+
+    #[test]
+    fn test_generic_calls() {
+        let interpreter = Interpreter::new();
+        let source = "fn first(t: (T, T)) -> T {\nlet a, b := t\na\n}\n\
+            let n: i32 = first((1, 2))\n\
+            let s: string = first((1, 2))\n\
+            first((1, 'x'))\n\
+            fn pick(a: T, b: T) -> T { a }\n\
+            let f: f64 = pick(1, 2.5)\n\
+            pick(a: true, b: 'no')";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::DeclarationMismatch("s".to_string(), Type::String, Type::I32),
+            TypeCheckError::GenericBindingConflict("first".to_string(), 'T', Type::I32, Type::String),
+            TypeCheckError::GenericBindingConflict("pick".to_string(), 'T', Type::Bool, Type::String),
+        ]);
+    }
+
+    #[test]
+    fn test_casts() {
+        let interpreter = Interpreter::new();
+        let source = "let a: u8 = 3\n\
+            let b: string = a as string\n\
+            let c: bool = (a as f64) > 2.5\n\
+            let d := (1, 2) as u8";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::InvalidCast(Type::Tuple(vec![Type::I32, Type::I32]), Type::U8),
+        ]);
+    }
+
+    #[test]
+    fn test_lists() {
+        let interpreter = Interpreter::new();
+        let source = "mut xs: [i32] = [1, 2]\n\
+            let n: i32 = xs[0] + xs.len()\n\
+            let rest: [i32] = xs[1:]\n\
+            xs.push('three')\n\
+            let fixed := [true]\n\
+            fixed.pop()\n\
+            let s: string = xs[0]\n\
+            let mixed := [1, 'a']\n\
+            let bad := n[0]\n\
+            xs['first']\n\
+            xs[0] := 'x'\n\
+            fixed[0] := false";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::ArgumentMismatch("xs.push".to_string(), "value".to_string(), Type::I32, Type::String),
+            TypeCheckError::CannotAssignToImmutableVariable("fixed".to_string()),
+            TypeCheckError::DeclarationMismatch("s".to_string(), Type::String, Type::I32),
+            TypeCheckError::ListElementMismatch(Type::I32, Type::String),
+            TypeCheckError::NotAList(Type::I32),
+            TypeCheckError::InvalidIndex(Type::String),
+            TypeCheckError::AssignmentMismatch("xs[]".to_string(), Type::I32, Type::String),
+            TypeCheckError::CannotAssignToImmutableVariable("fixed".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_maps() {
+        let interpreter = Interpreter::new();
+        let source = "mut ages := {'ann': 31}\n\
+            let n: i32 = ages['ann'] + ages.len()\n\
+            let names: [string] = ages.keys()\n\
+            let found: bool = 'bob' in ages\n\
+            ages.set('bob', 'old')\n\
+            ages[1]\n\
+            let fixed := {true: 1}\n\
+            fixed.remove(true)\n\
+            let mixed := {'a': 1, 'b': 'x'}\n\
+            let floats := {1.5: 'a'}";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::ArgumentMismatch("ages.set".to_string(), "value".to_string(), Type::I32, Type::String),
+            TypeCheckError::InvalidIndex(Type::I32),
+            TypeCheckError::CannotAssignToImmutableVariable("fixed".to_string()),
+            TypeCheckError::MapEntryMismatch(Type::I32, Type::String),
+            TypeCheckError::UnhashableKey(Type::F32),
+        ]);
+    }
+
+    #[test]
+    fn test_strings() {
+        let interpreter = Interpreter::new();
+        let source = "let name := 'ann'\n\
+            let before: bool = name < 'bob'\n\
+            let initial: string = name[0] + name[1:] * 2\n\
+            let parts: [string] = name.split(',')\n\
+            let at: i64? = name.find('n')\n\
+            name < 1\n\
+            name * 1.5\n\
+            name.split(1)\n\
+            name.shout()\n\
+            let upper: string = 'abc'.upper()\n\
+            let count: i64 = name.split(',')[0].len() + ('a' + name).len()\n\
+            let wrong: bool = name[0].upper()\n\
+            'abc'.shout()\n\
+            [1, 2].push(3)\n\
+            {'a': 1}.keys().len()";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::OperandMismatch(Operator::Lt, Type::String, Type::I32),
+            TypeCheckError::OperandMismatch(Operator::Mul, Type::String, Type::F32),
+            TypeCheckError::ArgumentMismatch("name.split".to_string(), "separator".to_string(), Type::String, Type::I32),
+            TypeCheckError::NotAFunction("name.shout".to_string(), Type::Any),
+            TypeCheckError::DeclarationMismatch("wrong".to_string(), Type::Bool, Type::String),
+            TypeCheckError::NotAFunction("string.shout".to_string(), Type::Any),
+            TypeCheckError::TemporaryReceiver("list.push".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_tuples() {
+        let interpreter = Interpreter::new();
+        let source = "let t := (1, 'a')\n\
+            mut p := (1, (2, 'b'))\n\
+            let n: i32 = t.0 + p.1.0\n\
+            p.1.1 := 'c'\n\
+            t.2\n\
+            n.0\n\
+            t.0 := 2\n\
+            p.1.0 := 'x'\n\
+            fn sum((a, b): (i32, i32)) -> i32 { a + b }\n\
+            sum(('a', 1))\n\
+            let x, (y, z) := (1, 2, 3)";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::NoTupleElement(Type::Tuple(vec![Type::I32, Type::String]), 2),
+            TypeCheckError::NotATuple(Type::I32),
+            TypeCheckError::CannotAssignToImmutableVariable("t".to_string()),
+            TypeCheckError::AssignmentMismatch("p.1.0".to_string(), Type::I32, Type::String),
+            TypeCheckError::ArgumentMismatch("sum".to_string(), "(a, b)".to_string(), Type::Tuple(vec![Type::I32, Type::I32]), Type::Tuple(vec![Type::String, Type::I32])),
+            TypeCheckError::TuplePatternMismatch("x, (y, z)".to_string(), 2, Type::Tuple(vec![Type::I32, Type::I32, Type::I32])),
+        ]);
+        assert_eq!(
+            TypeCheckError::TuplePatternMismatch("a, b".to_string(), 2, Type::I32).to_string(),
+            "`a, b` takes 2 values but is given i32"
+        );
+    }
+
+    #[test]
+    fn test_reference_params() {
+        let interpreter = Interpreter::new();
+        let source = "fn swap(mut a: i32, mut b: i32) {\n    let t := a\n    a := b\n    b := t\n}\n\
+            fn show(n: i32) -> string {\n    n := 0\n    '{n}'\n}\n\
+            mut x: i32 = 1\n\
+            mut y: i32 = 2\n\
+            let z: i32 = 3\n\
+            mut w: i64 = 4\n\
+            swap(mut x, mut y)\n\
+            swap(x, mut y)\n\
+            swap(mut x, mut z)\n\
+            swap(mut x, mut w)\n\
+            show(mut x)";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::CannotAssignToImmutableVariable("n".to_string()),
+            TypeCheckError::BorrowMismatch("swap".to_string(), "a".to_string()),
+            TypeCheckError::CannotAssignToImmutableVariable("z".to_string()),
+            TypeCheckError::ArgumentMismatch("swap".to_string(), "b".to_string(), Type::I32, Type::I64),
+            TypeCheckError::BorrowMismatch("show".to_string(), "n".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let interpreter = Interpreter::new();
+        let source = "mut i := 0\n\
+            mut s := 'a'\n\
+            mut p := (1, 'b')\n\
+            let c := 1\n\
+            i += 1\n\
+            s *= 2\n\
+            p.0 -= 1\n\
+            i += 0.5\n\
+            s -= 'a'\n\
+            p.1 += 1\n\
+            c += 1\n\
+            (i, s) += (1, 2)";
+        assert_eq!(errors(&interpreter, source), vec![
+            TypeCheckError::AssignmentMismatch("i".to_string(), Type::I32, Type::F32),
+            TypeCheckError::OperandMismatch(Operator::Sub, Type::String, Type::String),
+            TypeCheckError::OperandMismatch(Operator::Add, Type::String, Type::I32),
+            TypeCheckError::CannotAssignToImmutableVariable("c".to_string()),
+            TypeCheckError::OperandMismatch(Operator::Add, Type::String, Type::I32),
+        ]);
+    }
+}
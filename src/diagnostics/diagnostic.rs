@@ -0,0 +1,154 @@
+use crate::checker::checker::TypeCheckError;
+use crate::environment::environment::RuntimeError;
+use crate::environment::trace::{traceback, StackFrame};
+use crate::lexer::{LexerError, Span};
+use crate::parser::parser::ParseError;
+use crate::Error;
+use colored::{ColoredString, Colorize};
+
+/// An error as it is shown to a user: what went wrong, where in the source,
+/// and what might fix it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// The kind of error, like `syntax error`, shown as its severity.
+    pub label: &'static str,
+    pub message: String,
+    /// The code underlined, up to the end of its first line.
+    pub span: Option<Span>,
+    pub hints: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(label: &'static str, message: String) -> Self {
+        Self { label, message, span: None, hints: Vec::new() }
+    }
+
+    /// The diagnostics of `error`, one for each mismatch found by the type
+    /// checker and otherwise just one.
+    pub fn from_error(error: &Error) -> Vec<Diagnostic> {
+        match error {
+            Error::TypeCheckError(errors) => errors.iter()
+                .map(|(e, span)| Diagnostic { hints: type_check_hints(e), ..Diagnostic::new("type error", e.to_string()).at(*span) })
+                .collect(),
+            error => vec![Diagnostic { hints: hints(error), ..Diagnostic::new(label(error), error.to_string()) }],
+        }
+    }
+
+    pub fn at(self, span: Option<Span>) -> Self {
+        Self { span, ..self }
+    }
+
+    /// Points at the innermost call of `trace` that has a position, which is
+    /// the call the error was raised in, for errors raised where the source
+    /// is not known.
+    pub fn in_trace(self, trace: &[StackFrame]) -> Self {
+        match trace.iter().find_map(|frame| Some(Span::at(frame.position?, frame.function.chars().count()))) {
+            Some(span) => self.at(Some(span)),
+            None => self,
+        }
+    }
+
+    /// The message, the line of `source` it is at with the error underlined,
+    /// and the hints. Without `color` the text has no escape codes, for logs.
+    pub fn render(&self, source: Option<&str>, color: bool) -> String {
+        let paint = |text: &str, style: fn(&str) -> ColoredString| match color {
+            true => style(text).to_string(),
+            false => text.to_string(),
+        };
+        let mut lines = vec![format!("{}: {}", paint(self.label, |s| s.red().bold()), paint(&self.message, |s| s.bold()))];
+        let line = self.span.and_then(|span| Some((span, source?.lines().nth(span.start.line - 1)?)));
+        match line {
+            Some((span, text)) => {
+                let position = span.start;
+                let number = position.line.to_string();
+                let gutter = " ".repeat(number.len());
+                // tabs are kept so that the underline lines up with the text
+                let indent = text.chars().take(position.column - 1).map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+                let rest = text.chars().count().saturating_sub(position.column - 1);
+                let width = match span.end.line == position.line {
+                    true => span.end.column.saturating_sub(position.column).min(rest),
+                    false => rest,
+                };
+                lines.push(format!("{}{} {}", gutter, paint("-->", |s| s.blue().bold()), position));
+                lines.push(format!("{} {}", gutter, paint("|", |s| s.blue().bold())));
+                lines.push(format!("{} {} {}", paint(&number, |s| s.blue().bold()), paint("|", |s| s.blue().bold()), text));
+                lines.push(format!("{} {} {}{}", gutter, paint("|", |s| s.blue().bold()), indent, paint(&"^".repeat(width.max(1)), |s| s.red().bold())));
+            }
+            None => {
+                if let Some(span) = self.span {
+                    lines.push(format!(" {} {}", paint("-->", |s| s.blue().bold()), span.start));
+                }
+            }
+        }
+        for hint in &self.hints {
+            lines.push(format!(" {} {}: {}", paint("=", |s| s.blue().bold()), paint("hint", |s| s.cyan().bold()), hint));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Everything to show for `error` raised running `source`: the calls it went
+/// through, then its diagnostics. `span` is where the error came from, which
+/// is where lexing or parsing failed or the innermost expression raising it.
+pub fn report(error: &Error, source: &str, span: Option<Span>, trace: &[StackFrame], color: bool) -> String {
+    let mut sections = Vec::new();
+    if !trace.is_empty() {
+        sections.push(traceback(trace));
+    }
+    for diagnostic in Diagnostic::from_error(error) {
+        let diagnostic = match (diagnostic.span, span) {
+            (Some(_), _) => diagnostic,
+            (None, Some(span)) => diagnostic.at(Some(span)),
+            (None, None) => diagnostic.in_trace(trace),
+        };
+        sections.push(diagnostic.render(Some(source), color));
+    }
+    sections.join("\n")
+}
+
+fn label(error: &Error) -> &'static str {
+    match error {
+        Error::LexerError(_) | Error::ParserError(_) | Error::ASTError(_) => "syntax error",
+        Error::TypeCheckError(_) => "type error",
+        Error::RuntimeError(_) | Error::TupleError(_) | Error::TypeError(_) | Error::Return(_) | Error::TailCall(_) => "runtime error",
+        Error::PackageError(_) | Error::IoError(_) => "error",
+    }
+}
+
+fn hints(error: &Error) -> Vec<String> {
+    let hint = match error {
+        Error::LexerError(LexerError::InvalidStringLiteral(_)) => "a string ends with the quote it starts with".to_string(),
+        Error::ParserError(ParseError::NoMatchingBracket) => "every `(`, `[` and `{` needs a closing bracket".to_string(),
+        Error::ParserError(ParseError::NoMatchingBraceForKeyword(keyword)) => format!("the block of `{}` goes in braces", keyword.source()),
+        Error::ParserError(ParseError::AsyncReference(_)) => "a task runs on copies, so return the new value instead".to_string(),
+        Error::RuntimeError(e) => return runtime_hints(e),
+        _ => return Vec::new(),
+    };
+    vec![hint]
+}
+
+fn runtime_hints(error: &RuntimeError) -> Vec<String> {
+    let hint = match error {
+        RuntimeError::VariableNotFound(name) => format!("declare it first, like `let {} := ...`", name),
+        RuntimeError::CannotAssignToImmutableVariable(name) => format!("declare it with `mut {} := ...` to assign to it again", name),
+        RuntimeError::FunctionNotFound(_) => "check the name, or import the module it is in".to_string(),
+        RuntimeError::BorrowMismatch(_, param) => format!("pass `mut` to `{}` only if it is declared `mut`", param),
+        RuntimeError::UnwrapNone(_) => "use `?? default` to give a value for none".to_string(),
+        RuntimeError::Raised(_, _) => "catch it with `except`".to_string(),
+        RuntimeError::IntegerOverflow(_, _, _) => "use a wider type, or `wrapping_add` and the like".to_string(),
+        RuntimeError::NonFiniteFloat(_, _) => "raised because of `--strict-float`".to_string(),
+        RuntimeError::LossyCast(_, _) => "`as!` converts anyway, wrapping or truncating".to_string(),
+        RuntimeError::StackOverflow(_) => "make the recursive call a tail call, or raise the limit with `--max-depth=N`".to_string(),
+        _ => return Vec::new(),
+    };
+    vec![hint]
+}
+
+fn type_check_hints(error: &TypeCheckError) -> Vec<String> {
+    match error {
+        TypeCheckError::CannotAssignToImmutableVariable(name) => vec![format!("declare it with `mut {} := ...` to assign to it again", name)],
+        TypeCheckError::BorrowMismatch(_, param) => vec![format!("pass `mut` to `{}` only if it is declared `mut`", param)],
+        TypeCheckError::InvalidCast(_, _) => vec!["only numbers, `bool` and `string` can be cast".to_string()],
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod test_diagnostics {
+    use crate::ast::function::FnSignature;
+    use crate::ast::type_node::Type;
+    use crate::checker::checker::{check, TypeCheckError};
+    use crate::diagnostics::diagnostic::Diagnostic;
+    use crate::lexer::token::{Bracket, Token};
+    use crate::lexer::{Position, Span};
+    use crate::package::PackageError;
+    use crate::parser::parser::ParseError;
+    use crate::{Error, Interpreter};
+
+    fn error(interpreter: &mut Interpreter, source: &str) -> Error {
+        interpreter.eval_str(source).expect_err("expected an error")
+    }
+
+    /// How the error evaluating `source` is shown, without color.
+    fn rendered(source: &str) -> String {
+        let mut interpreter = Interpreter::new();
+        let e = error(&mut interpreter, source);
+        interpreter.render_error(&e, false)
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(error(&mut interpreter, "y + 1").to_string(), "no variable named `y`");
+        assert_eq!(error(&mut interpreter, "let b: u8 = 250\nb + 10").to_string(), "`250 + 10` overflows u8");
+        assert_eq!(error(&mut interpreter, "'a' - 1").to_string(), "cannot apply `-` to 'a' and 1");
+        assert_eq!(error(&mut interpreter, "[1, 2][5]").to_string(), "index 5 is out of bounds for length 2");
+        assert_eq!(Error::ParserError(ParseError::UnexpectedToken(Token::Bracket(Bracket::CloseParen))).to_string(), "unexpected `)`");
+
+        let signature = FnSignature { params: vec![("xs".to_string(), Type::List(Box::new(Type::I64)))], references: vec!["xs".to_string()], return_type: Some(Type::Option(Box::new(Type::String))) };
+        assert_eq!(Type::Map(Box::new(Type::String), Box::new(Type::FnType(Box::new(signature)))).to_string(), "{string: fn(mut xs: [i64]) -> string?}");
+    }
+
+    #[test]
+    fn test_render_lexer_error() {
+        assert_eq!(rendered("x := 1\ny := 'abc\nz := 2"), [
+            "syntax error: invalid string literal `'abc`",
+            " --> 2:6",
+            "  |",
+            "2 | y := 'abc",
+            "  |      ^",
+            " = hint: a string ends with the quote it starts with",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_render_parser_error() {
+        assert_eq!(rendered("x := 1\nif x > 0\n    print(x)"), [
+            "syntax error: expected a `{` block after `if`",
+            " --> 2:1",
+            "  |",
+            "2 | if x > 0",
+            "  | ^^",
+            " = hint: the block of `if` goes in braces",
+        ].join("\n"));
+        // a statement after the first starts with the newline before it
+        assert_eq!(rendered("let x := 1\n(1, 2"), [
+            "syntax error: unexpected end of input",
+            " --> 2:1",
+            "  |",
+            "2 | (1, 2",
+            "  | ^",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_render_type_error() {
+        let mut interpreter = Interpreter::new();
+        let ast = interpreter.parse("fn twice(n: i32) -> i32 { n * 2 }\nlet s: string = 5\nlet t := twice(n: 'a') + 1").unwrap();
+        let e = check(&interpreter.env, ast.as_ref()).expect_err("expected type errors");
+        assert_eq!(interpreter.render_error(&e, false), [
+            "type error: `s` is declared string but given i32",
+            " --> 2:1",
+            "  |",
+            "2 | let s: string = 5",
+            "  | ^^^^^^^^^^^^^^^^^",
+            "type error: param `n` of `twice` is i32 but given string",
+            " --> 3:10",
+            "  |",
+            "3 | let t := twice(n: 'a') + 1",
+            "  |          ^^^^^^^^^^^^^",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_render_runtime_error() {
+        assert_eq!(rendered("let x := 1\nx + y * 2"), [
+            "runtime error: no variable named `y`",
+            " --> 2:5",
+            "  |",
+            "2 | x + y * 2",
+            "  |     ^",
+            " = hint: declare it first, like `let y := ...`",
+        ].join("\n"));
+        assert_eq!(rendered("let xs := [1, 2]\nlet n := xs[5] + 1"), [
+            "runtime error: index 5 is out of bounds for length 2",
+            " --> 2:10",
+            "  |",
+            "2 | let n := xs[5] + 1",
+            "  |          ^^^^^",
+        ].join("\n"));
+
+        // the expression raising the error rather than the call it is in
+        let source = "fn parse_age(text: string) -> i64 {\n\tif text == '' { error EmptyField('age') }\n\t1\n}\nfn main() -> i64 {\n\tparse_age('') + 1\n}\nmain()";
+        assert_eq!(rendered(source), [
+            "Traceback, most recent call last:",
+            "  main() at 8:1",
+            "  parse_age(text: '') at 6:2",
+            "runtime error: uncaught error EmptyField('age')",
+            " --> 2:18",
+            "  |",
+            "2 | \tif text == '' { error EmptyField('age') }",
+            "  | \t                ^^^^^^^^^^^^^^^^^^^^^^^",
+            " = hint: catch it with `except`",
+        ].join("\n"));
+        // destructuring fails while the program runs, not while it is parsed
+        assert!(rendered("let t := (1, 2, 3)\nlet a, b := t").starts_with("runtime error: the names and values of the tuple do not pair up\n --> 2:1"));
+    }
+
+    #[test]
+    fn test_render_other_errors() {
+        let e = Error::PackageError(PackageError::Io("disk full".to_string()));
+        assert_eq!(Interpreter::new().render_error(&e, false), format!("error: {}", e));
+    }
+
+    #[test]
+    fn test_type_check_diagnostics() {
+        let error = Error::TypeCheckError(vec![
+            (TypeCheckError::DeclarationMismatch("x".to_string(), Type::I64, Type::String), None),
+            (TypeCheckError::CannotAssignToImmutableVariable("x".to_string()), None),
+        ]);
+        let diagnostics = Diagnostic::from_error(&error);
+        assert_eq!(diagnostics.iter().map(|d| d.render(None, false)).collect::<Vec<_>>(), vec![
+            "type error: `x` is declared i64 but given string".to_string(),
+            "type error: cannot assign twice to immutable variable `x`\n = hint: declare it with `mut x := ...` to assign to it again".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_render_color() {
+        let diagnostic = Diagnostic::new("runtime error", "no variable named `y`".to_string()).at(Some(Span::at(Position { line: 1, column: 1 }, 1)));
+        colored::control::set_override(true);
+        let colored = diagnostic.render(Some("y + 1"), true);
+        colored::control::unset_override();
+        assert!(colored.contains("\u{1b}["));
+        assert!(!diagnostic.render(Some("y + 1"), false).contains('\u{1b}'));
+    }
+}
@@ -1,16 +1,21 @@
 use crate::ast::assignment::AssignmentError;
 use crate::ast::value::Value;
+use crate::ast::ASTNode;
 use crate::ast::type_node::Type;
 use crate::ast::binary_operation::BinaryOperationError;
 use crate::ast::unary_operation::UnaryOperationError;
-use std::collections::HashMap;
 use crate::Error;
-use crate::lexer::{get_string_interpolations};
+use crate::lexer::token::Operator;
+use crate::lexer::{get_string_interpolations, Span};
 use crate::parser::parser::Parser;
 use std::rc::Rc;
 use crate::environment::heap::{Heap, VariableHeap};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use crate::environment::frame::Frame;
 use crate::environment::variable::Variable;
+use crate::package::Project;
+use crate::environment::task::TaskRuntime;
+use crate::environment::trace::{summarize, StackFrame};
 
 #[derive(Debug)]
 pub enum RuntimeError {
@@ -28,20 +33,152 @@ pub enum RuntimeError {
     NoVariableAtHeapIndex(usize),
     FunctionNotFound(String),
     InvalidFunctionCall,
+    /// Function and param, when `mut x` is passed to a param that is not
+    /// `mut`, or anything else to one that is.
+    BorrowMismatch(String, String),
     CannotDeclareAnonymousFunction,
     UnaryOperationError(UnaryOperationError),
-}   
+    ModuleNotFound(String),
+    ModuleMemberNotFound(String, String),
+    UnsupportedImportScheme(String),
+    ArgumentCountMismatch(String, usize, usize),
+    UnwrapNone(String),
+    Raised(String, Value),
+    TaskNotFound(usize),
+    TaskAwaitsItself(usize),
+    ChannelNotFound(usize),
+    ChannelEmpty(usize),
+    /// Function, type variable, first binding, conflicting binding.
+    GenericBindingConflict(String, char, Type, Type),
+    /// Operator, operands, type the result does not fit in.
+    IntegerOverflow(Operator, Vec<Value>, Type),
+    DivisionByZero(Operator, Vec<Value>),
+    /// A shift by a negative amount or by at least the width of the type.
+    InvalidShift(Operator, Vec<Value>, Type),
+    NegativeExponent(Operator, Vec<Value>),
+    /// A string repeated a negative number of times.
+    NegativeRepetition(Operator, Vec<Value>),
+    /// A NaN or infinite result, only raised with `--strict-float`.
+    NonFiniteFloat(Operator, Vec<Value>),
+    InvalidCast(Value, Type),
+    /// A checked cast of a value the type cannot represent exactly.
+    LossyCast(Value, Type),
+    /// Index, length of the list or tuple.
+    IndexOutOfBounds(i128, usize),
+    KeyNotFound(Value),
+    /// A float, list, map or other value used as a map key.
+    UnhashableKey(Value),
+    /// A map type with a key type whose values cannot be map keys.
+    UnhashableType(Type),
+    /// A call of the function nested deeper than the max call depth.
+    StackOverflow(String),
+    /// The value of the condition of an `if`, `elif` or `while`.
+    ConditionNotBool(Value),
+    /// A method that changes its receiver, called on a value in no variable.
+    TemporaryReceiver(String),
+}
 
-#[derive(Clone, Debug, Default)]
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeError::VariableNotFound(name) => write!(f, "no variable named `{}`", name),
+            RuntimeError::VariableNotInitialized(name) => write!(f, "`{}` is used before it is given a value", name),
+            RuntimeError::ValueOutOfBounds(value, type_) => write!(f, "{} does not fit in {}", value, type_),
+            RuntimeError::ValueNotOfType(value, type_) => write!(f, "{} is not a {}", value, type_),
+            RuntimeError::TupleLengthMismatch(expected, found) => write!(f, "expected a tuple of {} elements, found {}", expected, found),
+            RuntimeError::CannotAssignToImmutableVariable(name) => write!(f, "cannot assign twice to immutable variable `{}`", name),
+            RuntimeError::CannotInferType(value) => write!(f, "cannot infer the type of {}", value),
+            RuntimeError::TypeNotImplemented(type_) => write!(f, "type {} is not implemented", type_),
+            RuntimeError::ValueNotOfTupleType(value, types) => write!(f, "{} is not a {}", value, Type::Tuple(types.clone())),
+            RuntimeError::BinaryOperationError(e) => write!(f, "{}", e),
+            RuntimeError::AssignmentError(e) => write!(f, "{}", e),
+            RuntimeError::NoVariableAtHeapIndex(index) => write!(f, "no variable at heap index {}", index),
+            RuntimeError::FunctionNotFound(name) => write!(f, "no function named `{}`", name),
+            RuntimeError::InvalidFunctionCall => write!(f, "invalid function call"),
+            RuntimeError::BorrowMismatch(function, param) => write!(f, "`mut` does not match param `{}` of `{}`", param, function),
+            RuntimeError::CannotDeclareAnonymousFunction => write!(f, "a function declared as a statement needs a name"),
+            RuntimeError::UnaryOperationError(e) => write!(f, "{}", e),
+            RuntimeError::ModuleNotFound(module) => write!(f, "module `{}` not found", module),
+            RuntimeError::ModuleMemberNotFound(module, member) => write!(f, "module `{}` has no member `{}`", module, member),
+            RuntimeError::UnsupportedImportScheme(path) => write!(f, "cannot import `{}`", path),
+            RuntimeError::ArgumentCountMismatch(function, expected, found) => write!(f, "`{}` takes {} arguments but {} were given", function, expected, found),
+            RuntimeError::UnwrapNone(expr) => write!(f, "`{}` is none", expr),
+            RuntimeError::Raised(kind, Value::None) => write!(f, "uncaught error {}", kind),
+            RuntimeError::Raised(kind, payload) => write!(f, "uncaught error {}({})", kind, summarize(payload)),
+            RuntimeError::TaskNotFound(id) => write!(f, "no task #{}", id),
+            RuntimeError::TaskAwaitsItself(id) => write!(f, "task #{} awaits itself", id),
+            RuntimeError::ChannelNotFound(id) => write!(f, "no channel #{}", id),
+            RuntimeError::ChannelEmpty(id) => write!(f, "channel #{} is empty and no task can send to it", id),
+            RuntimeError::GenericBindingConflict(function, variable, first, second) => {
+                write!(f, "`{}` binds {} to both {} and {}", function, variable, first, second)
+            }
+            RuntimeError::IntegerOverflow(operator, operands, type_) => write!(f, "`{}` overflows {}", operation(operator, operands), type_),
+            RuntimeError::DivisionByZero(operator, operands) => write!(f, "division by zero in `{}`", operation(operator, operands)),
+            RuntimeError::InvalidShift(operator, operands, type_) => write!(f, "`{}` shifts past the width of {}", operation(operator, operands), type_),
+            RuntimeError::NegativeExponent(operator, operands) => write!(f, "negative exponent in `{}`", operation(operator, operands)),
+            RuntimeError::NegativeRepetition(operator, operands) => write!(f, "negative repetition in `{}`", operation(operator, operands)),
+            RuntimeError::NonFiniteFloat(operator, operands) => write!(f, "`{}` is not a finite float", operation(operator, operands)),
+            RuntimeError::InvalidCast(value, type_) => write!(f, "cannot cast {} to {}", summarize(value), type_),
+            RuntimeError::LossyCast(value, type_) => write!(f, "{} cannot be cast to {} exactly", summarize(value), type_),
+            RuntimeError::IndexOutOfBounds(index, length) => write!(f, "index {} is out of bounds for length {}", index, length),
+            RuntimeError::KeyNotFound(key) => write!(f, "no entry for key {}", summarize(key)),
+            RuntimeError::UnhashableKey(key) => write!(f, "{} cannot be a map key", summarize(key)),
+            RuntimeError::UnhashableType(type_) => write!(f, "{} cannot be the key type of a map", type_),
+            RuntimeError::StackOverflow(function) => write!(f, "stack overflow in `{}`", function),
+            RuntimeError::ConditionNotBool(value) => write!(f, "condition {} is not a bool", summarize(value)),
+            RuntimeError::TemporaryReceiver(call) => write!(f, "`{}` changes its receiver, which has to be a variable", call),
+        }
+    }
+}
+
+/// `operator` applied to `operands` as it would be written.
+fn operation(operator: &Operator, operands: &[Value]) -> String {
+    match operands {
+        [operand] if *operator == Operator::Not => format!("not {}", summarize(operand)),
+        [operand] => format!("{}{}", operator, summarize(operand)),
+        operands => operands.iter().map(summarize).collect::<Vec<_>>().join(&format!(" {} ", operator)),
+    }
+}
+
+/// How deep calls can nest unless configured otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;   
+
+#[derive(Clone, Debug)]
 pub struct Environment {
-    pub local_variables: HashMap<String, Variable>,
-    pub parent: Option<Rc<RefCell<Environment>>>,
+    /// The variables of the innermost scope. Shared with the environments
+    /// running in the same scope.
+    pub frame: Rc<RefCell<Frame>>,
+    /// The outermost frame of `frame`, where globals and functions are
+    /// declared.
+    pub globals: Rc<RefCell<Frame>>,
     pub heap: Rc<RefCell<VariableHeap>>,
+    pub project: Option<Rc<Project>>,
+    pub tasks: Rc<RefCell<TaskRuntime>>,
+    /// Raise `NonFiniteFloat` instead of producing NaN or infinity.
+    pub strict_float: bool,
+    /// Evaluate with the bytecode VM instead of walking the AST.
+    pub bytecode: bool,
+    /// The calls the error being raised has left, innermost first.
+    pub trace: Rc<RefCell<Vec<StackFrame>>>,
+    /// The innermost expression the error being raised came from.
+    pub error_span: Rc<Cell<Option<Span>>>,
+    /// How many function calls this environment is nested in.
+    pub depth: usize,
+    /// Calls nested deeper than this raise `StackOverflow`.
+    pub max_depth: usize,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
 }   
 
 #[derive(Clone, Debug)]
 pub enum ReferenceOrValue {
     Reference(usize, String),
+    /// A variable passed as `mut`, with its name.
+    Borrow(Variable, String),
     Value(Value),
 }
 
@@ -53,25 +190,24 @@ impl ReferenceOrValue {
 
     pub fn from_reference(env: &mut Environment, name: &str) -> Result<Self, Error> {
         let var = env.get_variable(name)?;
-        let value = env.heap.borrow().get(var.index).unwrap().clone();
-        if var.type_.is_basic() {
-            Ok(Self::Value(value.clone()))
-        } else {
-            Ok(Self::Reference(var.index, name.to_string()))
-        }
+        Ok(Self::Reference(var.index, name.to_string()))
     }
 
     pub fn is_reference(&self) -> bool {
         matches!(self, Self::Reference(_, _))
     }
 
+    pub fn is_borrow(&self) -> bool {
+        matches!(self, Self::Borrow(_, _))
+    }
+
     pub fn is_value(&self) -> bool {
         matches!(self, Self::Value(_))
     }
 
     pub fn eval(&self, env: &mut Environment) -> Result<Value, Error> {
         match self {
-            Self::Reference(index, _) => {
+            Self::Reference(index, _) | Self::Borrow(Variable { index, .. }, _) => {
                 let value = env.heap.borrow().get(*index).unwrap().clone();
                 Ok(value)
             }
@@ -81,28 +217,86 @@ impl ReferenceOrValue {
 }
 
 impl Environment {
-    pub fn new(parent: Option<Rc<RefCell<Environment>>>, heap: Option<Rc<RefCell<VariableHeap>>>) -> Self {
-        let heap = match heap {
-            Some(heap) => heap,
-            None => match &parent {
-                Some(p) => Rc::clone(&p.borrow().heap),
-                None => Rc::new(RefCell::new(VariableHeap::new())),
-            }
-        };
-        Self { local_variables: HashMap::new(), parent, heap }
+    pub fn new(parent: Option<Rc<RefCell<Frame>>>, heap: Option<Rc<RefCell<VariableHeap>>>) -> Self {
+        let heap = heap.unwrap_or_else(|| Rc::new(RefCell::new(VariableHeap::new())));
+        let mut globals = parent.clone();
+        while let Some(parent) = globals.as_ref().and_then(|frame| frame.borrow().parent.clone()) {
+            globals = Some(parent);
+        }
+        let frame = Rc::new(RefCell::new(Frame::new(parent)));
+        heap.borrow_mut().register(&frame);
+        Self {
+            globals: globals.unwrap_or_else(|| Rc::clone(&frame)),
+            frame,
+            heap,
+            project: None,
+            tasks: Rc::new(RefCell::new(TaskRuntime::default())),
+            strict_float: false,
+            bytecode: false,
+            trace: Rc::new(RefCell::new(Vec::new())),
+            error_span: Rc::new(Cell::new(None)),
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// The calls the last error raised left, innermost first, which are
+    /// forgotten along with where it came from so that the next error starts
+    /// a trace of its own.
+    pub fn take_trace(&self) -> Vec<StackFrame> {
+        self.error_span.set(None);
+        std::mem::take(&mut self.trace.borrow_mut())
+    }
+
+    /// Records `span` as where `error` was raised, unless an expression
+    /// inside it already is or the error only unwinds to a call.
+    pub fn locate(&self, span: Option<Span>, error: Error) -> Error {
+        if span.is_some() && self.error_span.get().is_none() && !matches!(error, Error::Return(_) | Error::TailCall(_)) {
+            self.error_span.set(span);
+        }
+        error
     }
 
     pub fn free(&self) {
         let mut heap = self.heap.borrow_mut();
-        for (_, variable) in &self.local_variables {
+        for (_, variable) in self.frame.borrow().variables() {
             heap.deallocate(variable.index);
         }
     }
 
+    /// An environment for a nested scope. Its frame refers to this one's, so
+    /// what it assigns to the variables of this scope stays assigned.
     pub fn new_child(&self) -> Self {
-        let parent = Some(Rc::new(RefCell::new(self.clone())));
-        let heap = Rc::clone(&self.heap);
-        Self::new(parent, Some(heap))
+        self.with_frame(Some(Rc::clone(&self.frame)))
+    }
+
+    /// Makes the variables declared from now on go in a new frame inside
+    /// the current one, until `exit_scope`. Like `new_child`, without
+    /// another environment.
+    pub fn enter_scope(&mut self) {
+        let frame = Rc::new(RefCell::new(Frame::new(Some(Rc::clone(&self.frame)))));
+        self.heap.borrow_mut().register(&frame);
+        self.frame = frame;
+    }
+
+    /// Frees the variables of the frame `enter_scope` made and goes back to
+    /// the one around it.
+    pub fn exit_scope(&mut self) {
+        self.free();
+        let parent = self.frame.borrow().parent.clone();
+        if let Some(parent) = parent {
+            self.frame = parent;
+        }
+    }
+
+    /// An environment sharing everything with this one but its variables,
+    /// which go in a new frame inside `parent`, a frame of this environment.
+    pub fn with_frame(&self, parent: Option<Rc<RefCell<Frame>>>) -> Self {
+        let outermost = parent.is_none();
+        let frame = Rc::new(RefCell::new(Frame::new(parent)));
+        self.heap.borrow_mut().register(&frame);
+        let globals = if outermost { Rc::clone(&frame) } else { Rc::clone(&self.globals) };
+        Self { frame, globals, ..self.clone() }
     }
 
     pub fn interpolate(&mut self, value: Value) -> Result<Value, Error> {
@@ -110,7 +304,8 @@ impl Environment {
             Value::String(ref s, false) => {
                 let interpolations = get_string_interpolations(s);
                 let mut s = s.clone();
-                for (interpolation, index) in interpolations {
+                // from the end, so that the positions before stay valid
+                for (interpolation, index) in interpolations.into_iter().rev() {
                     let node = Parser::parse_string(&interpolation)?;
                     let value = node.eval(self)?;
                     s = s[..index].to_string() + &value.to_string() + &s[index+interpolation.len()+2..];
@@ -121,8 +316,9 @@ impl Environment {
         }
     }
 
-    pub fn add_reference(&mut self, name: &str, index: usize) -> Result<(), Error> {
-        self.local_variables.insert(name.to_string(), Variable { index, ..self.get_variable(name)? });
-        Ok(())
+    /// Declares `name` as another name for `variable`, so that assigning to
+    /// one assigns to the other.
+    pub fn add_reference(&mut self, name: &str, variable: Variable) {
+        self.frame.borrow_mut().insert(name.to_string(), variable);
     }
 }
\ No newline at end of file
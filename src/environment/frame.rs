@@ -0,0 +1,63 @@
+use crate::environment::variable::Variable;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The variables declared in one scope. Environments share their frames, so
+/// an assignment made in a block is seen by the scopes around it.
+#[derive(Debug, Default)]
+pub struct Frame {
+    /// In declaration order. Redeclaring a name reuses its slot.
+    variables: Vec<(String, Variable)>,
+    slots: HashMap<String, usize>,
+    pub parent: Option<Rc<RefCell<Frame>>>,
+}
+
+/// Where the resolver found a variable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Address {
+    /// `depth` frames out from the scope it is used in, at `slot` in that frame.
+    Local { depth: usize, slot: usize },
+    /// Not declared in the function or block it is used in, so it is looked
+    /// for in the outermost frame first, without going through the frames
+    /// of the calls in between.
+    Global,
+}
+
+impl Frame {
+    pub fn new(parent: Option<Rc<RefCell<Frame>>>) -> Self {
+        Self { variables: Vec::new(), slots: HashMap::new(), parent }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Variable> {
+        self.slots.get(name).map(|slot| &self.variables[*slot].1)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Variable> {
+        self.slots.get(name).map(|slot| &mut self.variables[*slot].1)
+    }
+
+    /// The variable at `slot`, if it is called `name`.
+    pub fn slot(&self, slot: usize, name: &str) -> Option<&Variable> {
+        self.variables.get(slot).filter(|(declared, _)| declared == name).map(|(_, variable)| variable)
+    }
+
+    pub fn slot_mut(&mut self, slot: usize, name: &str) -> Option<&mut Variable> {
+        self.variables.get_mut(slot).filter(|(declared, _)| declared == name).map(|(_, variable)| variable)
+    }
+
+    /// Declares `name`, replacing a variable of the same name in this frame.
+    pub fn insert(&mut self, name: String, variable: Variable) {
+        match self.slots.get(&name) {
+            Some(slot) => self.variables[*slot].1 = variable,
+            None => {
+                self.slots.insert(name.clone(), self.variables.len());
+                self.variables.push((name, variable));
+            }
+        }
+    }
+
+    pub fn variables(&self) -> impl Iterator<Item = (&String, &Variable)> {
+        self.variables.iter().map(|(name, variable)| (name, variable))
+    }
+}
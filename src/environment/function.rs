@@ -1,67 +1,210 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 use crate::ast::function::FnDeclaration;
 use crate::ast::type_node::Type;
 use crate::ast::Value;
 use crate::environment::environment::{Environment, RuntimeError, ReferenceOrValue};
+use crate::environment::frame::Address;
 use crate::environment::heap::Heap;
-use crate::environment::variable::{check_type, Variable};
+use crate::environment::trace::StackFrame;
+use crate::environment::variable::{check_type, coerce, Variable};
+use crate::lexer::Position;
 use crate::Error;
 
+/// A call whose value is the value of the function it is made in, made by
+/// the caller of that function in place of the call it returns from.
+#[derive(Debug)]
+pub struct TailCall {
+    pub name: String,
+    /// Where the resolver found the function.
+    pub address: Option<Address>,
+    pub args: HashMap<String, ReferenceOrValue>,
+    /// The function called, as seen where the call is made.
+    pub function: Variable,
+    /// Where the call is made, which makes it when the caller sees a
+    /// different function by the name.
+    pub env: Environment,
+    /// The return type of the function the call is made in.
+    pub return_type: Option<Type>,
+    pub position: Option<Position>,
+}
+
 impl Environment {
 
     pub fn declare_function(&mut self, declaration: FnDeclaration) -> Result<(), Error> {
-        let index = self.heap.borrow_mut().allocate(Value::Fn(Box::new(declaration.body.clone())));
+        let index = self.heap.borrow_mut().allocate(Value::Fn(Rc::clone(&declaration.body)));
         let type_ = Type::FnType(Box::new(declaration.signature()));
         let name = match declaration.name {
             Some(name) => name,
             None => return Err(Error::RuntimeError(RuntimeError::CannotDeclareAnonymousFunction)),
         };
-        self.local_variables.insert(name, Variable { initialized: true, index, mutable: false, type_ });
+        self.frame.borrow_mut().insert(name, Variable { initialized: true, index, mutable: false, type_ });
         Ok(())
     }
 
     pub fn call(&mut self, name: &str, args: HashMap<String, ReferenceOrValue>) -> Result<Value, Error> {
         let function = self.get_variable(name)?;
-        let mut env = self.new_child();
-        for (param, reference_or_value) in args.clone() {
-            match reference_or_value {
-                ReferenceOrValue::Reference(index, _) => {
-                    env.add_reference(&param, index)?;
-                }
-                ReferenceOrValue::Value(value) => {
-                    env.declare_assign(param, value.clone(), false, None)?;
+        self.call_at(name, function, args, None)
+    }
+
+    /// Calls `function`, found by `name`, from `position` in the source,
+    /// which a traceback of an error raised in the call shows.
+    pub fn call_at(&mut self, name: &str, function: Variable, args: HashMap<String, ReferenceOrValue>, position: Option<Position>) -> Result<Value, Error> {
+        if self.depth >= self.max_depth {
+            return Err(Error::RuntimeError(RuntimeError::StackOverflow(name.to_string())));
+        }
+        let mut result = grow_stack(|| self.call_once(name, &function, args, position));
+        // the return types of the calls replaced by tail calls, outermost first
+        let mut returns: Vec<Type> = Vec::new();
+        loop {
+            let call = match result {
+                Err(Error::TailCall(call)) => *call,
+                result => return returns.into_iter().rev().try_fold(result?, |value, type_| coerce(type_, value)),
+            };
+            if let Some(type_) = call.return_type {
+                if returns.last() != Some(&type_) {
+                    returns.push(type_);
                 }
             }
+            let TailCall { name, address, args, function, mut env, position, .. } = call;
+            result = match self.variable_at(&name, address) {
+                // it only replaces the call when this caller sees the same function
+                Ok(seen) if seen.index == function.index => match self.tail_scope(&env, &function.type_) {
+                    Some(mut scope) => grow_stack(|| scope.call_once(&name, &function, args, position)),
+                    None => env.call_at(&name, function, args, position),
+                },
+                _ => env.call_at(&name, function, args, position),
+            };
         }
-        let signature = match function.type_ {
-            Type::FnType(signature) => signature.clone(),
-            _ => return Err(Error::RuntimeError(RuntimeError::FunctionNotFound(name.to_string()))),
+    }
+
+    /// The environment a tail call from `env`, which is inside this one, is
+    /// made from in place of `env`. Scoping is dynamic, so it has the
+    /// variables of the frames it replaces that the params of `function` do
+    /// not hide, gathered in one frame so that a loop of tail calls does not
+    /// nest them.
+    fn tail_scope(&self, env: &Environment, function: &Type) -> Option<Environment> {
+        let params = match function {
+            Type::FnType(signature) => &signature.params,
+            _ => return None,
         };
-        self.params_match(signature.params, args)?;
-        let body = match self.lookup(name)? {
-            Value::Fn(body) => body,
+        let mut visible: Vec<(String, Variable)> = Vec::new();
+        let mut frame = Rc::clone(&env.frame);
+        while !Rc::ptr_eq(&frame, &self.frame) {
+            let parent = {
+                let current = frame.borrow();
+                for (name, variable) in current.variables() {
+                    if !params.iter().any(|(param, _)| param == name) && !visible.iter().any(|(seen, _)| seen == name) {
+                        visible.push((name.clone(), variable.clone()));
+                    }
+                }
+                current.parent.clone()?
+            };
+            frame = parent;
+        }
+        if visible.is_empty() {
+            return Some(self.clone());
+        }
+        let scope = self.new_child();
+        for (name, variable) in visible {
+            scope.frame.borrow_mut().insert(name, variable);
+        }
+        Some(scope)
+    }
+
+    /// Makes a call, leaving a tail call in the function to the caller.
+    fn call_once(&mut self, name: &str, function: &Variable, mut args: HashMap<String, ReferenceOrValue>, position: Option<Position>) -> Result<Value, Error> {
+        let signature = match &function.type_ {
+            Type::FnType(signature) => signature.clone(),
             _ => return Err(Error::RuntimeError(RuntimeError::FunctionNotFound(name.to_string()))),
         };
-        let result = body.eval(&mut env)?;
-        Ok(result)
+        for (param, arg) in &args {
+            if arg.is_borrow() != signature.takes_reference(param) {
+                return Err(Error::RuntimeError(RuntimeError::BorrowMismatch(name.to_string(), param.clone())));
+            }
+        }
+        let bindings = self.params_match(&signature.params, &args)
+            .map_err(|e| match e {
+                Error::RuntimeError(RuntimeError::GenericBindingConflict(_, variable, bound, found)) => {
+                    Error::RuntimeError(RuntimeError::GenericBindingConflict(name.to_string(), variable, bound, found))
+                }
+                e => e,
+            })?;
+        let body = self.heap.borrow().get(function.index).cloned();
+        let result = match body.ok_or(Error::RuntimeError(RuntimeError::NoVariableAtHeapIndex(function.index)))? {
+            Value::Fn(body) => {
+                let mut env = self.new_child();
+                env.depth = self.depth + 1;
+                // in the order of the signature, which gives the params the slots the resolver expects
+                for (param, type_) in &signature.params {
+                    match args.remove(param) {
+                        Some(ReferenceOrValue::Borrow(variable, _)) => env.add_reference(param, variable),
+                        Some(arg) => {
+                            let value = arg.eval(self)?;
+                            env.declare_assign(param.clone(), value, false, Some(type_.substitute(&bindings)))?;
+                        }
+                        None => {}
+                    }
+                }
+                match env.evaluate_scope(&body) {
+                    Err(Error::Return(value)) => Ok(value),
+                    Err(Error::TailCall(mut call)) => {
+                        call.return_type = signature.return_type.map(|type_| type_.substitute(&bindings));
+                        return Err(Error::TailCall(call));
+                    }
+                    Err(error) => {
+                        let args = signature.params.iter().filter_map(|(param, _)| Some((param.clone(), env.lookup(param).ok()?))).collect();
+                        self.trace.borrow_mut().push(StackFrame::new(name, args, position));
+                        Err(error)
+                    }
+                    result => result,
+                }
+            }
+            Value::NativeFn(native) => {
+                let mut values = Vec::new();
+                for (param, _) in &signature.params {
+                    values.push(args[param].eval(self)?);
+                }
+                match native.call(values) {
+                    Err(error) => {
+                        let args = signature.params.iter().filter_map(|(param, _)| Some((param.clone(), args[param].eval(self).ok()?))).collect();
+                        self.trace.borrow_mut().push(StackFrame::new(name, args, position));
+                        Err(error)
+                    }
+                    result => result,
+                }
+            }
+            _ => Err(Error::RuntimeError(RuntimeError::FunctionNotFound(name.to_string()))),
+        }?;
+        match signature.return_type {
+            Some(return_type) => coerce(return_type.substitute(&bindings), result),
+            None => Ok(result),
+        }
     }
 
-    fn params_match(&mut self, params: HashMap<String, Type>, args: HashMap<String, ReferenceOrValue>) -> Result<(), Error> {
+    /// Checks the arguments against the params and returns what each type
+    /// variable of the signature was bound to by this call.
+    fn params_match(&mut self, params: &[(String, Type)], args: &HashMap<String, ReferenceOrValue>) -> Result<HashMap<char, Type>, Error> {
+        let mut bindings = HashMap::new();
         for (param, type_) in params {
-            let reference_or_value = match args.get(&param) {
-                Some(reference_or_value) => reference_or_value,
+            let value = match args.get(param) {
+                Some(reference_or_value) => reference_or_value.eval(self)?,
                 None => return Err(Error::RuntimeError(RuntimeError::VariableNotFound(param.to_string()))),
             };
-            match reference_or_value {
-                ReferenceOrValue::Reference(_, _) => {
-                    let value = reference_or_value.eval(self)?;
-                    check_type(type_, value.clone())?;
-                }
-                ReferenceOrValue::Value(value) => {
-                    check_type(type_, value.clone())?;
-                }
+            let found = self.infer_type(value.clone()).unwrap_or(Type::Any);
+            if let Err((variable, bound, found)) = type_.unify(&found, &mut bindings) {
+                return Err(Error::RuntimeError(RuntimeError::GenericBindingConflict(String::new(), variable, bound, found)));
             }
+            check_type(type_.substitute(&bindings), value)?;
         }
-        Ok(())
+        Ok(bindings)
     }
-}
\ No newline at end of file
+}
+/// Runs `f`, the evaluation of a call, on a new segment of stack when less
+/// than `RED_ZONE` is left, so that the depth of calls is limited by
+/// `max_depth` rather than by the stack of the thread running it.
+fn grow_stack<T>(f: impl FnOnce() -> T) -> T {
+    const RED_ZONE: usize = 256 * 1024;
+    const SEGMENT: usize = 4 * 1024 * 1024;
+    stacker::maybe_grow(RED_ZONE, SEGMENT, f)
+}
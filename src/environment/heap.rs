@@ -1,8 +1,19 @@
 use crate::ast::value::Value;
+use crate::environment::frame::Frame;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
 
 #[derive(Clone, Debug)]
 pub struct GcBox<T> {
     value: T,
+    /// Set while a collection finds the box reachable.
+    marked: bool,
+}
+
+impl<T> GcBox<T> {
+    fn new(value: T) -> Self {
+        Self { value, marked: false }
+    }
 }
 
 pub trait Heap<T : std::fmt::Debug> {
@@ -26,16 +37,106 @@ pub trait Heap<T : std::fmt::Debug> {
     }
 }
 
+/// The live boxes a heap starts collecting at.
+const MIN_THRESHOLD: usize = 1024;
+
+/// What a heap holds, and what collecting it has freed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GcStats {
+    pub live: usize,
+    /// Empty slots waiting to be reused.
+    pub free: usize,
+    /// Boxes freed by the last collection.
+    pub freed: usize,
+    pub collections: usize,
+}
+
+impl std::fmt::Display for GcStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} live, {} free, {} freed by the last of {} collections", self.live, self.free, self.freed, self.collections)
+    }
+}
 
-#[derive(Clone, Debug, Default)]
+/// The values of every variable. Boxes are freed when their scope ends, and
+/// the ones that outlive it, such as the params of a call, by a tracing
+/// collection: every box no live frame has a variable in is unreachable.
+#[derive(Clone, Debug)]
 pub struct VariableHeap {
     boxes: Vec<Option<GcBox<Value>>>,
+    /// Empty slots, reused before the heap grows.
+    free: Vec<usize>,
+    /// The frames created with this heap. The ones still alive are the roots
+    /// of a collection.
+    frames: Vec<Weak<RefCell<Frame>>>,
+    /// Allocating with this many live boxes collects first.
+    threshold: usize,
+    stats: GcStats,
+}
+
+impl Default for VariableHeap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VariableHeap {
     pub fn new() -> Self {
-        Self { boxes: Vec::new() }
-    }    
+        Self { boxes: Vec::new(), free: Vec::new(), frames: Vec::new(), threshold: MIN_THRESHOLD, stats: GcStats::default() }
+    }
+
+    /// Makes the variables of `frame` roots of collections while it is alive.
+    pub fn register(&mut self, frame: &Rc<RefCell<Frame>>) {
+        if self.frames.len() == self.frames.capacity() {
+            self.frames.retain(|frame| frame.strong_count() > 0);
+            // grow with the live frames, so that dropping the dead ones stays amortized O(1)
+            self.frames.reserve(self.frames.len());
+        }
+        self.frames.push(Rc::downgrade(frame));
+    }
+
+    pub fn stats(&self) -> GcStats {
+        GcStats { free: self.free.len(), ..self.stats }
+    }
+
+    /// Frees every box no live frame has a variable in.
+    pub fn collect(&mut self) -> GcStats {
+        self.frames.retain(|frame| frame.strong_count() > 0);
+        let mut roots = Vec::new();
+        for frame in self.frames.iter().filter_map(Weak::upgrade) {
+            match frame.try_borrow() {
+                Ok(frame) => roots.extend(frame.variables().map(|(_, variable)| variable.index)),
+                // a frame being declared in cannot be traced; the next allocation tries again
+                Err(_) => return self.stats(),
+            }
+        }
+        for index in roots {
+            if let Some(Some(box_)) = self.boxes.get_mut(index) {
+                box_.marked = true;
+            }
+        }
+        let mut freed = 0;
+        for (index, slot) in self.boxes.iter_mut().enumerate() {
+            match slot {
+                Some(box_) if box_.marked => box_.marked = false,
+                Some(_) => {
+                    *slot = None;
+                    self.free.push(index);
+                    freed += 1;
+                }
+                None => {}
+            }
+        }
+        while let Some(None) = self.boxes.last() {
+            self.boxes.pop();
+        }
+        let len = self.boxes.len();
+        self.free.retain(|index| *index < len);
+        self.stats.live -= freed;
+        self.stats.freed = freed;
+        self.stats.collections += 1;
+        self.threshold = MIN_THRESHOLD.max(self.stats.live * 2);
+        self.stats()
+    }
 }
 
 impl Heap<Value> for VariableHeap {
@@ -45,19 +146,28 @@ impl Heap<Value> for VariableHeap {
     }
 
     fn allocate(&mut self, value: Value) -> usize {
-        // Try to reuse a free slot
-        if let Some((i, slot)) = self.boxes.iter_mut().enumerate().find(|(_, slot)| slot.is_none()) {
-            *slot = Some(GcBox { value });
-            i
-        } else {
-            self.boxes.push(Some(GcBox { value }));
-            self.boxes.len() - 1
+        if self.stats.live >= self.threshold {
+            self.collect();
+        }
+        self.stats.live += 1;
+        match self.free.pop() {
+            Some(index) => {
+                self.boxes[index] = Some(GcBox::new(value));
+                index
+            }
+            None => {
+                self.boxes.push(Some(GcBox::new(value)));
+                self.boxes.len() - 1
+            }
         }
     }
 
     fn deallocate(&mut self, index: usize) {
         if let Some(slot) = self.boxes.get_mut(index) {
-            *slot = None;
+            if slot.take().is_some() {
+                self.free.push(index);
+                self.stats.live -= 1;
+            }
         }
     }
 
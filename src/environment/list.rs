@@ -0,0 +1,148 @@
+use crate::ast::assignment::AssignmentError;
+use crate::ast::list::position;
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::environment::heap::Heap;
+use crate::environment::variable::coerce;
+use crate::Error;
+
+/// The params of the methods of lists, `None` for a method that does not exist.
+pub fn method_params(method: &str) -> Option<&'static [&'static str]> {
+    match method {
+        "push" => Some(&["value"]),
+        "pop" | "len" => Some(&[]),
+        "insert" => Some(&["index", "value"]),
+        "remove" => Some(&["index"]),
+        _ => None,
+    }
+}
+
+/// Whether the method changes the list, which needs a `mut` variable.
+pub fn mutates(method: &str) -> bool {
+    matches!(method, "push" | "pop" | "insert" | "remove")
+}
+
+impl Environment {
+    /// Calls `method` on the list held by the variable `name`, changing it in
+    /// its heap slot so that every reference to it sees the change.
+    pub fn call_list_method(&mut self, name: &str, method: &str, args: Vec<(Option<String>, Value)>) -> Result<Value, Error> {
+        let call = format!("{}.{}", name, method);
+        let params = method_params(method).ok_or_else(|| Error::RuntimeError(RuntimeError::FunctionNotFound(call.clone())))?;
+        let args = bind(&call, params, args)?;
+        let variable = self.get_variable(name)?;
+        if mutates(method) && !variable.mutable {
+            return Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name.to_string())));
+        }
+        let element = match &variable.type_ {
+            Type::List(element) => *element.clone(),
+            _ => Type::Any,
+        };
+        let mut heap = self.heap.borrow_mut();
+        let values = match heap.get_mut(variable.index) {
+            Some(Value::List(values)) => values,
+            Some(value) => return Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::List(Box::new(Type::Any))))),
+            None => return Err(Error::RuntimeError(RuntimeError::NoVariableAtHeapIndex(variable.index))),
+        };
+        apply_method(values, element, method, args)
+    }
+}
+
+/// Calls `method` on `values`, a list of `element`, with the arguments bound
+/// to its params.
+pub fn apply_method(values: &mut Vec<Value>, element: Type, method: &str, args: Vec<Value>) -> Result<Value, Error> {
+    let mut args = args.into_iter();
+    let mut next = || args.next().unwrap_or(Value::None);
+    match method {
+        "push" => {
+            values.push(coerce(element, next())?);
+            Ok(Value::None)
+        }
+        "pop" => Ok(values.pop().unwrap_or(Value::None)),
+        "insert" => {
+            let index = next();
+            // inserting at the length appends
+            let index = position(&index, values.len() + 1)?;
+            values.insert(index, coerce(element, next())?);
+            Ok(Value::None)
+        }
+        "remove" => {
+            let index = position(&next(), values.len())?;
+            Ok(values.remove(index))
+        }
+        _ => Ok(Value::Int(values.len() as i64)),
+    }
+}
+
+impl Environment {
+    /// Sets the element at `indexes` of the list or map held by the variable
+    /// `name`, in its heap slot. The indexes before the last one pick the
+    /// list or map to set it in. The last one may be a new key of a map.
+    pub fn assign_index(&mut self, name: &str, indexes: &[Value], value: Value) -> Result<(), Error> {
+        let variable = self.get_variable(name)?;
+        if !variable.mutable {
+            return Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name.to_string())));
+        }
+        let (last, path) = match indexes.split_last() {
+            Some(split) => split,
+            None => return Err(Error::RuntimeError(RuntimeError::AssignmentError(AssignmentError::CannotAssignToEmptyName))),
+        };
+        let mut heap = self.heap.borrow_mut();
+        let mut target = match heap.get_mut(variable.index) {
+            Some(value) => value,
+            None => return Err(Error::RuntimeError(RuntimeError::NoVariableAtHeapIndex(variable.index))),
+        };
+        let mut type_ = variable.type_;
+        for index in path {
+            target = match target {
+                Value::List(values) => {
+                    let i = position(index, values.len())?;
+                    &mut values[i]
+                }
+                Value::Map(map) => match map.get_mut(index)? {
+                    Some(value) => value,
+                    None => return Err(Error::RuntimeError(RuntimeError::KeyNotFound(index.clone()))),
+                },
+                value => return Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::List(Box::new(Type::Any))))),
+            };
+            type_ = match type_ {
+                Type::List(element) | Type::Map(_, element) => *element,
+                _ => Type::Any,
+            };
+        }
+        match (target, type_) {
+            (Value::List(values), type_) => {
+                let i = position(last, values.len())?;
+                let element = match type_ {
+                    Type::List(element) => *element,
+                    _ => Type::Any,
+                };
+                values[i] = coerce(element, value)?;
+            }
+            (Value::Map(map), Type::Map(key_type, value_type)) => {
+                map.insert(coerce(*key_type, last.clone())?, coerce(*value_type, value)?)?;
+            }
+            (Value::Map(map), _) => {
+                map.insert(last.clone(), value)?;
+            }
+            (value, _) => return Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::List(Box::new(Type::Any))))),
+        }
+        Ok(())
+    }
+}
+
+/// Orders the arguments of a method call like its params, by name or by position.
+pub fn bind(call: &str, params: &[&str], args: Vec<(Option<String>, Value)>) -> Result<Vec<Value>, Error> {
+    if args.len() != params.len() {
+        return Err(Error::RuntimeError(RuntimeError::ArgumentCountMismatch(call.to_string(), params.len(), args.len())));
+    }
+    let mut bound = vec![None; params.len()];
+    for (i, (param, value)) in args.into_iter().enumerate() {
+        let i = match param {
+            Some(param) => params.iter().position(|p| *p == param).ok_or(Error::RuntimeError(RuntimeError::InvalidFunctionCall))?,
+            None => i,
+        };
+        bound[i] = Some(value);
+    }
+    bound.into_iter().collect::<Option<Vec<_>>>().ok_or(Error::RuntimeError(RuntimeError::InvalidFunctionCall))
+}
@@ -0,0 +1,69 @@
+use crate::ast::map::Map;
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::environment::heap::Heap;
+use crate::environment::list::bind;
+use crate::environment::variable::coerce;
+use crate::Error;
+
+/// The params of the methods of maps, `None` for a method that does not exist.
+pub fn method_params(method: &str) -> Option<&'static [&'static str]> {
+    match method {
+        "get" | "remove" | "contains" => Some(&["key"]),
+        "set" => Some(&["key", "value"]),
+        "len" | "keys" | "values" | "entries" => Some(&[]),
+        _ => None,
+    }
+}
+
+/// Whether the method changes the map, which needs a `mut` variable.
+pub fn mutates(method: &str) -> bool {
+    matches!(method, "set" | "remove")
+}
+
+impl Environment {
+    /// Calls `method` on the map held by the variable `name`, changing it in
+    /// its heap slot so that every reference to it sees the change.
+    pub fn call_map_method(&mut self, name: &str, method: &str, args: Vec<(Option<String>, Value)>) -> Result<Value, Error> {
+        let call = format!("{}.{}", name, method);
+        let params = method_params(method).ok_or_else(|| Error::RuntimeError(RuntimeError::FunctionNotFound(call.clone())))?;
+        let args = bind(&call, params, args)?;
+        let variable = self.get_variable(name)?;
+        if mutates(method) && !variable.mutable {
+            return Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name.to_string())));
+        }
+        let (key_type, value_type) = match &variable.type_ {
+            Type::Map(key_type, value_type) => (*key_type.clone(), *value_type.clone()),
+            _ => (Type::Any, Type::Any),
+        };
+        let mut heap = self.heap.borrow_mut();
+        let map = match heap.get_mut(variable.index) {
+            Some(Value::Map(map)) => map,
+            Some(value) => return Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::Map(Box::new(Type::Any), Box::new(Type::Any))))),
+            None => return Err(Error::RuntimeError(RuntimeError::NoVariableAtHeapIndex(variable.index))),
+        };
+        apply_method(map, key_type, value_type, method, args)
+    }
+}
+
+/// Calls `method` on `map`, a map of `key_type` to `value_type`, with the
+/// arguments bound to its params.
+pub fn apply_method(map: &mut Map, key_type: Type, value_type: Type, method: &str, args: Vec<Value>) -> Result<Value, Error> {
+    let mut args = args.into_iter();
+    let mut next = || args.next().unwrap_or(Value::None);
+    match method {
+        "get" => Ok(map.get(&next())?.cloned().unwrap_or(Value::None)),
+        "contains" => Ok(Value::Bool(map.contains(&next())?)),
+        "set" => {
+            let key = coerce(key_type, next())?;
+            map.insert(key, coerce(value_type, next())?)?;
+            Ok(Value::None)
+        }
+        "remove" => Ok(map.remove(&next())?.unwrap_or(Value::None)),
+        "keys" => Ok(Value::List(map.entries().iter().map(|(key, _)| key.clone()).collect())),
+        "values" => Ok(Value::List(map.entries().iter().map(|(_, value)| value.clone()).collect())),
+        "entries" => Ok(Value::List(map.entries().iter().map(|(key, value)| Value::Tuple(vec![key.clone(), value.clone()])).collect())),
+        _ => Ok(Value::Int(map.len() as i64)),
+    }
+}
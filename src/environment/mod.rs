@@ -1,4 +1,13 @@
 pub mod environment; 
 pub mod heap;
+pub mod frame;
 pub mod variable;
-pub mod function;
\ No newline at end of file
+pub mod function;
+pub mod module;
+pub mod native;
+pub mod task;
+pub mod trace;
+pub mod list;
+pub mod map;
+pub mod string;
+pub mod test_environment;
\ No newline at end of file
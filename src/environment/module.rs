@@ -0,0 +1,69 @@
+use crate::ast::ASTNode;
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::lexer::Lexer;
+use crate::package::PackageError;
+use crate::parser::parser::Parser;
+use crate::stdlib::{self, StdModule};
+use crate::Error;
+use std::rc::Rc;
+
+impl Environment {
+
+    pub fn import(&mut self, path: &str, alias: Option<&str>, names: Option<&[String]>) -> Result<(), Error> {
+        let mut module_env = Environment::new(None, Some(Rc::clone(&self.heap)));
+        module_env.project = self.project.clone();
+        module_env.tasks = Rc::clone(&self.tasks);
+        module_env.strict_float = self.strict_float;
+        match path.split_once(':') {
+            Some(("std", name)) => match stdlib::get_module(name) {
+                Some(StdModule::Rust(register)) => register(&mut module_env),
+                Some(StdModule::Loop(source)) => module_env.eval_module_source(source)?,
+                None => return Err(Error::RuntimeError(RuntimeError::ModuleNotFound(path.to_string()))),
+            },
+            Some(("git", url)) => {
+                let source = match &self.project {
+                    Some(project) => project.module_source(url)?,
+                    None => return Err(Error::PackageError(PackageError::NoProject(path.to_string()))),
+                };
+                module_env.eval_module_source(&source)?;
+            }
+            _ => return Err(Error::RuntimeError(RuntimeError::UnsupportedImportScheme(path.to_string()))),
+        }
+
+        match names {
+            Some(names) => {
+                for name in names {
+                    let variable = match module_env.frame.borrow().get(name) {
+                        Some(variable) => variable.clone(),
+                        None => return Err(Error::RuntimeError(RuntimeError::ModuleMemberNotFound(path.to_string(), name.clone()))),
+                    };
+                    self.frame.borrow_mut().insert(name.clone(), variable);
+                }
+            }
+            None => {
+                let prefix = alias.unwrap_or_else(|| default_alias(path));
+                for (name, variable) in module_env.frame.borrow().variables() {
+                    self.frame.borrow_mut().insert(format!("{}.{}", prefix, name), variable.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn eval_module_source(&mut self, source: &str) -> Result<(), Error> {
+        let mut lexer = Lexer::new(source.to_string());
+        lexer.tokenize().map_err(Error::LexerError)?;
+        lexer.clean_tokens();
+        // without spans, since they would be shown in the source importing it
+        let mut parser = Parser::new(lexer.tokens.clone());
+        parser.parse()?.eval(self)?;
+        Ok(())
+    }
+}
+
+pub fn default_alias(path: &str) -> &str {
+    // 'std:os' is bound as `os`, 'git:github.com/user/mymodule' as `mymodule`
+    let path = path.split_once(':').map_or(path, |(_, rest)| rest);
+    let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or(path);
+    name.strip_suffix(".git").unwrap_or(name)
+}
@@ -0,0 +1,47 @@
+use crate::ast::function::FnSignature;
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::Environment;
+use crate::environment::heap::Heap;
+use crate::environment::variable::Variable;
+use crate::Error;
+use std::rc::Rc;
+
+/// A function implemented in Rust. It receives the evaluated arguments in the
+/// order of its signature's params.
+#[derive(Clone)]
+pub struct NativeFn {
+    pub name: String,
+    pub func: Rc<dyn Fn(Vec<Value>) -> Result<Value, Error>>,
+}
+
+impl NativeFn {
+    pub fn new(name: &str, func: impl Fn(Vec<Value>) -> Result<Value, Error> + 'static) -> Self {
+        Self { name: name.to_string(), func: Rc::new(func) }
+    }
+
+    pub fn call(&self, args: Vec<Value>) -> Result<Value, Error> {
+        (self.func)(args)
+    }
+}
+
+impl std::fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFn({})", self.name)
+    }
+}
+
+impl PartialEq for NativeFn {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.func, &other.func)
+    }
+}
+
+impl Environment {
+
+    pub fn register_native(&mut self, name: &str, signature: FnSignature, func: impl Fn(Vec<Value>) -> Result<Value, Error> + 'static) {
+        let index = self.heap.borrow_mut().allocate(Value::NativeFn(NativeFn::new(name, func)));
+        let type_ = Type::FnType(Box::new(signature));
+        self.frame.borrow_mut().insert(name.to_string(), Variable { initialized: true, index, mutable: false, type_ });
+    }
+}
@@ -0,0 +1,80 @@
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::environment::list::bind;
+use crate::Error;
+
+/// The params of the methods of strings, `None` for a method that does not exist.
+pub fn method_params(method: &str) -> Option<&'static [&'static str]> {
+    match method {
+        "len" | "trim" | "upper" | "lower" => Some(&[]),
+        "split" => Some(&["separator"]),
+        "join" => Some(&["values"]),
+        "replace" => Some(&["old", "new"]),
+        "contains" | "starts_with" | "find" => Some(&["value"]),
+        _ => None,
+    }
+}
+
+fn text(value: Value) -> Result<String, Error> {
+    match value {
+        Value::String(s, _) => Ok(s),
+        value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::String))),
+    }
+}
+
+impl Environment {
+    /// Calls `method` on the string held by the variable `name`. Lengths and
+    /// positions count characters, and the strings returned are raw when the
+    /// string is.
+    pub fn call_string_method(&mut self, name: &str, method: &str, args: Vec<(Option<String>, Value)>) -> Result<Value, Error> {
+        let call = format!("{}.{}", name, method);
+        let params = method_params(method).ok_or_else(|| Error::RuntimeError(RuntimeError::FunctionNotFound(call.clone())))?;
+        let args = bind(&call, params, args)?;
+        match self.lookup(name)? {
+            Value::String(s, raw) => apply_method(&s, raw, method, args),
+            value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::String))),
+        }
+    }
+}
+
+/// Calls `method` on `s` with the arguments bound to its params.
+pub fn apply_method(s: &str, raw: bool, method: &str, args: Vec<Value>) -> Result<Value, Error> {
+    let mut args = args.into_iter();
+    let mut next = || text(args.next().unwrap_or(Value::None));
+    let string = |s: &str| Value::String(s.to_string(), raw);
+    match method {
+        "len" => Ok(Value::Int(s.chars().count() as i64)),
+        "trim" => Ok(string(s.trim())),
+        "upper" => Ok(string(&s.to_uppercase())),
+        "lower" => Ok(string(&s.to_lowercase())),
+        "split" => {
+            let separator = next()?;
+            // an empty separator splits into characters
+            let parts = if separator.is_empty() {
+                s.chars().map(|c| string(&c.to_string())).collect()
+            } else {
+                s.split(separator.as_str()).map(string).collect()
+            };
+            Ok(Value::List(parts))
+        }
+        "join" => {
+            let values = match args.next().unwrap_or(Value::None) {
+                Value::List(values) => values,
+                value => return Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::List(Box::new(Type::String))))),
+            };
+            let parts = values.into_iter().map(text).collect::<Result<Vec<_>, _>>()?;
+            Ok(string(&parts.join(s)))
+        }
+        "replace" => {
+            let (old, new) = (next()?, next()?);
+            Ok(string(&s.replace(&old, &new)))
+        }
+        "contains" => Ok(Value::Bool(s.contains(&next()?))),
+        "starts_with" => Ok(Value::Bool(s.starts_with(&next()?))),
+        _ => match s.find(&next()?) {
+            Some(byte) => Ok(Value::Int(s[..byte].chars().count() as i64)),
+            None => Ok(Value::None),
+        },
+    }
+}
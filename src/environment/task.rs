@@ -0,0 +1,394 @@
+use crate::ast::error::catchable;
+use crate::ast::scope::Scope;
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::environment::heap::Heap;
+use crate::environment::variable::{coerce, Variable};
+use crate::Error;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::panic::AssertUnwindSafe;
+use std::rc::Rc;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Tasks and channels shared by every environment of a program.
+///
+/// Scheduling is cooperative and deterministic. Each task runs on a stack of
+/// its own, and suspends at `await`, `recv` and `sleep` so that the tasks
+/// waiting longest that can go on run in the meantime. Time is virtual: when
+/// nothing can run, the clock moves forward to the first `sleep` to end.
+pub struct TaskRuntime {
+    tasks: Vec<Task>,
+    channels: Vec<VecDeque<Value>>,
+    /// The running task, or `None` for the main program.
+    current: Option<usize>,
+    clock: u64,
+    /// The tasks, and the main program, that are suspended, in the order
+    /// they were, with what they wait for.
+    waiting: VecDeque<(Option<usize>, Wait)>,
+    /// The error a resumed task or main program raises where it waited.
+    woken: Option<Error>,
+    baton: Arc<Baton>,
+}
+
+enum TaskState {
+    Pending(Scope, Option<Type>, Environment),
+    Running,
+    /// The result and the time the task finished at.
+    Done(Result<Value, (String, Value)>, u64),
+}
+
+struct Task {
+    state: TaskState,
+}
+
+#[derive(Debug)]
+enum Wait {
+    Start,
+    Until(u64),
+    Message(usize),
+    Task(usize),
+    Any(Vec<usize>),
+}
+
+/// Which of the threads running the main program and the tasks may go on.
+/// Only the one holding it touches the runtime, so the `Rc`s the threads
+/// share are never used by two at once.
+#[derive(Default)]
+struct Baton {
+    turn: Mutex<Option<usize>>,
+    changed: Condvar,
+}
+
+impl Baton {
+    fn pass(&self, to: Option<usize>) {
+        *self.turn.lock().unwrap() = to;
+        self.changed.notify_all();
+    }
+
+    fn wait(&self, fiber: Option<usize>) {
+        let mut turn = self.turn.lock().unwrap();
+        while *turn != fiber {
+            turn = self.changed.wait(turn).unwrap();
+        }
+    }
+}
+
+impl Default for TaskRuntime {
+    fn default() -> Self {
+        Self {
+            tasks: Vec::new(),
+            channels: Vec::new(),
+            current: None,
+            clock: 0,
+            waiting: VecDeque::new(),
+            woken: None,
+            baton: Arc::new(Baton::default()),
+        }
+    }
+}
+
+impl std::fmt::Debug for TaskRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TaskRuntime({} tasks, {} channels, t={})", self.tasks.len(), self.channels.len(), self.clock)
+    }
+}
+
+impl TaskRuntime {
+    /// Virtual time, the same for every task.
+    pub fn now(&self) -> u64 {
+        self.clock
+    }
+
+    pub fn spawn(&mut self, body: Scope, return_type: Option<Type>, env: Environment) -> usize {
+        self.tasks.push(Task { state: TaskState::Pending(body, return_type, env) });
+        let id = self.tasks.len() - 1;
+        self.waiting.push_back((Some(id), Wait::Start));
+        id
+    }
+
+    pub fn channel(&mut self) -> usize {
+        self.channels.push(VecDeque::new());
+        self.channels.len() - 1
+    }
+
+    pub fn send(&mut self, channel: usize, value: Value) -> Result<(), Error> {
+        match self.channels.get_mut(channel) {
+            Some(queue) => {
+                queue.push_back(value);
+                Ok(())
+            }
+            None => Err(Error::RuntimeError(RuntimeError::ChannelNotFound(channel))),
+        }
+    }
+
+    fn take_message(&mut self, channel: usize) -> Result<Option<Value>, Error> {
+        match self.channels.get_mut(channel) {
+            Some(queue) => Ok(queue.pop_front()),
+            None => Err(Error::RuntimeError(RuntimeError::ChannelNotFound(channel))),
+        }
+    }
+
+    fn done(&self, id: usize) -> bool {
+        matches!(self.tasks[id].state, TaskState::Done(..))
+    }
+
+    fn ready(&self, wait: &Wait) -> bool {
+        match wait {
+            Wait::Start => true,
+            Wait::Until(time) => self.clock >= *time,
+            Wait::Message(channel) => !self.channels[*channel].is_empty(),
+            Wait::Task(id) => self.done(*id),
+            Wait::Any(ids) => ids.iter().any(|id| self.done(*id)),
+        }
+    }
+
+    /// Takes the suspended task or main program to run next: the one that
+    /// has waited longest of those that can go on, once the clock is moved
+    /// to the first `sleep` to end if none can. When nothing can go on at
+    /// all, the last one to be suspended is resumed with an error, so that a
+    /// task failing that way ends the waits on it.
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            if let Some(i) = self.waiting.iter().position(|(_, wait)| self.ready(wait)) {
+                let (fiber, _) = self.waiting.remove(i).unwrap();
+                return fiber;
+            }
+            let wake = self.waiting.iter().filter_map(|(_, wait)| match wait {
+                Wait::Until(time) => Some(*time),
+                _ => None,
+            }).min();
+            match wake {
+                Some(time) => self.clock = time,
+                None => break,
+            }
+        }
+        // the main program is always running or waiting, so something is
+        let (fiber, wait) = self.waiting.pop_back().unwrap();
+        self.woken = Some(Error::RuntimeError(match wait {
+            Wait::Message(channel) => RuntimeError::ChannelEmpty(channel),
+            Wait::Task(id) => RuntimeError::TaskAwaitsItself(id),
+            Wait::Any(ids) => RuntimeError::TaskAwaitsItself(ids[0]),
+            Wait::Start | Wait::Until(_) => unreachable!(),
+        }));
+        fiber
+    }
+
+    fn check_task(&self, id: usize) -> Result<(), Error> {
+        if id < self.tasks.len() {
+            Ok(())
+        } else {
+            Err(Error::RuntimeError(RuntimeError::TaskNotFound(id)))
+        }
+    }
+
+    fn check_channel(&self, channel: usize) -> Result<(), Error> {
+        if channel < self.channels.len() {
+            Ok(())
+        } else {
+            Err(Error::RuntimeError(RuntimeError::ChannelNotFound(channel)))
+        }
+    }
+}
+
+/// Suspends the running task, or the main program, until `wait` is over,
+/// running the others meanwhile. Raises when nothing that could end the wait
+/// can run anymore.
+fn suspend(runtime: &Rc<RefCell<TaskRuntime>>, wait: Wait) -> Result<(), Error> {
+    let (fiber, next, baton) = {
+        let mut rt = runtime.borrow_mut();
+        let fiber = rt.current;
+        rt.waiting.push_back((fiber, wait));
+        (fiber, rt.next(), Arc::clone(&rt.baton))
+    };
+    if next != fiber {
+        start(runtime, next);
+        baton.pass(next);
+        baton.wait(fiber);
+    }
+    let mut rt = runtime.borrow_mut();
+    rt.current = fiber;
+    match rt.woken.take() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Starts a task about to be handed over to on a thread of its own, unless
+/// it has started already. The thread waits for the baton.
+fn start(runtime: &Rc<RefCell<TaskRuntime>>, fiber: Option<usize>) {
+    let pending = {
+        let mut rt = runtime.borrow_mut();
+        match fiber {
+            Some(id) if matches!(rt.tasks[id].state, TaskState::Pending(..)) => {
+                match std::mem::replace(&mut rt.tasks[id].state, TaskState::Running) {
+                    TaskState::Pending(body, return_type, env) => Fiber { runtime: Rc::clone(runtime), id, body, return_type, env },
+                    _ => unreachable!(),
+                }
+            }
+            _ => return,
+        }
+    };
+    std::thread::Builder::new()
+        .stack_size(TASK_STACK)
+        .spawn(move || {
+            let fiber = pending;
+            fiber.run()
+        })
+        .expect("cannot start a thread for a task");
+}
+
+const TASK_STACK: usize = 8 * 1024 * 1024;
+
+/// A task that has not started, taken to the thread that runs it.
+struct Fiber {
+    runtime: Rc<RefCell<TaskRuntime>>,
+    id: usize,
+    body: Scope,
+    return_type: Option<Type>,
+    env: Environment,
+}
+
+// Nothing in it is touched before the thread gets the baton, and only the
+// thread holding the baton touches what it shares with the others.
+unsafe impl Send for Fiber {}
+
+impl Fiber {
+    fn run(self) {
+        let Fiber { runtime, id, body, return_type, mut env } = self;
+        let baton = Arc::clone(&runtime.borrow().baton);
+        baton.wait(Some(id));
+        runtime.borrow_mut().current = Some(id);
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| evaluate(&body, return_type, &mut env)));
+        let result = result.unwrap_or_else(|_| Err(("TaskFailed".to_string(), Value::String("the task panicked".to_string(), false))));
+        drop((body, env));
+        let next = {
+            let mut rt = runtime.borrow_mut();
+            let now = rt.clock;
+            rt.tasks[id].state = TaskState::Done(result, now);
+            rt.next()
+        };
+        start(&runtime, next);
+        // everything the thread shares is let go of before handing over
+        drop(runtime);
+        baton.pass(next);
+    }
+}
+
+fn evaluate(body: &Scope, return_type: Option<Type>, env: &mut Environment) -> Result<Value, (String, Value)> {
+    let result = match body.eval(env) {
+        Err(Error::Return(value)) => Ok(value),
+        result => result,
+    };
+    env.free();
+    let result = result.and_then(|value| match return_type {
+        Some(type_) => coerce(type_, value),
+        None => Ok(value),
+    });
+    if result.is_err() {
+        // the task keeps its error as a value, which is raised again where it is awaited
+        env.take_trace();
+    }
+    result.map_err(|e| catchable(&e).unwrap_or_else(|| ("TaskFailed".to_string(), Value::String(e.to_string(), false))))
+}
+
+fn result(runtime: &Rc<RefCell<TaskRuntime>>, id: usize) -> Result<Value, Error> {
+    match &runtime.borrow().tasks[id].state {
+        TaskState::Done(Ok(value), _) => Ok(value.clone()),
+        TaskState::Done(Err((kind, payload)), _) => Err(Error::RuntimeError(RuntimeError::Raised(kind.clone(), payload.clone()))),
+        _ => Err(Error::RuntimeError(RuntimeError::TaskAwaitsItself(id))),
+    }
+}
+
+/// Moves the clock of the running task forward, running the others until it
+/// gets there.
+pub fn sleep(runtime: &Rc<RefCell<TaskRuntime>>, ms: u64) -> Result<(), Error> {
+    let until = runtime.borrow().clock + ms;
+    suspend(runtime, Wait::Until(until))
+}
+
+/// Waits for a task and returns its value, re-raising the error it failed with.
+pub fn await_task(runtime: &Rc<RefCell<TaskRuntime>>, id: usize) -> Result<Value, Error> {
+    let done = {
+        let rt = runtime.borrow();
+        rt.check_task(id)?;
+        if rt.current == Some(id) {
+            return Err(Error::RuntimeError(RuntimeError::TaskAwaitsItself(id)));
+        }
+        rt.done(id)
+    };
+    if !done {
+        suspend(runtime, Wait::Task(id))?;
+    }
+    result(runtime, id)
+}
+
+/// Waits for the first of the tasks to finish, and returns its index and
+/// value. Of tasks finished at the same time, the earlier one is taken.
+pub fn select(runtime: &Rc<RefCell<TaskRuntime>>, ids: &[usize]) -> Result<(usize, Value), Error> {
+    if ids.is_empty() {
+        return Err(Error::RuntimeError(RuntimeError::ArgumentCountMismatch("select".to_string(), 1, 0)));
+    }
+    let done = {
+        let rt = runtime.borrow();
+        for id in ids {
+            rt.check_task(*id)?;
+        }
+        ids.iter().any(|id| rt.done(*id))
+    };
+    if !done {
+        suspend(runtime, Wait::Any(ids.to_vec()))?;
+    }
+    let first = {
+        let rt = runtime.borrow();
+        ids.iter().enumerate().filter_map(|(i, id)| match rt.tasks[*id].state {
+            TaskState::Done(_, time) => Some((time, i)),
+            _ => None,
+        }).min().map(|(_, i)| i).unwrap()
+    };
+    Ok((first, result(runtime, ids[first])?))
+}
+
+/// Takes the next message from a channel, waiting for one to be sent while
+/// it is empty.
+pub fn recv(runtime: &Rc<RefCell<TaskRuntime>>, channel: usize) -> Result<Value, Error> {
+    runtime.borrow().check_channel(channel)?;
+    loop {
+        if let Some(value) = runtime.borrow_mut().take_message(channel)? {
+            return Ok(value);
+        }
+        suspend(runtime, Wait::Message(channel))?;
+    }
+}
+
+impl Environment {
+    /// An environment for a task: globals stay shared, while the locals
+    /// visible here are copied so that they outlive the current scope.
+    pub fn capture(&self) -> Environment {
+        let mut locals: HashMap<String, Variable> = HashMap::new();
+        let mut frame = Rc::clone(&self.frame);
+        loop {
+            let parent = frame.borrow().parent.clone();
+            match parent {
+                Some(parent) => {
+                    for (name, variable) in frame.borrow().variables() {
+                        locals.entry(name.clone()).or_insert_with(|| variable.clone());
+                    }
+                    frame = parent;
+                }
+                None => break,
+            }
+        }
+        let captured = self.with_frame(Some(frame));
+        for (name, variable) in locals {
+            let value = match self.heap.borrow().get(variable.index) {
+                Some(value) => value.clone(),
+                None => continue,
+            };
+            let index = self.heap.borrow_mut().allocate(value);
+            captured.frame.borrow_mut().insert(name, Variable { index, ..variable });
+        }
+        captured
+    }
+}
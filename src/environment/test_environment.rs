@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod test_environment {
+    use crate::ast::function::FnSignature;
+    use crate::ast::type_node::Type;
+    use crate::ast::Value;
+    use crate::environment::environment::RuntimeError;
+    use crate::environment::frame::Frame;
+    use crate::environment::heap::{GcStats, Heap, VariableHeap};
+    use crate::environment::variable::Variable;
+    use crate::{Error, Interpreter};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    fn signature(params: &[(&str, Type)], return_type: Option<Type>) -> FnSignature {
+        FnSignature { params: params.iter().map(|(p, t)| (p.to_string(), t.clone())).collect(), references: Vec::new(), return_type }
+    }
+
+    #[test]
+    fn test_native_fn_receives_arguments_in_signature_order() {
+        let mut interpreter = Interpreter::new();
+        interpreter.env.register_native("sub", signature(&[("a", Type::I64), ("b", Type::I64)], Some(Type::I64)), |args| {
+            Ok(Value::Int(i64::try_from(args[0].clone())? - i64::try_from(args[1].clone())?))
+        });
+        assert_eq!(interpreter.eval_str("sub(b: 2, a: 10)").unwrap(), Value::Int(8));
+        assert_eq!(interpreter.eval_str("let x := 3\nsub(a: x, b: 1)").unwrap(), Value::Int(2));
+    }
+
+    #[test]
+    fn test_native_fn_captures_host_state() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut interpreter = Interpreter::new();
+        let sink = Rc::clone(&log);
+        interpreter.env.register_native("record", signature(&[("message", Type::String)], None), move |args| {
+            sink.borrow_mut().push(args[0].to_string());
+            Ok(Value::None)
+        });
+        interpreter.eval_str("record('hello')").unwrap();
+        interpreter.eval_str("let name := 'loop'\nrecord('hi {name}')").unwrap();
+        assert_eq!(*log.borrow(), vec!["hello".to_string(), "hi loop".to_string()]);
+    }
+
+    #[test]
+    fn test_native_fn_checks_signature() {
+        let mut interpreter = Interpreter::new();
+        interpreter.env.register_native("twice", signature(&[("x", Type::I32)], Some(Type::I32)), |args| match args[0] {
+            Value::Int(x) => Ok(Value::Int(x * 2)),
+            _ => unreachable!(),
+        });
+        assert!(matches!(interpreter.eval_str("twice('a')"), Err(Error::RuntimeError(RuntimeError::ValueNotOfType(_, Type::I32)))));
+        assert!(matches!(interpreter.env.call("twice", HashMap::new()), Err(Error::RuntimeError(RuntimeError::VariableNotFound(_)))));
+        assert_eq!(interpreter.env.get_type("twice").unwrap(), Type::FnType(Box::new(signature(&[("x", Type::I32)], Some(Type::I32)))));
+    }
+
+    #[test]
+    fn test_native_fn_errors_propagate() {
+        let mut interpreter = Interpreter::new();
+        interpreter.env.register_native("fail", signature(&[], None), |_| Err(Error::RuntimeError(RuntimeError::InvalidFunctionCall)));
+        assert!(matches!(interpreter.eval_str("fail()"), Err(Error::RuntimeError(RuntimeError::InvalidFunctionCall))));
+    }
+
+    /// A frame registered with `heap` that has a variable in each of `indexes`.
+    fn root(heap: &mut VariableHeap, indexes: &[usize]) -> Rc<RefCell<Frame>> {
+        let frame = Rc::new(RefCell::new(Frame::new(None)));
+        for index in indexes {
+            let variable = Variable { initialized: true, index: *index, type_: Type::I64, mutable: false };
+            frame.borrow_mut().insert(format!("v{}", index), variable);
+        }
+        heap.register(&frame);
+        frame
+    }
+
+    #[test]
+    fn test_collect_reuses_freed_slots() {
+        let mut heap = VariableHeap::new();
+        let kept = heap.allocate(Value::Int(1));
+        let dropped = [heap.allocate(Value::Int(2)), heap.allocate(Value::Int(3))];
+        let last = heap.allocate(Value::Int(4));
+        let _frame = root(&mut heap, &[kept, last]);
+
+        assert_eq!(heap.collect(), GcStats { live: 2, free: 2, freed: 2, collections: 1 });
+        assert_eq!(heap.get(dropped[0]), None);
+        let reused = [heap.allocate(Value::Int(5)), heap.allocate(Value::Int(6))];
+        assert!(reused.iter().all(|index| dropped.contains(index)));
+        assert_eq!(heap.allocate(Value::Int(7)), last + 1);
+        assert_eq!(heap.stats(), GcStats { live: 5, free: 0, freed: 2, collections: 1 });
+        assert_eq!(heap.get(kept), Some(&Value::Int(1)));
+    }
+
+    #[test]
+    fn test_collect_skips_while_a_frame_is_borrowed() {
+        let mut heap = VariableHeap::new();
+        let kept = heap.allocate(Value::Int(1));
+        heap.allocate(Value::Int(2));
+        let frame = root(&mut heap, &[kept]);
+
+        let declaring = frame.borrow_mut();
+        assert_eq!(heap.collect(), GcStats { live: 2, free: 0, freed: 0, collections: 0 });
+        drop(declaring);
+        assert_eq!(heap.collect(), GcStats { live: 1, free: 0, freed: 1, collections: 1 });
+        drop(frame);
+        assert_eq!(heap.collect(), GcStats { live: 0, free: 0, freed: 1, collections: 2 });
+    }
+}
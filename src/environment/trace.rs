@@ -0,0 +1,64 @@
+use crate::ast::Value;
+use crate::lexer::Position;
+
+/// Arguments are shown up to this many characters.
+const MAX_ARG_LENGTH: usize = 24;
+
+/// A call an error was raised in, or unwound through.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackFrame {
+    pub function: String,
+    /// The params and what they were called with, shortened.
+    pub args: Vec<(String, String)>,
+    /// Where the call is made, when it is in parsed source.
+    pub position: Option<Position>,
+}
+
+impl StackFrame {
+    pub fn new(function: &str, args: Vec<(String, Value)>, position: Option<Position>) -> Self {
+        let args = args.into_iter().map(|(param, value)| (param, summarize(&value))).collect();
+        Self { function: function.to_string(), args, position }
+    }
+}
+
+impl std::fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let args = self.args.iter().map(|(param, value)| format!("{}: {}", param, value)).collect::<Vec<_>>();
+        write!(f, "{}({})", self.function, args.join(", "))?;
+        match self.position {
+            Some(position) => write!(f, " at {}", position),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `value` as it is shown in messages, quoted if it is a string and shortened.
+pub(crate) fn summarize(value: &Value) -> String {
+    let text = match value {
+        Value::String(s, _) => format!("'{}'", s),
+        value => value.to_string(),
+    };
+    match text.char_indices().nth(MAX_ARG_LENGTH) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+/// The calls of `trace`, which is innermost first, as lines with the
+/// outermost call first. Calls repeated from the same place, like deep
+/// recursion, are counted rather than listed.
+pub fn traceback(trace: &[StackFrame]) -> String {
+    let mut lines = vec!["Traceback, most recent call last:".to_string()];
+    let mut frames = trace.iter().rev().peekable();
+    while let Some(frame) = frames.next() {
+        lines.push(format!("  {}", frame));
+        let mut repeated = 0;
+        while frames.next_if(|next| next.function == frame.function && next.position == frame.position).is_some() {
+            repeated += 1;
+        }
+        if repeated > 0 {
+            lines.push(format!("  ... {} more calls of {}", repeated, frame.function));
+        }
+    }
+    lines.join("\n")
+}
@@ -1,6 +1,10 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
+use crate::environment::frame::{Address, Frame};
 use crate::environment::heap::Heap;
 
+use crate::ast::integer;
 use crate::ast::type_node::Type;
 use crate::ast::value::Value;
 use crate::environment::environment::{Environment, RuntimeError};
@@ -17,106 +21,179 @@ pub struct Variable {
 impl Environment {
     pub fn all_variables(&self) -> HashMap<String, Variable> {
         let mut variables = HashMap::new();
-        for (name, variable) in &self.local_variables {
-            variables.insert(name.clone(), variable.clone());
-        }
-        match &self.parent {
-            Some(p) => {
-                let parent_variables = p.borrow().all_variables();
-                variables.extend(parent_variables);
-                variables
+        let mut frame = Some(Rc::clone(&self.frame));
+        while let Some(current) = frame {
+            for (name, variable) in current.borrow().variables() {
+                variables.entry(name.clone()).or_insert_with(|| variable.clone());
             }
-            None => variables,
+            frame = current.borrow().parent.clone();
         }
+        variables
     }
 
-    fn update_variable_value(&mut self, name: &str, value: Value) -> Result<(), Error> {
-        let index = self.get_variable(name)?.index;
-        let mut heap = self.heap.borrow_mut();
-        let val = match heap.get_mut(index) {
-            Some(val) => val,
-            None => return Err(Error::RuntimeError(RuntimeError::NoVariableAtHeapIndex(index))),
-        };
-        *val = value;
-        Ok(())
+    /// The frame `depth` scopes out from this one.
+    fn frame_at(&self, depth: usize) -> Option<Rc<RefCell<Frame>>> {
+        let mut frame = Rc::clone(&self.frame);
+        for _ in 0..depth {
+            let parent = frame.borrow().parent.clone()?;
+            frame = parent;
+        }
+        Some(frame)
     }
-    
-    fn update_variable(&mut self, name: &str, variable: Variable) -> Result<(), Error> {
-        match self.local_variables.get_mut(name) {
-            Some(var) => {
-                *var = variable;
-                Ok(())
+
+    /// The frame `name` is declared in, searching outwards.
+    fn frame_of(&self, name: &str) -> Result<Rc<RefCell<Frame>>, Error> {
+        let mut frame = Rc::clone(&self.frame);
+        loop {
+            if frame.borrow().get(name).is_some() {
+                return Ok(frame);
             }
-            None => match &mut self.parent {
-                Some(p) => p.borrow_mut().update_variable(name, variable),
-                None => Err(Error::RuntimeError(RuntimeError::VariableNotFound(name.to_string()))),
+            let parent = frame.borrow().parent.clone();
+            match parent {
+                Some(parent) => frame = parent,
+                None => return Err(Error::RuntimeError(RuntimeError::VariableNotFound(name.to_string()))),
             }
         }
     }
 
-    pub fn get_variable(&self, name: &str) -> Result<Variable, Error> {
-        match self.local_variables.get(name) {
-            Some(var) => Ok(var.clone()),
-            _ => match &self.parent {
-                Some(p) => p.borrow().get_variable(name),
-                None => Err(Error::RuntimeError(RuntimeError::VariableNotFound(name.to_string()))),
+    /// The variable at `address`, falling back to a search by name when it is
+    /// not resolved or the frame there does not hold `name`.
+    fn frame_and_variable(&self, name: &str, address: Option<Address>) -> Result<(Rc<RefCell<Frame>>, Variable), Error> {
+        match address {
+            Some(Address::Local { depth, slot }) => {
+                if let Some(frame) = self.frame_at(depth) {
+                    let variable = frame.borrow().slot(slot, name).cloned();
+                    if let Some(variable) = variable {
+                        return Ok((frame, variable));
+                    }
+                }
+            }
+            Some(Address::Global) => {
+                let variable = self.globals.borrow().get(name).cloned();
+                if let Some(variable) = variable {
+                    return Ok((Rc::clone(&self.globals), variable));
+                }
             }
+            None => {}
         }
+        let frame = self.frame_of(name)?;
+        let variable = frame.borrow().get(name).cloned().unwrap();
+        Ok((frame, variable))
     }
 
+    pub fn get_variable(&self, name: &str) -> Result<Variable, Error> {
+        self.variable_at(name, None)
+    }
 
-    pub fn declare_assign(&mut self, name: String, value: Value, mutable: bool, type_: Option<Type>) -> Result<(), Error> {
-        let type_ = if type_.is_some() {
-            let type_ = type_.unwrap();
-            check_type(type_.clone(), value.clone())?;
-            type_
-        } else {
-            self.infer_type(value.clone())?
+    /// The variable the resolver found at `address`.
+    pub fn variable_at(&self, name: &str, address: Option<Address>) -> Result<Variable, Error> {
+        Ok(self.frame_and_variable(name, address)?.1)
+    }
+
+    /// Declares `name` in the current scope and returns its variable.
+    pub fn declare_assign(&mut self, name: String, value: Value, mutable: bool, type_: Option<Type>) -> Result<Variable, Error> {
+        let type_ = match type_ {
+            Some(type_) => type_,
+            None => self.infer_type(value.clone())?,
         };
+        let value = coerce(type_.clone(), value)?;
+        self.check_finite(&value, &type_)?;
         let index = self.heap.borrow_mut().allocate(value);
-        self.local_variables.insert(name, Variable { initialized: true, index, mutable, type_ });
-        Ok(())
+        let variable = Variable { initialized: true, index, mutable, type_ };
+        self.frame.borrow_mut().insert(name, variable.clone());
+        Ok(variable)
     }
 
-    pub fn declare(&mut self, name: String, mutable: bool, type_: Type) -> Result<(), Error> {
+    pub fn declare(&mut self, name: String, mutable: bool, type_: Type) -> Result<Variable, Error> {
         let index = self.heap.borrow_mut().allocate(Value::None);
-        self.local_variables.insert(name, Variable { initialized: false, index, mutable, type_ });
-        Ok(())
+        let variable = Variable { initialized: false, index, mutable, type_ };
+        self.frame.borrow_mut().insert(name, variable.clone());
+        Ok(variable)
     }
 
     pub fn assign(&mut self, name: &str, value: Value) -> Result<(), Error> {
-        let mut var = self.get_variable(name)?;
+        self.assign_at(name, None, value)
+    }
+
+    /// Assigns the variable the resolver found at `address`.
+    pub fn assign_at(&mut self, name: &str, address: Option<Address>, value: Value) -> Result<(), Error> {
+        let (frame, var) = self.frame_and_variable(name, address)?;
         if !var.mutable && var.initialized {
             return Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name.to_string())));
         }
-        check_type(var.type_.clone(), value.clone())?;
+        self.store(&var, value)?;
         if !var.initialized {
-            var.initialized = true;
-            self.update_variable(name, var)?;
+            if let Some(declared) = frame.borrow_mut().get_mut(name) {
+                declared.initialized = true;
+            }
         }
-        self.update_variable_value(name, value)?;
         Ok(())
     }
 
+    /// Stores `value` in `variable`, checked against its type.
+    pub fn store(&self, variable: &Variable, value: Value) -> Result<(), Error> {
+        let value = coerce(variable.type_.clone(), value)?;
+        self.check_finite(&value, &variable.type_)?;
+        match self.heap.borrow_mut().get_mut(variable.index) {
+            Some(val) => *val = value,
+            None => return Err(Error::RuntimeError(RuntimeError::NoVariableAtHeapIndex(variable.index))),
+        }
+        Ok(())
+    }
+
+    /// With `--strict-float` a float variable can't hold NaN or an infinity, wherever it came from.
+    fn check_finite(&self, value: &Value, type_: &Type) -> Result<(), Error> {
+        match value {
+            Value::Float(f) if self.strict_float && !f.is_finite() => {
+                Err(Error::RuntimeError(RuntimeError::ValueOutOfBounds(value.to_string(), type_.clone())))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn lookup_mut(&mut self, name: &str) -> Result<bool, Error> {
         Ok(self.get_variable(name)?.mutable)
     }
 
     pub fn lookup(&mut self, name: &str) -> Result<Value, Error> {
-        let var = self.get_variable(name)?;
+        self.lookup_at(name, None)
+    }
+
+    /// Looks up the variable the resolver found at `address`.
+    pub fn lookup_at(&mut self, name: &str, address: Option<Address>) -> Result<Value, Error> {
+        self.inspect(name, address, Value::clone)
+    }
+
+    /// Applies `f` to the value of a variable where it is in the heap, which
+    /// saves copying a list or map to read a part of it.
+    pub fn inspect<T>(&self, name: &str, address: Option<Address>, f: impl FnOnce(&Value) -> T) -> Result<T, Error> {
+        let (_, var) = self.frame_and_variable(name, address)?;
         if !var.initialized {
             return Err(Error::RuntimeError(RuntimeError::VariableNotInitialized(name.to_string())));
         }
-        let val = self.heap.borrow().get(var.index).unwrap().clone();
-        Ok(val)
+        self.inspect_variable(&var, f)
+    }
+
+    /// Like `inspect`, for a variable already found.
+    pub fn inspect_variable<T>(&self, variable: &Variable, f: impl FnOnce(&Value) -> T) -> Result<T, Error> {
+        match self.heap.borrow().get(variable.index) {
+            Some(value) => Ok(f(value)),
+            None => Err(Error::RuntimeError(RuntimeError::NoVariableAtHeapIndex(variable.index))),
+        }
     }
 
     pub fn infer_type(&self, value: Value) -> Result<Type, Error> {
         match value {
-            Value::Int(_) => Ok(Type::I32),
+            Value::Int(i) if integer::fits(i as i128, &Type::I32) => Ok(Type::I32),
+            Value::Int(_) => Ok(Type::I64),
+            Value::TypedInt(_, type_) => Ok(type_),
             Value::Float(_) => Ok(Type::F32),
             Value::String(_, _) => Ok(Type::String),
             Value::Bool(_) => Ok(Type::Bool),
+            Value::None => Ok(Type::Option(Box::new(Type::Any))),
+            Value::Error(_, _) => Ok(Type::Error),
+            Value::Task(_) => Ok(Type::Task(Box::new(Type::Any))),
+            Value::Channel(_) => Ok(Type::Channel),
             Value::Tuple(values) => {
                 let mut types = Vec::new();
                 for value in values {
@@ -124,6 +201,29 @@ impl Environment {
                 }
                 Ok(Type::Tuple(types))
             },
+            // the type every element fits in, `Any` for an empty list
+            Value::List(ref values) => {
+                let mut element = Type::Any;
+                for value in values {
+                    element = match element.join(&self.infer_type(value.clone())?) {
+                        Some(joined) => joined,
+                        None => return Err(Error::RuntimeError(RuntimeError::CannotInferType(value.to_string()))),
+                    };
+                }
+                Ok(Type::List(Box::new(element)))
+            },
+            Value::Map(ref map) => {
+                let (mut key_type, mut value_type) = (Type::Any, Type::Any);
+                for (key, value) in map.entries() {
+                    for (value, type_) in [(key, &mut key_type), (value, &mut value_type)] {
+                        *type_ = match type_.join(&self.infer_type(value.clone())?) {
+                            Some(joined) => joined,
+                            None => return Err(Error::RuntimeError(RuntimeError::CannotInferType(value.to_string()))),
+                        };
+                    }
+                }
+                Ok(Type::Map(Box::new(key_type), Box::new(value_type)))
+            },
             _ => Err(Error::RuntimeError(RuntimeError::CannotInferType(value.to_string()))),
         }
     } 
@@ -138,64 +238,23 @@ impl Environment {
 
 pub fn check_bounds(value: Value, type_: Type) -> Result<(), Error> {
     match type_ {
-        Type::I32 => match value {
-            Value::Int(i) => if i > i32::MIN as i64 && i < i32::MAX as i64 {
-                return Ok(())
-            }
-            _ => {}
-        },
-        Type::I64 => match value {
-            Value::Int(i) => if i > i64::MIN as i64 && i < i64::MAX as i64 {
-                return Ok(())
-            }
-            _ => {}
-        },
-        Type::I16 => match value {
-            Value::Int(i) => if i > i16::MIN as i64 && i < i16::MAX as i64 {
-                return Ok(())
-            }
-            _ => {}
-        },
-        Type::U8 => match value {
-            Value::Int(i) => if i > 0 && i < u8::MAX as i64 {
-                return Ok(())
-            }
-            _ => {}
-        },
-        Type::U16 => match value {
-            Value::Int(i) => if i > 0 && i < u16::MAX as i64 {
-                return Ok(())
-            }
-            _ => {}
-        },
-        Type::U32 => match value {
-            Value::Int(i) => if i > 0 && i < u32::MAX as i64 {
-                return Ok(())
-            }
-            _ => {}
-        },
-        Type::U64 => match value {
-            Value::Int(i) => if i > 0 && i < u64::MAX as i64 {
-                return Ok(())
+        Type::I16 | Type::I32 | Type::I64 | Type::U8 | Type::U16 | Type::U32 | Type::U64 => {
+            if let Some((i, _)) = integer::as_integer(&value) {
+                if integer::fits(i, &type_) {
+                    return Ok(())
+                }
             }
-            _ => {}
-        },
+        }
+        // NaN and infinities belong to every float type; only `--strict-float` rejects them
         Type::F32 => match value {
-            Value::Float(f) => if f > f32::MIN as f64 && f < f32::MAX as f64 {
-                return Ok(())
-            }
-            Value::Int(i) => if i > f32::MIN as i64 && i < f32::MAX as i64 {
+            Value::Float(f) => if !f.is_finite() || (f32::MIN as f64..=f32::MAX as f64).contains(&f) {
                 return Ok(())
             }
+            Value::Int(_) | Value::TypedInt(..) => return Ok(()),
             _ => {}
         },
         Type::F64 => match value {
-            Value::Float(f) => if f > f64::MIN && f < f64::MAX {
-                return Ok(())
-            }
-            Value::Int(i) => if i > f64::MIN as i64 && i < f64::MAX as i64 {
-                return Ok(())
-            }
+            Value::Float(_) | Value::Int(_) | Value::TypedInt(..) => return Ok(()),
             _ => {}
         },
         _ => {}
@@ -207,12 +266,12 @@ pub fn check_bounds(value: Value, type_: Type) -> Result<(), Error> {
 pub fn check_type(type_: Type, value: Value) -> Result<(), Error> {
     match type_ {
         Type::I32 | Type::I64 | Type::I16 | Type::U8 | Type::U16 | Type::U32 | Type::U64 => match value {
-            Value::Int(_) => check_bounds(value, type_),
+            Value::Int(_) | Value::TypedInt(..) => check_bounds(value, type_),
             _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), type_))),
         },
         Type::F32 | Type::F64 => match value {
             Value::Float(_) => check_bounds(value, type_),
-            Value::Int(_) => check_bounds(value, type_),
+            Value::Int(_) | Value::TypedInt(..) => check_bounds(value, type_),
             _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), type_))),
         },
         Type::String => match value {
@@ -227,7 +286,8 @@ pub fn check_type(type_: Type, value: Value) -> Result<(), Error> {
             Value::None => Ok(()),
             _ => check_type(*inner, value),
         },
-        Type::Generic(_) |  Type::UserDefined(_) => Err(Error::RuntimeError(RuntimeError::TypeNotImplemented(type_))),
+        Type::Generic(_) => Ok(()),
+        Type::UserDefined(_) => Err(Error::RuntimeError(RuntimeError::TypeNotImplemented(type_))),
         Type::Tuple(types) => match value {
             Value::Tuple(values) => {
                 if values.len() != types.len() {
@@ -240,10 +300,66 @@ pub fn check_type(type_: Type, value: Value) -> Result<(), Error> {
             },
             _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfTupleType(value.to_string(), types))),
         },
+        Type::List(inner) => match value {
+            Value::List(values) => {
+                for value in values {
+                    check_type(*inner.clone(), value)?;
+                }
+                Ok(())
+            },
+            _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::List(inner)))),
+        },
+        Type::Map(ref key_type, _) if !key_type.is_hashable() => Err(Error::RuntimeError(RuntimeError::UnhashableType(type_))),
+        Type::Map(key_type, value_type) => match value {
+            Value::Map(map) => {
+                for (key, value) in map.entries() {
+                    check_type(*key_type.clone(), key.clone())?;
+                    check_type(*value_type.clone(), value.clone())?;
+                }
+                Ok(())
+            },
+            _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::Map(key_type, value_type)))),
+        },
         Type::FnType(signature) => match value {
-            Value::Fn(_) => Ok(()),
+            Value::Fn(_) | Value::NativeFn(_) => Ok(()),
             _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::FnType(signature)))),
         },
+        Type::Task(_) => match value {
+            Value::Task(_) => Ok(()),
+            _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), type_))),
+        },
+        Type::Channel => match value {
+            Value::Channel(_) => Ok(()),
+            _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), type_))),
+        },
+        Type::Error => match value {
+            Value::Error(_, _) => Ok(()),
+            _ => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), type_))),
+        },
         Type::Any => Ok(()),
     }
-}
\ No newline at end of file
+}
+
+/// Checks `value` against `type_` and gives integers the integer type they
+/// are stored as, or makes them floats when stored as a float.
+pub fn coerce(type_: Type, value: Value) -> Result<Value, Error> {
+    check_type(type_.clone(), value.clone())?;
+    Ok(convert(&type_, value))
+}
+
+fn convert(type_: &Type, value: Value) -> Value {
+    match (type_, value) {
+        (type_, Value::Int(i)) if integer::range(type_).is_some() => Value::TypedInt(i as i128, type_.clone()),
+        (type_, Value::TypedInt(i, _)) if integer::range(type_).is_some() => Value::TypedInt(i, type_.clone()),
+        (Type::F32 | Type::F64, value @ (Value::Int(_) | Value::TypedInt(..))) => match integer::as_integer(&value) {
+            Some((i, _)) => Value::Float(i as f64),
+            None => value,
+        },
+        (Type::Option(inner), value) => convert(inner, value),
+        (Type::Tuple(types), Value::Tuple(values)) => Value::Tuple(types.iter().zip(values).map(|(type_, value)| convert(type_, value)).collect()),
+        (Type::List(inner), Value::List(values)) => Value::List(values.into_iter().map(|value| convert(inner, value)).collect()),
+        // keys are hashable, so converting them keeps them hashing the same
+        (Type::Map(key_type, value_type), Value::Map(map)) => Value::Map(Box::new(map.map_entries(|key, value| (convert(key_type, key), convert(value_type, value))))),
+        (_, value) => value,
+    }
+}
@@ -1,37 +1,188 @@
 use std::fs;
 use std::path::Path;
-use crate::lexer::Lexer;
+use crate::ast::{ASTNode, Node};
+use crate::ast::Value;
+use crate::ast::function::FnSignature;
+use crate::ast::type_node::Type;
+use crate::lexer::{Lexer, Span};
 use crate::parser::parser::Parser;
-use crate::environment::environment::Environment;
-use crate::environment::heap::VariableHeap;
+use crate::environment::environment::{Environment, ReferenceOrValue, RuntimeError};
+use crate::environment::heap::{GcStats, Heap};
+use crate::environment::trace::StackFrame;
+use crate::diagnostics::diagnostic::report;
+use std::collections::HashMap;
 use std::rc::Rc;
-use std::cell::RefCell;
-
-pub fn run_file(path: &Path) {
-    let content = fs::read_to_string(path).expect("Unable to read file");
-    let heap = VariableHeap::new();
-    let mut lexer = Lexer::new(content);
-    match lexer.tokenize() {
-        Ok(_) => {
-            lexer.clean_tokens();
-            let mut parser = Parser::new(lexer.tokens.clone());
-            match parser.parse() {
-                Ok(ast) => {
-                    let mut env = Environment::new(None, Some(Rc::new(RefCell::new(heap))));
-                    for child in ast.children() {
-                        match child.eval(&mut env) {
-                            Ok(value) => println!("{}", value.to_string()),
-                            Err(e) => eprintln!("Error: {:?}", e),
-                        }
+use crate::package::Project;
+use crate::stdlib;
+use crate::checker::checker;
+use crate::Error;
+
+/// Entry point for embedding Loop in a Rust program. Globals, functions and
+/// imports persist between calls.
+pub struct Interpreter {
+    pub(crate) env: Environment,
+    /// The source parsed last, which errors are shown in.
+    source: String,
+    /// Where lexing or parsing the source failed.
+    error_span: Option<Span>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let mut env = Environment::new(None, None);
+        stdlib::prelude::register(&mut env);
+        Self { env, source: String::new(), error_span: None }
+    }
+
+    /// Evaluates `source` and returns the value of its last expression.
+    pub fn eval_str(&mut self, source: &str) -> Result<Value, Error> {
+        self.env.take_trace();
+        let ast = self.parse(source)?;
+        top_level(self.env.evaluate(&ast))
+    }
+
+    /// Evaluates a file. Its `loop.toml`, if any, is used for `git:` imports.
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Value, Error> {
+        self.env.take_trace();
+        let ast = self.load_file(path.as_ref())?;
+        top_level(self.env.evaluate(&ast))
+    }
+
+    pub(crate) fn load_file(&mut self, path: &Path) -> Result<Rc<Node>, Error> {
+        let content = fs::read_to_string(path).map_err(Error::IoError)?;
+        if self.env.project.is_none() {
+            self.env.project = path.parent().and_then(Project::discover).map(Rc::new);
+        }
+        self.parse(&content)
+    }
+
+    /// Calls a Loop or native function with positional arguments, in the
+    /// order of its declared params.
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        let signature = match self.env.get_type(name)? {
+            Type::FnType(signature) => signature,
+            _ => return Err(Error::RuntimeError(RuntimeError::FunctionNotFound(name.to_string()))),
+        };
+        if signature.params.len() != args.len() {
+            return Err(Error::RuntimeError(RuntimeError::ArgumentCountMismatch(name.to_string(), signature.params.len(), args.len())));
+        }
+        let args = signature.params.iter()
+            .zip(args)
+            .map(|((param, _), value)| (param.clone(), ReferenceOrValue::Value(value)))
+            .collect::<HashMap<_, _>>();
+        self.env.take_trace();
+        self.env.call(name, args)
+    }
+
+    /// The calls the last error returned was raised in, innermost first.
+    pub fn trace(&self) -> Vec<StackFrame> {
+        self.env.trace.borrow().clone()
+    }
+
+    /// The last error returned shown with the calls it went through, the
+    /// source line it is at, and hints, in color unless `color` is false.
+    pub fn render_error(&self, error: &Error, color: bool) -> String {
+        let span = self.error_span.or(self.env.error_span.get());
+        report(error, &self.source, span, &self.env.trace.borrow(), color)
+    }
+
+    pub(crate) fn parse(&mut self, source: &str) -> Result<Rc<Node>, Error> {
+        self.source = source.to_string();
+        let mut lexer = Lexer::new(source.to_string());
+        if let Err(e) = lexer.tokenize() {
+            self.error_span = Some(Span::at(lexer.position(), 1));
+            return Err(Error::LexerError(e));
+        }
+        lexer.clean_tokens();
+        let mut parser = Parser::with_spans(lexer.tokens.clone(), lexer.spans.clone());
+        let ast = parser.parse();
+        self.error_span = parser.error_span();
+        ast
+    }
+
+    pub fn register_fn(&mut self, name: &str, signature: FnSignature, func: impl Fn(Vec<Value>) -> Result<Value, Error> + 'static) {
+        self.env.register_native(name, signature, func);
+    }
+
+    /// Binds an immutable global, replacing any previous binding of `name`.
+    pub fn set_global(&mut self, name: &str, value: impl Into<Value>) -> Result<(), Error> {
+        if let Some(previous) = self.env.frame.borrow().get(name) {
+            self.env.heap.borrow_mut().deallocate(previous.index);
+        }
+        self.env.declare_assign(name.to_string(), value.into(), false, None)?;
+        Ok(())
+    }
+
+    pub fn get_global(&mut self, name: &str) -> Result<Value, Error> {
+        self.env.lookup(name)
+    }
+
+    /// Makes float operations raise `NonFiniteFloat` instead of producing NaN or infinity.
+    pub fn set_strict_float(&mut self, strict: bool) {
+        self.env.strict_float = strict;
+    }
+
+    /// Evaluates with the bytecode VM instead of walking the AST. Both give
+    /// the same results.
+    pub fn set_bytecode(&mut self, bytecode: bool) {
+        self.env.bytecode = bytecode;
+    }
+
+    /// Makes calls nested deeper than `depth` raise `StackOverflow`. The
+    /// stack grows on the heap as calls nest, so any thread can run them.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.env.max_depth = depth;
+    }
+
+    /// Frees the variables no scope can reach any more. This also happens on
+    /// its own as the heap grows.
+    pub fn collect_garbage(&mut self) -> GcStats {
+        self.env.heap.borrow_mut().collect()
+    }
+}
+
+/// A `?` outside of any function ends the script with its value.
+fn top_level(result: Result<Value, Error>) -> Result<Value, Error> {
+    match result {
+        Err(Error::Return(value)) => Ok(value),
+        result => result,
+    }
+}
+
+
+pub fn run_file(path: &Path, strict_float: bool, bytecode: bool, max_depth: usize, color: bool) {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_strict_float(strict_float);
+    interpreter.set_bytecode(bytecode);
+    interpreter.set_max_call_depth(max_depth);
+    match interpreter.load_file(path) {
+        Ok(ast) => {
+            if let Err(e @ Error::TypeCheckError(_)) = checker::check(&interpreter.env, ast.as_ref()) {
+                eprintln!("{}", interpreter.render_error(&e, color));
+                return;
+            }
+            for child in ast.children() {
+                match interpreter.env.evaluate(&child) {
+                    Ok(value) => println!("{}", value.to_string()),
+                    Err(Error::Return(value)) => {
+                        println!("{}", value);
+                        break;
+                    }
+                    Err(e) => {
+                        eprintln!("{}", interpreter.render_error(&e, color));
+                        interpreter.env.take_trace();
                     }
-                }
-                Err(e) => {
-                    eprintln!("Error: {:?}", e);
                 }
             }
         }
         Err(e) => {
-            eprintln!("Error: {:?}", e);
+            eprintln!("{}", interpreter.render_error(&e, color));
         }
     }
-} 
\ No newline at end of file
+}
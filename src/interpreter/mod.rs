@@ -1 +1 @@
-pub mod interpreter; 
\ No newline at end of file
+pub mod interpreter; pub mod test_interpreter;
@@ -0,0 +1,716 @@
+#[cfg(test)]
+mod test_interpreter {
+    use crate::ast::function::FnSignature;
+    use crate::ast::type_node::Type;
+    use crate::ast::binary_operation::BinaryOperationError;
+    use crate::environment::environment::RuntimeError;
+    use crate::environment::trace::traceback;
+    use crate::lexer::token::Operator;
+    use crate::{Error, Interpreter, Value};
+
+    #[test]
+    fn test_eval_str_keeps_state() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.eval_str("let x := 20").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("x + 22").unwrap(), Value::Int(42));
+        assert!(matches!(interpreter.eval_str("y + 1"), Err(Error::RuntimeError(RuntimeError::VariableNotFound(_)))));
+        assert!(matches!(interpreter.eval_str("let z := (1"), Err(Error::ParserError(_))));
+    }
+
+    #[test]
+    fn test_globals() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("name", "loop").unwrap();
+        interpreter.set_global("point", (1.5, 2.5)).unwrap();
+        assert_eq!(interpreter.eval_str("'hello {name}'").unwrap(), Value::from("hello loop"));
+        interpreter.eval_str("let x, y := point").unwrap();
+        assert_eq!(f64::try_from(interpreter.get_global("y").unwrap()).unwrap(), 2.5);
+        interpreter.set_global("name", "rust").unwrap();
+        assert_eq!(String::try_from(interpreter.get_global("name").unwrap()).unwrap(), "rust");
+        assert!(interpreter.get_global("missing").is_err());
+    }
+
+    #[test]
+    fn test_call_function() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("fn scale(point: (i64, i64), by: i64) -> (i64, i64) {\nlet x, y := point\n(x * by, y * by)\n}").unwrap();
+        let result = interpreter.call_function("scale", vec![(2, 3).into(), 10.into()]).unwrap();
+        assert_eq!(<(i64, i64)>::try_from(result).unwrap(), (20, 30));
+        assert!(matches!(
+            interpreter.call_function("scale", vec![1.into()]),
+            Err(Error::RuntimeError(RuntimeError::ArgumentCountMismatch(_, 2, 1)))
+        ));
+        assert!(matches!(
+            interpreter.call_function("scale", vec![true.into(), 1.into()]),
+            Err(Error::RuntimeError(RuntimeError::ValueNotOfTupleType(_, _)))
+        ));
+    }
+
+    #[test]
+    fn test_register_fn() {
+        let mut interpreter = Interpreter::new();
+        let signature = FnSignature { params: vec![("text".to_string(), Type::String)], references: Vec::new(), return_type: Some(Type::String) };
+        interpreter.register_fn("shout", signature, |args| Ok(Value::from(String::try_from(args[0].clone())?.to_uppercase())));
+        assert_eq!(interpreter.eval_str("shout('hi')").unwrap(), Value::from("HI"));
+        assert_eq!(interpreter.call_function("shout", vec!["ok".into()]).unwrap(), Value::from("OK"));
+    }
+
+    #[test]
+    fn test_value_conversions() {
+        assert_eq!(Value::from((1, (true, "a"))), Value::Tuple(vec![Value::Int(1), Value::Tuple(vec![Value::Bool(true), Value::from("a")])]));
+        assert_eq!(Value::from(None::<i64>), Value::None);
+        assert_eq!(f64::try_from(Value::Int(2)).unwrap(), 2.0);
+        assert!(i64::try_from(Value::Float(2.0)).is_err());
+        assert!(<(i64, i64)>::try_from(Value::from((1, 2, 3))).is_err());
+    }
+
+    #[test]
+    fn test_eval_file() {
+        let path = std::env::temp_dir().join(format!("loop-interpreter-{}.lp", std::process::id()));
+        std::fs::write(&path, "let base := 40\nbase + 2\n").unwrap();
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.eval_file(&path).unwrap(), Value::Int(42));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(interpreter.eval_file(&path), Err(Error::IoError(_))));
+    }
+
+    #[test]
+    fn test_optional_propagation_and_unwrap() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("fn bump(p: i32?) -> i32? {\nlet v := p?\nv + 1\n}").unwrap();
+        assert_eq!(interpreter.eval_str("bump(p: 1)").unwrap(), Value::Int(2));
+        assert_eq!(interpreter.eval_str("bump(p: none)").unwrap(), Value::None);
+        assert_eq!(interpreter.eval_str("bump(p: 1)! * 10").unwrap(), Value::Int(20));
+        assert!(matches!(interpreter.eval_str("bump(p: none)!"), Err(Error::RuntimeError(RuntimeError::UnwrapNone(_)))));
+        interpreter.eval_str("fn strict(p: i32?) -> i32 { p? }").unwrap();
+        assert!(matches!(interpreter.eval_str("strict(p: none)"), Err(Error::RuntimeError(RuntimeError::ValueNotOfType(_, Type::I32)))));
+    }
+
+    #[test]
+    fn test_only_optionals_hold_none() {
+        let mut interpreter = Interpreter::new();
+        assert!(matches!(interpreter.eval_str("let a: i32 = none"), Err(Error::RuntimeError(RuntimeError::ValueNotOfType(_, Type::I32)))));
+        interpreter.eval_str("let b: i32? = none").unwrap();
+        assert!(matches!(
+            interpreter.eval_str("b + 1"),
+            Err(Error::RuntimeError(RuntimeError::BinaryOperationError(BinaryOperationError::NoneOperand(Operator::Add))))
+        ));
+        assert_eq!(interpreter.eval_str("b ?? 5").unwrap(), Value::Int(5));
+        assert_eq!(interpreter.eval_str("(b ?? 5) + 1").unwrap(), Value::Int(6));
+        assert!(interpreter.eval_str("b ?? unknown ?? 3").is_err());
+        assert_eq!(interpreter.eval_str("3 ?? unknown").unwrap(), Value::Int(3));
+        assert_eq!(interpreter.eval_str("b == none").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_raise_and_except() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("fn parse(x: i32) -> i32 {\nif x > 100 { error TooBig(x) }\nx * 2\n}").unwrap();
+        interpreter.eval_str("fn total(x: i32) -> i32 { parse(x) + 1 }").unwrap();
+        assert_eq!(interpreter.eval_str("parse(4) except 0").unwrap(), Value::Int(8));
+        assert_eq!(interpreter.eval_str("total(101) except 0").unwrap(), Value::Int(0));
+        assert_eq!(interpreter.eval_str("total(300) except {\nTooBig(n) => n * 10\nerror => 1\n}").unwrap(), Value::Int(3000));
+        assert!(matches!(
+            interpreter.eval_str("parse(101) except { Other => 0 }"),
+            Err(Error::RuntimeError(RuntimeError::Raised(kind, payload))) if kind == "TooBig" && payload == Value::Int(101)
+        ));
+        assert_eq!(
+            interpreter.eval_str("parse(102) except { error(e) => e }").unwrap(),
+            Value::Error("TooBig".to_string(), Box::new(Value::Int(102)))
+        );
+        assert!(matches!(
+            interpreter.eval_str("let e := parse(102) except { error(e) => e }\nerror e"),
+            Err(Error::RuntimeError(RuntimeError::Raised(kind, payload))) if kind == "TooBig" && payload == Value::Int(102)
+        ));
+        assert!(matches!(
+            interpreter.eval_str("error 'bad record'"),
+            Err(Error::RuntimeError(RuntimeError::Raised(kind, Value::String(message, _)))) if kind == "Error" && message == "bad record"
+        ));
+    }
+
+    #[test]
+    fn test_except_catches_runtime_errors() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.eval_str("missing + 1 except { VariableNotFound(name) => name }").unwrap(),
+            Value::from("missing")
+        );
+        // faults in operations carry the operator and the operands
+        assert_eq!(
+            interpreter.eval_str("1 / 0 except { DivisionByZero(fault) => fault }").unwrap(),
+            Value::Tuple(vec![Value::from("/"), Value::Int(1), Value::Int(0)])
+        );
+        assert_eq!(
+            interpreter.eval_str("let low: i64 = -9223372036854775808\nlow / -1 except { IntegerOverflow(fault) => fault }").unwrap(),
+            Value::Tuple(vec![Value::from("/"), Value::TypedInt(i64::MIN as i128, Type::I64), Value::Int(-1)])
+        );
+        assert_eq!(interpreter.eval_str("[1, 2][5] except { IndexOutOfBounds(at) => at.0 + at.1 }").unwrap(), Value::Int(7));
+        assert_eq!(interpreter.eval_str("'a' - 1 except { BinaryOperationError(fault) => fault.2 }").unwrap(), Value::Int(1));
+        let result = interpreter.eval_str("let total := 0\n{\nlet x := 1 + none\nx\n} except {\nBinaryOperationError => 7\n}").unwrap();
+        assert_eq!(result, Value::Int(7));
+        interpreter.eval_str("fn first(p: i32?) -> i32? { p? except 0 }").unwrap();
+        assert_eq!(interpreter.eval_str("first(p: none)").unwrap(), Value::None);
+        // braces holding `key: value` entries are a map, not a block
+        interpreter.eval_str("let q := {'a': 1} except 0").unwrap();
+        assert_eq!(interpreter.eval_str("q['a']").unwrap(), Value::Int(1));
+        assert_eq!(interpreter.eval_str("{\n'a': 1,\n'b': 2\n}['c'] except { error => 3 }").unwrap(), Value::Int(3));
+        assert_eq!(interpreter.eval_str("{\nlet x: i32 = 2\nx + 1\n} except 0").unwrap(), Value::Int(3));
+    }
+
+    #[test]
+    fn test_async_tasks_use_virtual_time() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("async fn fetch(ms: i64) -> i64 {\nsleep(ms)\nms * 2\n}").unwrap();
+        interpreter.eval_str("let slow := fetch(30)\nlet fast := fetch(10)").unwrap();
+        assert!(matches!(interpreter.eval_str("slow").unwrap(), Value::Task(_)));
+        assert_eq!(interpreter.eval_str("now()").unwrap(), Value::Int(0));
+        assert_eq!(interpreter.eval_str("await fast + 1").unwrap(), Value::Int(21));
+        assert_eq!(interpreter.eval_str("now()").unwrap(), Value::Int(10));
+        assert_eq!(interpreter.eval_str("join_all((slow, fast))").unwrap(), Value::from((60, 20)));
+        assert_eq!(interpreter.eval_str("now()").unwrap(), Value::Int(30));
+        assert_eq!(interpreter.eval_str("select((fetch(50), fetch(20)))").unwrap(), Value::from((1, 40)));
+        assert_eq!(interpreter.eval_str("now()").unwrap(), Value::Int(50));
+        assert_eq!(interpreter.eval_str("let n := 4\nawait async { n * n }").unwrap(), Value::Int(16));
+    }
+
+    #[test]
+    fn test_tasks_suspend_and_interleave() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let log := channel()").unwrap();
+        interpreter.eval_str("async fn tick(name: string, every: i64) {\nmut i := 0\nwhile i < 3 {\nsleep(every)\nsend(log, '{name}{now()}')\ni += 1\n}\n}").unwrap();
+        interpreter.eval_str("let a := tick('a', 10)\nlet b := tick('b', 15)").unwrap();
+        interpreter.eval_str("join_all((a, b))").unwrap();
+        let order: Vec<String> = (0..6).map(|_| interpreter.eval_str("recv(log)").unwrap().to_string()).collect();
+        assert_eq!(order, ["a10", "b15", "a20", "b30", "a30", "b45"]);
+        // the sleeps overlap, so waiting for both takes as long as the longer
+        assert_eq!(interpreter.eval_str("now()").unwrap(), Value::Int(45));
+
+        // a task that awaits another one that awaits it cannot finish
+        interpreter.eval_str("mut first := async { 0 }\nlet second := async { await first }").unwrap();
+        interpreter.eval_str("first := async { await second }").unwrap();
+        assert!(matches!(interpreter.eval_str("await second"), Err(Error::RuntimeError(RuntimeError::Raised(kind, _))) if kind == "TaskAwaitsItself"));
+    }
+
+    #[test]
+    fn test_task_errors_and_channels() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("async fn fail(ms: i64) {\nsleep(ms)\nerror Timeout(ms)\n}").unwrap();
+        assert_eq!(interpreter.eval_str("await fail(5) except { Timeout(ms) => ms + 1 }").unwrap(), Value::Int(6));
+        assert!(matches!(interpreter.eval_str("await 3"), Err(Error::RuntimeError(RuntimeError::ValueNotOfType(_, _)))));
+
+        interpreter.eval_str("let ch := channel()").unwrap();
+        interpreter.eval_str("let producer := async {\nsleep(5)\nsend(ch, 'hello')\nsend(ch, 'world')\n}").unwrap();
+        assert_eq!(interpreter.eval_str("recv(ch)").unwrap(), Value::from("hello"));
+        assert_eq!(interpreter.eval_str("recv(ch)").unwrap(), Value::from("world"));
+        assert_eq!(interpreter.eval_str("now()").unwrap(), Value::Int(10));
+        assert!(matches!(interpreter.eval_str("recv(ch)"), Err(Error::RuntimeError(RuntimeError::ChannelEmpty(_)))));
+
+        interpreter.eval_str("let ping := channel()\nlet pong := channel()\nlet echo := async {\nsend(pong, recv(ping))\nsend(pong, recv(ping))\n}").unwrap();
+        interpreter.eval_str("send(ping, 1)").unwrap();
+        assert_eq!(interpreter.eval_str("recv(pong)").unwrap(), Value::Int(1));
+        interpreter.eval_str("send(ping, 2)").unwrap();
+        assert_eq!(interpreter.eval_str("recv(pong)").unwrap(), Value::Int(2));
+        assert_eq!(interpreter.eval_str("await echo").unwrap(), Value::None);
+
+        // a task waiting on a channel nothing sends to fails with ChannelEmpty
+        interpreter.eval_str("let stuck := async { recv(ping) }").unwrap();
+        assert_eq!(interpreter.eval_str("await stuck except { ChannelEmpty(channel) => channel }").unwrap(), Value::Channel(1));
+
+        // an error that cannot be caught as itself fails the task with its message
+        let failed = interpreter.eval_str("await async { import 'git:example.com/lib' } except { TaskFailed(message) => message }").unwrap();
+        assert!(matches!(failed, Value::String(message, _) if message.starts_with("no loop.toml found in")));
+    }
+
+    #[test]
+    fn test_generic_functions() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("fn first(t: (T, T)) -> T {\nlet a, b := t\na\n}").unwrap();
+        assert_eq!(interpreter.eval_str("first((1, 2))").unwrap(), Value::Int(1));
+        assert_eq!(interpreter.eval_str("first(('x', 'y'))").unwrap(), Value::from("x"));
+        interpreter.eval_str("let pair := (3, 4)").unwrap();
+        assert_eq!(interpreter.eval_str("first(pair)").unwrap(), Value::Int(3));
+
+        interpreter.eval_str("fn pick(a: T, b: T) -> T { b }").unwrap();
+        assert_eq!(interpreter.eval_str("pick(1, 2.5)").unwrap(), Value::Float(2.5));
+        assert!(matches!(
+            interpreter.eval_str("pick(1, 'x')"),
+            Err(Error::RuntimeError(RuntimeError::GenericBindingConflict(name, 'T', Type::I32, Type::String))) if name == "pick"
+        ));
+        interpreter.eval_str("fn bad(a: T) -> T { 'text' }").unwrap();
+        assert!(matches!(interpreter.eval_str("bad(1)"), Err(Error::RuntimeError(RuntimeError::ValueNotOfType(_, Type::I32)))));
+    }
+
+    #[test]
+    fn test_sized_integers() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let byte: u8 = 250").unwrap();
+        assert_eq!(interpreter.eval_str("byte + 5").unwrap(), Value::TypedInt(255, Type::U8));
+        assert!(matches!(
+            interpreter.eval_str("byte + 10"),
+            Err(Error::RuntimeError(RuntimeError::IntegerOverflow(Operator::Add, _, Type::U8)))
+        ));
+        assert_eq!(interpreter.eval_str("byte + 10 except { IntegerOverflow => 0 }").unwrap(), Value::Int(0));
+        assert_eq!(interpreter.eval_str("byte << 4").unwrap(), Value::TypedInt(160, Type::U8));
+        assert_eq!(interpreter.eval_str("wrapping_add(byte, 10)").unwrap(), Value::TypedInt(4, Type::U8));
+        assert_eq!(interpreter.eval_str("saturating_add(byte, 10)").unwrap(), Value::TypedInt(255, Type::U8));
+        assert_eq!(interpreter.eval_str("wrapping_sub(byte, 251)").unwrap(), Value::TypedInt(255, Type::U8));
+
+        interpreter.eval_str("let small: i16 = 0 - 1000").unwrap();
+        assert_eq!(interpreter.eval_str("byte + small").unwrap(), Value::TypedInt(-750, Type::I16));
+        assert_eq!(interpreter.eval_str("let zero: u8 = 0\nzero").unwrap(), Value::TypedInt(0, Type::U8));
+        assert!(matches!(
+            interpreter.eval_str("let negative: u32 = 0 - 1"),
+            Err(Error::RuntimeError(RuntimeError::ValueOutOfBounds(_, Type::U32)))
+        ));
+        assert!(matches!(interpreter.eval_str("-byte"), Err(Error::RuntimeError(RuntimeError::IntegerOverflow(Operator::Sub, _, Type::U8)))));
+
+        interpreter.eval_str("mut big: u64 = 9223372036854775807\nbig := big * 2 + 1").unwrap();
+        assert_eq!(interpreter.eval_str("big").unwrap(), Value::TypedInt(u64::MAX as i128, Type::U64));
+        assert!(matches!(interpreter.eval_str("big * 2"), Err(Error::RuntimeError(RuntimeError::IntegerOverflow(Operator::Mul, _, Type::U64)))));
+        assert_eq!(interpreter.eval_str("saturating_mul(big, big)").unwrap(), Value::TypedInt(u64::MAX as i128, Type::U64));
+        assert!(matches!(interpreter.eval_str("9223372036854775807 + 1"), Err(Error::RuntimeError(RuntimeError::IntegerOverflow(_, _, Type::I64)))));
+
+        // every type takes literals of its whole range, and only those
+        for (type_, min, max) in [
+            (Type::U8, 0, u8::MAX as i128),
+            (Type::U16, 0, u16::MAX as i128),
+            (Type::U32, 0, u32::MAX as i128),
+            (Type::U64, 0, u64::MAX as i128),
+            (Type::I16, i16::MIN as i128, i16::MAX as i128),
+            (Type::I32, i32::MIN as i128, i32::MAX as i128),
+            (Type::I64, i64::MIN as i128, i64::MAX as i128),
+        ] {
+            for value in [min, max] {
+                assert_eq!(interpreter.eval_str(&format!("let x: {} = {}\nx", type_, value)).unwrap(), Value::TypedInt(value, type_.clone()));
+            }
+            for value in [min - 1, max + 1].into_iter().filter(|value| (i64::MIN as i128..=u64::MAX as i128).contains(value)) {
+                assert!(matches!(
+                    interpreter.eval_str(&format!("let x: {} = {}", type_, value)),
+                    Err(Error::RuntimeError(RuntimeError::ValueOutOfBounds(_, found))) if found == type_
+                ), "{} {}", type_, value);
+            }
+        }
+        assert_eq!(interpreter.eval_str("18446744073709551615").unwrap(), Value::TypedInt(u64::MAX as i128, Type::U64));
+        assert_eq!(interpreter.eval_str("-9223372036854775808").unwrap(), Value::Int(i64::MIN));
+        assert!(matches!(interpreter.eval_str("18446744073709551616"), Err(Error::LexerError(_))));
+        assert!(matches!(
+            interpreter.eval_str("let x: i64 = -9223372036854775809"),
+            Err(Error::RuntimeError(RuntimeError::IntegerOverflow(Operator::Sub, _, Type::U64)))
+        ));
+    }
+
+    #[test]
+    fn test_casts_and_promotion() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let byte: u8 = 200\nlet ratio: f64 = 5").unwrap();
+        assert_eq!(interpreter.eval_str("ratio").unwrap(), Value::Float(5.0));
+        assert_eq!(interpreter.eval_str("ratio / 2").unwrap(), Value::Float(2.5));
+        assert_eq!(interpreter.eval_str("byte + 0.5").unwrap(), Value::Float(200.5));
+        assert_eq!(interpreter.eval_str("byte < 200.5").unwrap(), Value::Bool(true));
+
+        assert_eq!(interpreter.eval_str("(byte + 50) as u16 * 2").unwrap(), Value::TypedInt(500, Type::U16));
+        assert_eq!(interpreter.eval_str("byte as i16 - 300").unwrap(), Value::TypedInt(-100, Type::I16));
+        assert_eq!(interpreter.eval_str("300 as! u8").unwrap(), Value::TypedInt(44, Type::U8));
+        assert_eq!(interpreter.eval_str("2.7 as! u8").unwrap(), Value::TypedInt(2, Type::U8));
+        assert_eq!(interpreter.eval_str("1000.0 as! u8").unwrap(), Value::TypedInt(255, Type::U8));
+        assert_eq!(interpreter.eval_str("2.0 as u8").unwrap(), Value::TypedInt(2, Type::U8));
+        assert_eq!(interpreter.eval_str("'42' as u8 + 1").unwrap(), Value::TypedInt(43, Type::U8));
+        assert_eq!(interpreter.eval_str("byte as string").unwrap(), Value::from("200"));
+        assert_eq!(interpreter.eval_str("'true' as bool").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("2 as! bool").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("true as f32").unwrap(), Value::Float(1.0));
+
+        assert!(matches!(interpreter.eval_str("300 as u8"), Err(Error::RuntimeError(RuntimeError::LossyCast(_, Type::U8)))));
+        assert!(matches!(interpreter.eval_str("2.5 as i32"), Err(Error::RuntimeError(RuntimeError::LossyCast(_, Type::I32)))));
+        assert!(matches!(interpreter.eval_str("2 as bool"), Err(Error::RuntimeError(RuntimeError::LossyCast(_, Type::Bool)))));
+        assert!(matches!(interpreter.eval_str("'abc' as i64"), Err(Error::RuntimeError(RuntimeError::InvalidCast(_, Type::I64)))));
+        assert_eq!(interpreter.eval_str("2.5 as u8 except { LossyCast => 0 }").unwrap(), Value::Int(0));
+
+        // a finite float too large for an `f32` only becomes infinity with `as!`
+        assert!(matches!(interpreter.eval_str("1e39 as f32"), Err(Error::RuntimeError(RuntimeError::LossyCast(_, Type::F32)))));
+        assert!(matches!(interpreter.eval_str("'-1e39' as f32"), Err(Error::RuntimeError(RuntimeError::LossyCast(_, Type::F32)))));
+        assert_eq!(interpreter.eval_str("1e39 as! f32").unwrap(), Value::Float(f64::INFINITY));
+        assert_eq!(interpreter.eval_str("'-1e39' as! f32").unwrap(), Value::Float(f64::NEG_INFINITY));
+        assert_eq!(interpreter.eval_str("'inf' as f32").unwrap(), Value::Float(f64::INFINITY));
+        assert_eq!(interpreter.eval_str("3.5e38 as f64").unwrap(), Value::Float(3.5e38));
+    }
+
+    #[test]
+    fn test_arithmetic_faults_are_errors() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let minus_one := 0 - 1\nlet min: i64 = (0 - 9223372036854775807) - 1").unwrap();
+        assert!(matches!(
+            interpreter.eval_str("1 / 0"),
+            Err(Error::RuntimeError(RuntimeError::DivisionByZero(Operator::Div, operands))) if operands == vec![Value::Int(1), Value::Int(0)]
+        ));
+        assert!(matches!(interpreter.eval_str("7 % 0"), Err(Error::RuntimeError(RuntimeError::DivisionByZero(Operator::Mod, _)))));
+        assert!(matches!(interpreter.eval_str("1 << 64"), Err(Error::RuntimeError(RuntimeError::InvalidShift(Operator::BitShiftLeft, _, Type::I64)))));
+        assert!(matches!(interpreter.eval_str("1 >> minus_one"), Err(Error::RuntimeError(RuntimeError::InvalidShift(Operator::BitShiftRight, _, Type::I32)))));
+        assert!(matches!(interpreter.eval_str("2 ** minus_one"), Err(Error::RuntimeError(RuntimeError::NegativeExponent(Operator::Pow, _)))));
+        assert!(matches!(interpreter.eval_str("min / minus_one"), Err(Error::RuntimeError(RuntimeError::IntegerOverflow(Operator::Div, _, Type::I64)))));
+        assert_eq!(interpreter.eval_str("1 / 0 except { DivisionByZero => 0 }").unwrap(), Value::Int(0));
+        // the session survives the errors
+        assert_eq!(interpreter.eval_str("minus_one").unwrap(), Value::Int(-1));
+
+        assert_eq!(interpreter.eval_str("1.0 / 0.0").unwrap(), Value::Float(f64::INFINITY));
+        interpreter.set_strict_float(true);
+        assert!(matches!(interpreter.eval_str("1.0 / 0.0"), Err(Error::RuntimeError(RuntimeError::NonFiniteFloat(Operator::Div, _)))));
+        assert!(matches!(interpreter.eval_str("fn f(x: f64) -> f64 { x % 0 }\nf(1)"), Err(Error::RuntimeError(RuntimeError::NonFiniteFloat(Operator::Mod, _)))));
+        assert_eq!(interpreter.eval_str("1.0 / 4").unwrap(), Value::Float(0.25));
+    }
+
+    #[test]
+    fn test_non_finite_float_variables() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("import 'std:math'\nlet nan: f64 = 0.0 / 0.0\nlet inf: f32 = 1.0 / 0.0\nmut low: f64 = -1.0 / 0.0").unwrap();
+        assert_eq!(interpreter.eval_str("nan == nan").unwrap(), Value::Bool(false));
+        assert_eq!(interpreter.eval_str("inf").unwrap(), Value::Float(f64::INFINITY));
+        assert_eq!(interpreter.eval_str("low").unwrap(), Value::Float(f64::NEG_INFINITY));
+        interpreter.eval_str("low := math.sqrt(-1)").unwrap();
+        assert_eq!(interpreter.eval_str("low == low").unwrap(), Value::Bool(false));
+        // finite values are still range-checked
+        assert!(matches!(interpreter.eval_str("let big: f32 = 1e39"), Err(Error::RuntimeError(RuntimeError::ValueOutOfBounds(_, Type::F32)))));
+        interpreter.eval_str("let max: f32 = 340282346638528859811704183484516925440.0").unwrap();
+
+        interpreter.set_strict_float(true);
+        assert!(matches!(interpreter.eval_str("let y: f64 = 0.0 / 0.0"), Err(Error::RuntimeError(RuntimeError::NonFiniteFloat(Operator::Div, _)))));
+        assert!(matches!(interpreter.eval_str("let z: f32 = 1.0 / 0.0"), Err(Error::RuntimeError(RuntimeError::NonFiniteFloat(Operator::Div, _)))));
+        assert!(matches!(interpreter.eval_str("let root: f64 = math.sqrt(-1)"), Err(Error::RuntimeError(RuntimeError::ValueOutOfBounds(_, Type::F64)))));
+        assert!(matches!(interpreter.eval_str("low := math.sqrt(-1)"), Err(Error::RuntimeError(RuntimeError::ValueOutOfBounds(_, Type::F64)))));
+        assert!(matches!(interpreter.eval_str("fn f(x: f32) -> f32 { x }\nf(inf)"), Err(Error::RuntimeError(RuntimeError::ValueOutOfBounds(_, Type::F32)))));
+    }
+
+    #[test]
+    fn test_lists() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("mut xs := [1, 2, 3]\nlet words: [string] = ['a', 'b']").unwrap();
+        assert_eq!(interpreter.eval_str("xs[0] + xs[-1]").unwrap(), Value::Int(4));
+        assert_eq!(interpreter.eval_str("xs[1:]").unwrap(), Value::List(vec![Value::Int(2), Value::Int(3)]));
+        assert_eq!(interpreter.eval_str("xs[:-1] == [1, 2]").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("xs[5:]").unwrap(), Value::List(vec![]));
+        assert_eq!(interpreter.eval_str("2 in xs").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("'c' in words").unwrap(), Value::Bool(false));
+
+        interpreter.eval_str("xs.push(4)\nxs.insert(0, 0)").unwrap();
+        assert_eq!(interpreter.eval_str("xs.len()").unwrap(), Value::Int(5));
+        assert_eq!(interpreter.eval_str("xs.pop()").unwrap(), Value::Int(4));
+        assert_eq!(interpreter.eval_str("xs.remove(index: -1)").unwrap(), Value::Int(3));
+        assert_eq!(interpreter.eval_str("xs").unwrap().to_string(), "[0, 1, 2]");
+
+        // lists are passed by `mut` reference and copied by `let`
+        interpreter.eval_str("fn add(mut list: [i32], value: i32) { list.push(value) }\nlet copy := xs\nadd(mut xs, 9)").unwrap();
+        assert_eq!(interpreter.eval_str("xs").unwrap().to_string(), "[0, 1, 2, 9]");
+        assert_eq!(interpreter.eval_str("copy").unwrap().to_string(), "[0, 1, 2]");
+
+        assert!(matches!(interpreter.eval_str("xs[4]"), Err(Error::RuntimeError(RuntimeError::IndexOutOfBounds(4, 4)))));
+        assert!(matches!(interpreter.eval_str("xs.push('x')"), Err(Error::RuntimeError(RuntimeError::ValueNotOfType(_, Type::I32)))));
+        assert!(matches!(
+            interpreter.eval_str("add(copy, 1)"),
+            Err(Error::RuntimeError(RuntimeError::BorrowMismatch(function, param))) if function == "add" && param == "list"
+        ));
+        assert!(matches!(
+            interpreter.eval_str("add(mut copy, 1)"),
+            Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name))) if name == "copy"
+        ));
+
+        interpreter.eval_str("xs[0] := 5\nxs[-1] := xs[0] * 2").unwrap();
+        assert_eq!(interpreter.eval_str("xs").unwrap().to_string(), "[5, 1, 2, 10]");
+        interpreter.eval_str("mut grid := [[0, 0], [0, 0]]\nlet row := grid[1]\ngrid[1][0] := 7").unwrap();
+        assert_eq!(interpreter.eval_str("grid").unwrap().to_string(), "[[0, 0], [7, 0]]");
+        assert_eq!(interpreter.eval_str("row").unwrap().to_string(), "[0, 0]");
+        interpreter.eval_str("mut ages := {'ann': 31}\nages['bob'] := 42\nages['ann'] := 32").unwrap();
+        assert_eq!(interpreter.eval_str("ages").unwrap().to_string(), "{ann: 32, bob: 42}");
+        assert!(matches!(interpreter.eval_str("xs[4] := 1"), Err(Error::RuntimeError(RuntimeError::IndexOutOfBounds(4, 4)))));
+        assert!(matches!(interpreter.eval_str("xs[0] := 'x'"), Err(Error::RuntimeError(RuntimeError::ValueNotOfType(_, Type::I32)))));
+        assert!(matches!(
+            interpreter.eval_str("copy[0] := 1"),
+            Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name))) if name == "copy"
+        ));
+
+        assert_eq!(interpreter.eval_str("grid[1].len() + [1, 2, 3].len()").unwrap(), Value::Int(5));
+        assert_eq!(interpreter.eval_str("{'a': 1}.keys()").unwrap().to_string(), "[a]");
+        // a change to an element or a literal would be lost
+        assert!(matches!(
+            interpreter.eval_str("grid[0].push(1)"),
+            Err(Error::RuntimeError(RuntimeError::TemporaryReceiver(call))) if call == "list.push"
+        ));
+        assert_eq!(interpreter.eval_str("grid").unwrap().to_string(), "[[0, 0], [7, 0]]");
+    }
+
+    #[test]
+    fn test_maps() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("mut ages := {'ann': 31, 'bob': 42}\nlet grid := {(0, 1): 'a', (2, 3): 'b'}").unwrap();
+        assert_eq!(interpreter.eval_str("ages['bob']").unwrap(), Value::Int(42));
+        assert_eq!(interpreter.eval_str("grid[(2, 3)]").unwrap(), Value::from("b"));
+        assert_eq!(interpreter.eval_str("ages.get('zed')").unwrap(), Value::None);
+        assert_eq!(interpreter.eval_str("'ann' in ages").unwrap(), Value::Bool(true));
+
+        // setting an existing key keeps its place
+        interpreter.eval_str("ages.set('cid', 7)\nages.set(key: 'ann', value: 32)").unwrap();
+        assert_eq!(interpreter.eval_str("ages.keys()").unwrap().to_string(), "[ann, bob, cid]");
+        assert_eq!(interpreter.eval_str("ages.remove('bob')").unwrap(), Value::Int(42));
+        assert_eq!(interpreter.eval_str("ages.entries()").unwrap().to_string(), "[(ann, 32), (cid, 7)]");
+        assert_eq!(interpreter.eval_str("ages.contains('bob')").unwrap(), Value::Bool(false));
+        assert_eq!(interpreter.eval_str("ages == {'cid': 7, 'ann': 32}").unwrap(), Value::Bool(true));
+
+        interpreter.eval_str("fn bump(mut m: {string: i32}, key: string) { m.set(key, m[key] + 1) }\nbump(mut ages, 'cid')").unwrap();
+        assert_eq!(interpreter.eval_str("ages['cid']").unwrap(), Value::Int(8));
+
+        assert!(matches!(interpreter.eval_str("ages['zed']"), Err(Error::RuntimeError(RuntimeError::KeyNotFound(key))) if key == Value::from("zed")));
+        assert!(matches!(
+            interpreter.eval_str("mut loose := {}\nloose.set([1], 2)"),
+            Err(Error::RuntimeError(RuntimeError::UnhashableKey(Value::List(_))))
+        ));
+        assert!(matches!(interpreter.eval_str("let floats: {f64: i32} = {}"), Err(Error::RuntimeError(RuntimeError::UnhashableType(_)))));
+    }
+
+    #[test]
+    fn test_strings() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let name := 'héllo wörld'\nlet raw := r'{x}'\nlet csv := 'a,b,,c'\nlet sep := ', '").unwrap();
+        assert_eq!(interpreter.eval_str("'apple' < 'banana'").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("'b' >= 'abc'").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("'x' == r'x'").unwrap(), Value::Bool(true));
+        // rawness is how a string was written, not part of its value
+        assert_eq!(interpreter.eval_str("[r'x', 'y'] == ['x', r'y']").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("let pair := (r'x', 1)\npair == ('x', 1)").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("{r'x': 1}['x'] == {'x': 1}[r'x']").unwrap(), Value::Bool(true));
+        assert_eq!(interpreter.eval_str("raw").unwrap(), Value::from("{x}"));
+        assert_eq!(interpreter.eval_str("'ab' * 3").unwrap(), Value::from("ababab"));
+        assert!(matches!(interpreter.eval_str("2 * raw"), Ok(Value::String(s, true)) if s == "{x}{x}"));
+        assert!(matches!(interpreter.eval_str("'ab' * (0 - 1)"), Err(Error::RuntimeError(RuntimeError::NegativeRepetition(Operator::Mul, _)))));
+
+        // positions count characters, not bytes
+        assert_eq!(interpreter.eval_str("name[1]").unwrap(), Value::from("é"));
+        assert_eq!(interpreter.eval_str("name[-4]").unwrap(), Value::from("ö"));
+        assert_eq!(interpreter.eval_str("name[1:4]").unwrap(), Value::from("éll"));
+        assert!(matches!(interpreter.eval_str("raw[1:]"), Ok(Value::String(s, true)) if s == "x}"));
+        assert!(matches!(interpreter.eval_str("name[11]"), Err(Error::RuntimeError(RuntimeError::IndexOutOfBounds(11, 11)))));
+        assert_eq!(interpreter.eval_str("'wör' in name").unwrap(), Value::Bool(true));
+
+        assert_eq!(interpreter.eval_str("name.len()").unwrap(), Value::Int(11));
+        assert_eq!(interpreter.eval_str("name.upper()").unwrap(), Value::from("HÉLLO WÖRLD"));
+        assert!(matches!(interpreter.eval_str("raw.upper()"), Ok(Value::String(s, true)) if s == "{X}"));
+        assert_eq!(interpreter.eval_str("csv.split(',')").unwrap().to_string(), "[a, b, , c]");
+        assert_eq!(interpreter.eval_str("sep.join(['x', 'y'])").unwrap(), Value::from("x, y"));
+        assert_eq!(interpreter.eval_str("sep.trim()").unwrap(), Value::from(","));
+        assert_eq!(interpreter.eval_str("name.replace(old: 'l', new: 'L')").unwrap(), Value::from("héLLo wörLd"));
+        assert_eq!(interpreter.eval_str("name.starts_with('hé')").unwrap(), Value::Bool(true));
+
+        // any value can have its methods called, not only a variable
+        assert_eq!(interpreter.eval_str("'abc'.upper()").unwrap(), Value::from("ABC"));
+        assert_eq!(interpreter.eval_str("csv.split(',')[0].upper()").unwrap(), Value::from("A"));
+        assert_eq!(interpreter.eval_str("fn greet() -> string { 'hi ' }\ngreet().trim().len()").unwrap(), Value::Int(2));
+        assert_eq!(interpreter.eval_str("(name + '!').len()").unwrap(), Value::Int(12));
+        assert!(matches!(interpreter.eval_str("r'{x}'.upper()"), Ok(Value::String(s, true)) if s == "{X}"));
+        assert!(matches!(interpreter.eval_str("'abc'.shout()"), Err(Error::RuntimeError(RuntimeError::FunctionNotFound(call))) if call == "string.shout"));
+        assert_eq!(interpreter.eval_str("name.contains('xyz')").unwrap(), Value::Bool(false));
+        assert_eq!(interpreter.eval_str("name.find('w')").unwrap(), Value::Int(6));
+        assert_eq!(interpreter.eval_str("name.find('xyz')").unwrap(), Value::None);
+        assert_eq!(interpreter.eval_str("let n := 2\n'{n}é{n + 1}'").unwrap(), Value::from("2é3"));
+    }
+
+    #[test]
+    fn test_tuples() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let t := (1, ('a', 2.5), true)\nmut p := (1, (2, 3))").unwrap();
+        assert_eq!(interpreter.eval_str("t.0").unwrap(), Value::Int(1));
+        assert_eq!(interpreter.eval_str("t.1.0").unwrap(), Value::from("a"));
+        assert_eq!(interpreter.eval_str("t.1.1 + 1").unwrap(), Value::Float(3.5));
+        assert_eq!(interpreter.eval_str("[(1, 2), (3, 4)][1].0").unwrap(), Value::Int(3));
+        assert!(matches!(interpreter.eval_str("t.3"), Err(Error::RuntimeError(RuntimeError::IndexOutOfBounds(3, 3)))));
+
+        interpreter.eval_str("p.1.0 := 20\np.0 := p.0 + 5").unwrap();
+        assert_eq!(interpreter.eval_str("p").unwrap().to_string(), "(6, (20, 3))");
+        assert!(matches!(interpreter.eval_str("t.0 := 2"), Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name))) if name == "t"));
+
+        interpreter.eval_str("fn scale((x, y): (i32, i32), by: i32) -> (i32, i32) { (x * by, y * by) }").unwrap();
+        assert_eq!(interpreter.eval_str("scale((1, 2), 3)").unwrap().to_string(), "(3, 6)");
+        assert_eq!(interpreter.eval_str("scale(p.1, 2).1").unwrap(), Value::Int(6));
+        interpreter.eval_str("fn label(((a, b), c): ((i32, i32), string)) -> string { '{c}{a}{b}' }").unwrap();
+        assert_eq!(interpreter.eval_str("label(((1, 2), 'z'))").unwrap(), Value::from("z12"));
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("mut i := 0\nmut total := 0\nwhile i < 4 {\n    i += 1\n    total += i\n}").unwrap();
+        assert_eq!(interpreter.eval_str("total").unwrap(), Value::Int(10));
+        interpreter.eval_str("total -= 4\ntotal *= 3\ntotal /= 2\ntotal %= 5").unwrap();
+        assert_eq!(interpreter.eval_str("total").unwrap(), Value::Int(4));
+
+        interpreter.eval_str("mut s := 'ab'\ns *= 2\ns += 'c'").unwrap();
+        assert_eq!(interpreter.eval_str("s").unwrap(), Value::from("ababc"));
+
+        interpreter.eval_str("mut p := (1, (2, 3))\np.1.0 += 5\nmut a := 1\nmut b := 2\n(a, b) += (10, 20)").unwrap();
+        assert_eq!(interpreter.eval_str("p").unwrap().to_string(), "(1, (7, 3))");
+        assert_eq!(interpreter.eval_str("(a, b)").unwrap().to_string(), "(11, 22)");
+
+        interpreter.eval_str("let c := 1").unwrap();
+        assert!(matches!(interpreter.eval_str("c += 1"), Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name))) if name == "c"));
+        assert!(matches!(interpreter.eval_str("i /= 0"), Err(Error::RuntimeError(_))));
+        assert_eq!(interpreter.eval_str("i").unwrap(), Value::Int(4));
+    }
+
+    #[test]
+    fn test_assignments_in_blocks_stick() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let x: i32\nif true {\n    x := 5\n}").unwrap();
+        assert_eq!(interpreter.eval_str("x").unwrap(), Value::TypedInt(5, Type::I32));
+
+        let source = "fn f() -> i32 {\n    let found: i32\n    mut n := 0\n    while n < 3 {\n        n += 1\n        let step := n * 2\n        if step > 4 { found := step }\n    }\n    found\n}\nf()";
+        assert_eq!(interpreter.eval_str(source).unwrap(), Value::TypedInt(6, Type::I32));
+
+        // a block's own declaration shadows the outer one until the block ends
+        interpreter.eval_str("mut v := 1\nif true {\n    let v := 2\n    v\n}\nif true {\n    v := v + 10\n}").unwrap();
+        assert_eq!(interpreter.eval_str("v").unwrap(), Value::Int(11));
+    }
+
+    #[test]
+    fn test_reference_params() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("fn swap(mut a: i32, mut b: i32) {\n    let t := a\n    a := b\n    b := t\n}\nmut x: i32 = 1\nmut y: i32 = 2\nswap(mut x, mut y)").unwrap();
+        assert_eq!(interpreter.eval_str("(x, y)").unwrap(), Value::Tuple(vec![Value::TypedInt(2, Type::I32), Value::TypedInt(1, Type::I32)]));
+        // the callee writes through to a variable of its own caller too
+        interpreter.eval_str("fn bump(mut n: i32, by: i32) { n += by }\nfn bump_twice(mut n: i32) {\n    bump(mut n, 1)\n    bump(by: 10, n: mut n)\n}\nbump_twice(mut x)").unwrap();
+        assert_eq!(interpreter.eval_str("x").unwrap(), Value::TypedInt(13, Type::I32));
+
+        // params that are not `mut` are copies
+        interpreter.eval_str("fn reset(mut xs: [i32], ys: [i32]) -> [i32] {\n    xs := []\n    ys\n}\nmut a: [i32] = [1]\nlet b: [i32] = [2]\nreset(mut a, b)").unwrap();
+        assert_eq!(interpreter.eval_str("a").unwrap().to_string(), "[]");
+        assert_eq!(interpreter.eval_str("b").unwrap().to_string(), "[2]");
+
+        assert!(matches!(
+            interpreter.eval_str("swap(x, mut y)"),
+            Err(Error::RuntimeError(RuntimeError::BorrowMismatch(function, param))) if function == "swap" && param == "a"
+        ));
+        assert!(matches!(
+            interpreter.eval_str("print(mut x)"),
+            Err(Error::RuntimeError(RuntimeError::BorrowMismatch(function, param))) if function == "print" && param == "value"
+        ));
+        assert!(matches!(
+            interpreter.eval_str("let z: i32 = 3\nswap(mut x, mut z)"),
+            Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name))) if name == "z"
+        ));
+        assert_eq!(interpreter.eval_str("(x, y)").unwrap(), Value::Tuple(vec![Value::TypedInt(13, Type::I32), Value::TypedInt(1, Type::I32)]));
+    }
+
+    #[test]
+    fn test_tail_calls() {
+        let mut interpreter = Interpreter::new();
+        // deeper than the max call depth, which tail calls do not add to
+        interpreter.eval_str("fn count(n: i64, acc: i64) -> i64 {\n    if n == 0 { acc } else { count(n - 1, acc + 1) }\n}").unwrap();
+        assert_eq!(interpreter.eval_str("count(20000, 0)").unwrap(), Value::TypedInt(20000, Type::I64));
+        interpreter.eval_str("fn even(n: i64) -> bool {\n    if n == 0 { true } else { odd(n - 1) }\n}\nfn odd(n: i64) -> bool {\n    if n == 0 { false } elif n == 1 { true } else { even(n - 1) }\n}").unwrap();
+        assert_eq!(interpreter.eval_str("even(15001)").unwrap(), Value::Bool(false));
+        interpreter.eval_str("fn sum_to(n: i64, mut acc: i64) {\n    if n > 0 {\n        acc += n\n        sum_to(n - 1, mut acc)\n    }\n}\nmut total: i64 = 0\nsum_to(12000, mut total)").unwrap();
+        assert_eq!(interpreter.eval_str("total").unwrap(), Value::TypedInt(72006000, Type::I64));
+
+        // the value is returned as the type of the function the call was made in
+        interpreter.eval_str("fn seven() { 7 }\nfn typed() -> i32 { seven() }").unwrap();
+        assert_eq!(interpreter.eval_str("typed()").unwrap(), Value::TypedInt(7, Type::I32));
+        // a function the caller does not see is called as usual
+        interpreter.eval_str("fn outer(n: i32) -> i32 {\n    fn helper(k: i32) -> i32 { k * 2 }\n    helper(n)\n}").unwrap();
+        assert_eq!(interpreter.eval_str("outer(4)").unwrap(), Value::TypedInt(8, Type::I32));
+
+        // scoping is dynamic, so a tail call still sees the variables of its caller
+        interpreter.eval_str("fn reveal() -> i64 { secret }\nfn f1() -> i64 {\n    let secret: i64 = 5\n    reveal() + 0\n}\nfn f2() -> i64 {\n    let secret: i64 = 5\n    reveal()\n}").unwrap();
+        assert_eq!(interpreter.eval_str("f1()").unwrap(), Value::TypedInt(5, Type::I64));
+        assert_eq!(interpreter.eval_str("f2()").unwrap(), interpreter.eval_str("f1()").unwrap());
+        // but a global is found before a variable of the caller by the same name
+        interpreter.eval_str("let limit: i64 = 1\nfn read_limit() -> i64 { limit }\nfn shadowing() -> i64 {\n    let limit: i64 = 2\n    read_limit()\n}").unwrap();
+        assert_eq!(interpreter.eval_str("shadowing()").unwrap(), Value::TypedInt(1, Type::I64));
+        interpreter.eval_str("fn countdown(n: i64) -> i64 {\n    let seen := n\n    if n == 0 { seen } else { countdown(n - 1) }\n}").unwrap();
+        assert_eq!(interpreter.eval_str("countdown(12000)").unwrap(), Value::TypedInt(0, Type::I64));
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_call_depth(50);
+        interpreter.eval_str("fn down(n: i64) -> i64 {\n    if n == 0 { 0 } else { 1 + down(n - 1) }\n}").unwrap();
+        assert_eq!(interpreter.eval_str("down(49)").unwrap(), Value::TypedInt(49, Type::I64));
+        assert!(matches!(
+            interpreter.eval_str("down(50)"),
+            Err(Error::RuntimeError(RuntimeError::StackOverflow(name))) if name == "down"
+        ));
+        assert_eq!(interpreter.eval_str("down(80) except {\nStackOverflow(name) => name\n}").unwrap(), Value::from("down"));
+        assert_eq!(interpreter.eval_str("down(10)").unwrap(), Value::TypedInt(10, Type::I64));
+    }
+
+    #[test]
+    fn test_stack_overflow_on_default_thread() {
+        // calls grow the stack as they nest, so a thread with the default
+        // size reaches the max depth rather than the end of its stack, and
+        // finding `down` does not go through the frames of its callers
+        let result = std::thread::spawn(|| {
+            let mut interpreter = Interpreter::new();
+            interpreter.eval_str("fn down(n: i64) -> i64 {\n    if n == 0 { 0 } else { 1 + down(n - 1) }\n}").unwrap();
+            assert_eq!(interpreter.eval_str("down(9999)").unwrap(), Value::TypedInt(9999, Type::I64));
+            matches!(interpreter.eval_str("down(10000)"), Err(Error::RuntimeError(RuntimeError::StackOverflow(_))))
+        }).join();
+        assert!(matches!(result, Ok(true)));
+    }
+
+    #[test]
+    fn test_stack_traces() {
+        let mut interpreter = Interpreter::new();
+        let source = "fn inner(k: string, xs: [i32]) -> i32 {\n    k + missing\n}\nfn outer(n: i32) -> i32 {\n    let r := inner('a long value for {n} to be shortened', [1, 2])\n    r\n}\nouter(3)";
+        assert!(matches!(interpreter.eval_str(source), Err(Error::RuntimeError(RuntimeError::VariableNotFound(_)))));
+        let trace = interpreter.trace();
+        assert_eq!(trace.iter().map(|frame| frame.to_string()).collect::<Vec<_>>(), vec![
+            "inner(k: 'a long value for 3 to b..., xs: [1, 2]) at 5:14",
+            "outer(n: 3) at 8:1",
+        ]);
+
+        // a caught error leaves no trace behind for the next one
+        interpreter.eval_str("fn fails() { error Oops }\nfn safe() { fails() except 0 }\nsafe()").unwrap();
+        assert!(interpreter.trace().is_empty());
+        assert!(interpreter.eval_str("fn later() -> i32 { 1 / 0 }\nlater()").is_err());
+        assert_eq!(interpreter.trace().len(), 1);
+        assert!(interpreter.eval_str("print(value: 1)\n1 / 0").is_err());
+        assert!(interpreter.trace().is_empty());
+
+        // calls inside a tuple argument are parsed from a part of the call
+        assert!(interpreter.eval_str("fn broken(n: i32) -> i32 { n + missing }\nfn first(p: (i32, i32)) -> i32 { 0 }\nfirst((1,  broken(2)))").is_err());
+        assert_eq!(interpreter.trace().iter().map(|frame| frame.to_string()).collect::<Vec<_>>(), vec!["broken(n: 2) at 3:12"]);
+        assert!(interpreter.eval_str("let t := (1, (2, broken(3)))").is_err());
+        assert_eq!(interpreter.trace().iter().map(|frame| frame.to_string()).collect::<Vec<_>>(), vec!["broken(n: 3) at 1:18"]);
+
+        interpreter.set_max_call_depth(20);
+        interpreter.eval_str("fn down(n: i64) -> i64 {\n    if n == 0 { 0 } else { 1 + down(n - 1) }\n}").unwrap();
+        assert!(interpreter.eval_str("down(30)").is_err());
+        assert_eq!(
+            traceback(&interpreter.trace()),
+            "Traceback, most recent call last:\n  down(n: 30) at 1:1\n  down(n: 29) at 2:32\n  ... 18 more calls of down"
+        );
+    }
+
+    #[test]
+    fn test_garbage_collection() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("fn fib(n: i32) -> i32 {\n    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }\n}\nmut kept := (1, 2)").unwrap();
+        interpreter.eval_str("fib(10)").unwrap();
+        let live = interpreter.collect_garbage().live;
+        // calls and loops leave nothing behind once collected
+        assert_eq!(interpreter.eval_str("fib(15)").unwrap(), Value::TypedInt(610, Type::I32));
+        interpreter.eval_str("mut i := 0\nwhile i < 2000 {\n    let step := (i, i)\n    i += 1\n}").unwrap();
+        let stats = interpreter.collect_garbage();
+        assert_eq!(stats.live, live + 1);
+        assert!(stats.freed > 0);
+        // the loop allocated past the threshold, which collected on its own
+        assert!(stats.collections > 2);
+        assert_eq!(interpreter.eval_str("kept").unwrap(), Value::Tuple(vec![Value::Int(1), Value::Int(2)]));
+        assert_eq!(interpreter.eval_str("i").unwrap(), Value::Int(2000));
+
+
+        // elements are values rather than heap slots, so a list or map that holds
+        // another, or a copy of itself, is freed with the variable it is in
+        let live = interpreter.collect_garbage().live;
+        interpreter.eval_str("fn nest(n: i32) -> i32 {\n    mut xs := [[n]]\n    xs.push(xs[0])\n    mut m := {'xs': xs}\n    m['again'] := m['xs']\n    xs.len() + m.len()\n}").unwrap();
+        interpreter.eval_str("mut j := 0\nwhile j < 200 {\n    nest(j)\n    j += 1\n}").unwrap();
+        assert_eq!(interpreter.collect_garbage().live, live + 2);
+        assert_eq!(interpreter.eval_str("nest(1)").unwrap(), Value::Int(4));
+    }
+}
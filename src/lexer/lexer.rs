@@ -8,6 +8,61 @@ use std::collections::HashMap;
 pub struct Lexer {
     source: String,
     pub tokens: Vec<Token>,
+    /// Where each token is in the source, when they were lexed from it.
+    pub spans: Vec<Span>,
+    /// Where the rest of the source starts.
+    position: Position,
+}
+
+/// A line and column of the source, both counted from 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self { line: 1, column: 1 }
+    }
+}
+
+impl Position {
+    fn advance(&mut self, text: &str) {
+        for c in text.chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+}
+
+/// The part of the source from `start` up to, but not including, `end`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// The span of the `width` characters from `start` on its line.
+    pub fn at(start: Position, width: usize) -> Span {
+        Span { start, end: Position { line: start.line, column: start.column + width } }
+    }
+
+    /// The span from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span { start: self.start, end: other.end }
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -27,11 +82,34 @@ pub enum LexerError {
     NoMatchingBracket(String),
 }
 
+impl std::fmt::Display for LexerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexerError::NoMatchingSymbol(text) => write!(f, "unknown symbol `{}`", text),
+            LexerError::NoMatchingKeyword(text) => write!(f, "unknown keyword `{}`", text),
+            LexerError::NoMatchingBaseType(text) => write!(f, "unknown type `{}`", text),
+            LexerError::NoMatchingIdentifier(text) => write!(f, "invalid identifier `{}`", text),
+            LexerError::NoMatchingCustomType(text) => write!(f, "invalid type name `{}`", text),
+            LexerError::NoMatchingLiteral(text) => write!(f, "invalid literal `{}`", text),
+            LexerError::CouldNotTokenizeWhitespace => write!(f, "invalid whitespace"),
+            LexerError::CouldNotTokenize(text) => write!(f, "unexpected `{}`", text),
+            LexerError::InvalidFloatLiteral(text) => write!(f, "invalid float literal `{}`", text),
+            LexerError::InvalidIntegerLiteral(text) => write!(f, "invalid integer literal `{}`", text),
+            // an unclosed string runs to the end of the source
+            LexerError::InvalidStringLiteral(text) => write!(f, "invalid string literal `{}`", text.lines().next().unwrap_or_default()),
+            LexerError::InvalidComment(text) => write!(f, "invalid comment `{}`", text),
+            LexerError::NoMatchingBracket(text) => write!(f, "no matching bracket for `{}`", text),
+        }
+    }
+}
+
 impl Lexer {
     pub fn new(source: String) -> Self {
         Self {
             source,
             tokens: Vec::new(),
+            spans: Vec::new(),
+            position: Position::default(),
         }
     }
 
@@ -39,9 +117,17 @@ impl Lexer {
         Self {
             source: String::new(),
             tokens,
+            spans: Vec::new(),
+            position: Position::default(),
         }
     }
 
+    /// Where the rest of the source starts, which is where the token that
+    /// could not be lexed starts once `tokenize` fails.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
     pub fn tokenize(&mut self) -> Result<(), LexerError> {
         while self.source.len() > 0 {
             self.tokenize_next()?;
@@ -54,35 +140,35 @@ impl Lexer {
         while cursor < self.tokens.len() {
             match self.tokens[cursor] {
                 Token::Whitespace(Whitespace::Space) => {
-                    self.tokens.remove(cursor); // no need for spaces
+                    self.remove(cursor); // no need for spaces
                 },
                 Token::Whitespace(Whitespace::Newline) => {
                     if cursor == 0 {
-                        self.tokens.remove(cursor); // remove leading whitespace
+                        self.remove(cursor); // remove leading whitespace
                     } else if cursor > 1 
                     && self.tokens[cursor-1] == Token::Whitespace(Whitespace::Newline) 
                     && self.tokens[cursor-2] == Token::Whitespace(Whitespace::Newline) {
-                        self.tokens.remove(cursor); // remove 3+ newlines (allow max 2 newlines)
+                        self.remove(cursor); // remove 3+ newlines (allow max 2 newlines)
                     } else if cursor + 1 < self.tokens.len()
                     && match self.tokens[cursor + 1] {
                         Token::Bracket(Bracket::CloseBrace) | Token::Bracket(Bracket::CloseBracket) | Token::Bracket(Bracket::CloseParen) => true,
                         _ => false
                     }{
-                        self.tokens.remove(cursor); // remove newline before closing brackets
+                        self.remove(cursor); // remove newline before closing brackets
                     } else if cursor - 1 > 0
                     && match self.tokens[cursor - 1] {
                         Token::Bracket(Bracket::OpenBrace) | Token::Bracket(Bracket::OpenBracket) | Token::Bracket(Bracket::OpenParen) => true,
                         _ => false
                     }{
-                        self.tokens.remove(cursor); // remove newline after opening brackets
+                        self.remove(cursor); // remove newline after opening brackets
                     } else if cursor == self.tokens.len() - 1 {   
-                        self.tokens.remove(cursor); // remove newline before EOF
+                        self.remove(cursor); // remove newline before EOF
                     } else {
                         cursor += 1; // keep the newline if it's not leading or consecutive
                     }
                 },
                 Token::Comment(_) => {
-                    self.tokens.remove(cursor);
+                    self.remove(cursor);
                 },
                 _ => {
                     cursor += 1;
@@ -91,10 +177,19 @@ impl Lexer {
         }
     }
 
+    fn remove(&mut self, index: usize) {
+        if self.spans.len() == self.tokens.len() {
+            self.spans.remove(index);
+        }
+        self.tokens.remove(index);
+    }
+
     pub fn tokenize_next(&mut self) -> Result<Token, LexerError> {
+        let start = self.position;
         let (token, _) = self.tokenize_next_with_index()?;
         if token != Token::Whitespace(Whitespace::Space) {
-            self.tokens.push(token.clone())
+            self.tokens.push(token.clone());
+            self.spans.push(Span { start, end: self.position });
         } else {
             return self.tokenize_next()
         }
@@ -105,14 +200,19 @@ impl Lexer {
         let result = {
             let whitespace_re = Regex::new(r"^[\s\t\n]+").unwrap();
             let string_re = Regex::new(r#"^("|'|r"|r')"#).unwrap();
-            let bool_re = Regex::new(r"^(true|false)").unwrap();
-            let none_re = Regex::new(r"^none").unwrap();
-            let number_re = Regex::new(r"^-?[0-9]+([.][0-9]+)?([eE][+-]?[0-9]+)?").unwrap();
+            let bool_re = Regex::new(r"^(true|false)\b").unwrap();
+            let none_re = Regex::new(r"^none\b").unwrap();
+            // a leading `-` is lexed as an operator, negating the literal after it
+            let number_re = Regex::new(r"^[0-9]+([.][0-9]+)?([eE][+-]?[0-9]+)?").unwrap();
             let comment_re = Regex::new(r"^--").unwrap();
             let identifier_re = Regex::new(r"^[_a-z][a-zA-Z0-9_]*").unwrap();
             let custom_type_re = Regex::new(r"^[A-Z][a-zA-Z0-9_]*\??").unwrap();
+            let tuple_index_re = Regex::new(r"^[0-9]+").unwrap();
 
-            if whitespace_re.is_match(&self.source) {
+            if self.tokens.last() == Some(&Token::Punctuation(Punctuation::Dot)) && tuple_index_re.is_match(&self.source) {
+                // `t.1.2` indexes a tuple twice rather than with the float `1.2`
+                Self::tokenize_tuple_index(&self.source)
+            } else if whitespace_re.is_match(&self.source) {
                 Self::tokenize_whitespace(&self.source)
             } else if string_re.is_match(&self.source) 
                 || bool_re.is_match(&self.source)
@@ -147,6 +247,7 @@ impl Lexer {
             Err(e) => return Err(e),
         };
         
+        self.position.advance(&self.source[..index]);
         self.source = self.source[index..].to_string();
         Ok((token, index))
     }
@@ -177,13 +278,20 @@ impl Lexer {
         sorted_map.sort_by(|(k, _), (k2, _)| k.len().cmp(&k2.len()));
         sorted_map.reverse();
         for (base_type, token) in sorted_map {
-            if s.starts_with(base_type) {
+            if s.starts_with(base_type) && !Self::continues_word(base_type, &s[base_type.len()..]) {
                 return Ok((token.clone(), base_type.len()));
             }
         }
         Err(LexerError::NoMatchingBaseType(s.clone()))
     }
 
+    fn continues_word(word: &str, rest: &str) -> bool {
+        // a keyword like `in` must not match the start of an identifier like `insert`
+        let ends_alphanumeric = word.chars().last().is_some_and(|c| c.is_alphanumeric());
+        let next_is_word_char = rest.chars().next().is_some_and(|c| c.is_alphanumeric() || c == '_');
+        ends_alphanumeric && next_is_word_char
+    }
+
     pub fn tokenize_custom_type(custom_type: &String) -> Result<(Token, usize), LexerError> {
         let (custom_type, index) = index_until_boundary(custom_type.as_str());
         Ok((Token::custom_type(custom_type), index))
@@ -197,6 +305,14 @@ impl Lexer {
         Ok((Token::Literal(literal), index))
     }
 
+    pub fn tokenize_tuple_index(s: &str) -> Result<(Token, usize), LexerError> {
+        let index = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        match s[..index].parse::<i64>() {
+            Ok(i) => Ok((Token::Literal(Literal::Int(i as i128)), index)),
+            Err(_) => Err(LexerError::InvalidIntegerLiteral(s[..index].to_string())),
+        }
+    }
+
     pub fn tokenize_comment(comment: &String) -> Result<(Token, usize), LexerError> {
         let (comment, index) = index_until_char(comment.as_str(), '\n');
         Ok((Token::Comment(Comment::SingleLine(String::from(comment))), index))
@@ -35,9 +35,9 @@ impl Literal {
         let int_regex = regex::Regex::new(r"^-?\d+$").unwrap();
         
         if int_regex.is_match(literal) {
-            match literal.parse::<i64>() {
-                Ok(i) => Ok((Literal::Int(i), index)),
-                Err(_) => Err(LexerError::InvalidIntegerLiteral(literal.to_string())),
+            match literal.parse::<i128>() {
+                Ok(i) if i <= u64::MAX as i128 => Ok((Literal::Int(i), index)),
+                _ => Err(LexerError::InvalidIntegerLiteral(literal.to_string())),
             }
         } else {
             Err(LexerError::InvalidIntegerLiteral(literal.to_string()))
@@ -86,15 +86,16 @@ impl Literal {
             if literal[cursor..].starts_with(closing_quote) {
                 closed = true;
             }
-            cursor += 1;
+            // a whole character, which may take several bytes
+            cursor += literal[cursor..].chars().next().map_or(1, char::len_utf8);
         }
         Ok((Literal::String(literal[opening_quote.len()..cursor-1].to_string(), raw), cursor))
     }
     
     pub fn tokenize_literal(literal: &String) -> Result<(Literal, usize), LexerError> {
-        let true_re = regex::Regex::new(r"^true").unwrap();
-        let false_re = regex::Regex::new(r"^false").unwrap();
-        let none_re = regex::Regex::new(r"^none").unwrap();
+        let true_re = regex::Regex::new(r"^true\b").unwrap();
+        let false_re = regex::Regex::new(r"^false\b").unwrap();
+        let none_re = regex::Regex::new(r"^none\b").unwrap();
         // 1.23e-4 or 1.23e4 or 1.23 or 1e-4 or 1e4
         let float_re = regex::Regex::new(r"^((-?[0-9]+)\.[0-9]+([eE]-?[0-9]+)?|(-?[0-9]+[eE]-?[0-9]+))").unwrap();
 
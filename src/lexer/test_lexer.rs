@@ -43,10 +43,13 @@ mod test_lexer {
     fn test_tokenize_literal() {
         assert_eq!(Lexer::tokenize_literal(&String::from("true")), Ok((Token::literal(Literal::Bool(true)), 4)));
         assert_eq!(Lexer::tokenize_literal(&String::from("123")), Ok((Token::literal(Literal::Int(123)), 3)));
+        assert_eq!(Lexer::tokenize_literal(&String::from("18446744073709551615")), Ok((Token::literal(Literal::Int(u64::MAX as i128)), 20)));
+        assert!(Lexer::tokenize_literal(&String::from("18446744073709551616")).is_err());
         assert_eq!(Lexer::tokenize_literal(&String::from("123.45")), Ok((Token::literal(Literal::Float(123.45)), 6)));
         assert_eq!(Lexer::tokenize_literal(&String::from("123.45e6")), Ok((Token::literal(Literal::Float(123.45e6)), 8)));
         assert_eq!(Lexer::tokenize_literal(&String::from("\"Hello, world!\"")), Ok((Token::literal(Literal::String("Hello, world!".to_string(), false)), 15)));
         assert_eq!(Lexer::tokenize_literal(&String::from("'Hello, world!'")), Ok((Token::literal(Literal::String("Hello, world!".to_string(), false)), 15)));
+        assert_eq!(Lexer::tokenize_literal(&String::from("r'héllo {x}'")), Ok((Token::literal(Literal::String("héllo {x}".to_string(), true)), 13)));
     }
 
     #[test]
@@ -56,6 +59,36 @@ mod test_lexer {
         assert_eq!(Lexer::tokenize_identifier(&String::from("helloWorld")), Ok((Token::identifier("helloWorld"), 10)));
     }
 
+    #[test]
+    fn test_keywords_need_word_boundary() {
+        let mut lexer = Lexer::new("insert index nonempty origin abs".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        assert_eq!(lexer.tokens, vec![
+            Token::identifier("insert"),
+            Token::identifier("index"),
+            Token::identifier("nonempty"),
+            Token::identifier("origin"),
+            Token::from_keyword("abs").unwrap(),
+        ]);
+    }
+
+    #[test]
+    fn test_tuple_indexes_after_dot() {
+        let mut lexer = Lexer::new("t.1.2 + 1.5".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        assert_eq!(lexer.tokens, vec![
+            Token::identifier("t"),
+            Token::from_symbol(".").unwrap(),
+            Token::literal(Literal::Int(1)),
+            Token::from_symbol(".").unwrap(),
+            Token::literal(Literal::Int(2)),
+            Token::from_symbol("+").unwrap(),
+            Token::literal(Literal::Float(1.5)),
+        ]);
+    }
+
     #[test]
     fn test_tokenize_whitespace() {
         assert_eq!(Lexer::tokenize_whitespace(&String::from("   \t\n")), Ok((Token::Whitespace(Whitespace::Newline), 5)));
@@ -89,5 +122,20 @@ mod test_lexer {
         assert_eq!(lexer.tokenize_next(), Ok(Token::from_symbol("}").unwrap()));
         assert_eq!(lexer.tokenize_next(), Ok(Token::Whitespace(Whitespace::Newline)));
     }
-}
 
+    #[test]
+    fn test_token_positions() {
+        let mut lexer = Lexer::new("let x := 1 -- one\n\n\nf(x,\n  'é', y)".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        assert_eq!(lexer.tokens.len(), lexer.spans.len());
+        let at = |token: Token| {
+            let span = lexer.spans[lexer.tokens.iter().position(|t| *t == token).unwrap()];
+            format!("{}-{}", span.start, span.end)
+        };
+        assert_eq!(at(Token::identifier("x")), "1:5-1:6");
+        assert_eq!(at(Token::identifier("f")), "4:1-4:2");
+        assert_eq!(at(Token::Literal(Literal::String("é".to_string(), false))), "5:3-5:6");
+        assert_eq!(at(Token::identifier("y")), "5:8-5:9");
+    }
+}
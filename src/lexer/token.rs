@@ -22,7 +22,8 @@ pub enum Token {
 
 #[derive(Debug, Clone)]
 pub enum Literal {
-    Int(i64),
+    /// Any integer up to `u64::MAX`, wider than `i64` so that `u64` literals fit.
+    Int(i128),
     Float(f64),
     String(String, bool),
     Bool(bool),
@@ -103,6 +104,13 @@ pub enum Operator {
     MulAssign,
     DivAssign,
     ModAssign,
+    Coalesce,
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Token::Operator(self.clone()).source())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -228,6 +236,7 @@ use std::collections::HashMap;
 pub fn get_keywords_map() -> HashMap<&'static str, Token> {
     let mut keywords = HashMap::new();
     keywords.insert("async", Token::Function(Function::Async));
+    keywords.insert("await", Token::Function(Function::Await));
     keywords.insert("module", Token::Module(Module::Module));
     keywords.insert("import", Token::Module(Module::Import));
     keywords.insert("from", Token::Module(Module::From));
@@ -251,6 +260,8 @@ pub fn get_keywords_map() -> HashMap<&'static str, Token> {
     keywords.insert("type", Token::TypeDeclaration(TypeDeclaration::Type));
     keywords.insert("comp", Token::TypeDeclaration(TypeDeclaration::Component));
     keywords.insert("as", Token::Module(Module::As));
+    keywords.insert("error", Token::Error(Error::Error));
+    keywords.insert("except", Token::Error(Error::Except));
     keywords
 }
 
@@ -301,6 +312,7 @@ pub fn get_symbols_map() -> HashMap<&'static str, Token> {
     symbols.insert("/=", Token::Operator(Operator::DivAssign));
     symbols.insert("%=", Token::Operator(Operator::ModAssign));
     symbols.insert("?", Token::Punctuation(Punctuation::Question));
+    symbols.insert("??", Token::Operator(Operator::Coalesce));
     symbols.insert("!", Token::Punctuation(Punctuation::Exclamation));
     symbols
 }
@@ -377,6 +389,26 @@ impl Token {
             _ => Token::Type(Type::UserDefined(custom_type.to_string())),
         }
     }
+    /// The token as it is written in source, which `to_string` is not for
+    /// keywords, symbols and literals.
+    pub fn source(&self) -> String {
+        let maps = [get_keywords_map(), get_symbols_map(), get_base_types_map()];
+        if let Some(text) = maps.iter().flat_map(|map| map.iter()).find(|(_, token)| *token == self).map(|(text, _)| text) {
+            return text.to_string();
+        }
+        match self {
+            Token::Literal(Literal::String(s, _)) => format!("'{}'", s),
+            Token::Literal(Literal::Char(c)) => format!("'{}'", c),
+            Token::Literal(Literal::Int(i)) => i.to_string(),
+            Token::Literal(Literal::Float(f)) => f.to_string(),
+            Token::Literal(Literal::Bool(b)) => b.to_string(),
+            Token::Literal(Literal::None) => String::from("none"),
+            Token::Whitespace(Whitespace::Newline) => String::from("end of line"),
+            Token::Comment(_) => String::from("--"),
+            token => token.to_string(),
+        }
+    }
+
     pub fn to_string(&self) -> String {
         match self {
             Token::Operator(op) => format!("{:?}", &op),
@@ -53,7 +53,7 @@ pub fn find_matching_bracket(literal: &String, opening_bracket: char, closing_br
         if literal[cursor..].starts_with(closing_bracket) {
             open_brackets -= 1;
         }
-        cursor += 1;
+        cursor += literal[cursor..].chars().next().map_or(1, char::len_utf8);
     }
     Ok(cursor)
 }
@@ -77,9 +77,9 @@ pub fn get_string_interpolations(string: &String) -> Vec<(String, usize)> {
         if string[cursor..].starts_with('{') {
             let match_index = find_matching_bracket(&string[cursor..].to_string(), '{', '}').unwrap();
             interpolations.push((string[cursor+1..cursor+match_index-1].to_string(), cursor));
-            cursor += match_index + 1;
+            cursor += match_index;
         } else {
-            cursor += 1;
+            cursor += string[cursor..].chars().next().map_or(1, char::len_utf8);
         }
     }
     interpolations
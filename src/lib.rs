@@ -0,0 +1,53 @@
+pub mod lexer;
+pub mod repl;
+pub mod parser;
+pub mod ast;
+pub mod environment;
+pub mod interpreter;
+pub mod stdlib;
+pub mod package;
+pub mod checker;
+pub mod vm;
+pub mod diagnostics;
+
+pub use ast::Value;
+pub use interpreter::interpreter::Interpreter;
+
+#[derive(Debug)]
+pub enum Error {
+    LexerError(lexer::LexerError),
+    ParserError(parser::parser::ParseError),
+    ASTError(ast::ASTError),
+    RuntimeError(environment::environment::RuntimeError),
+    TupleError(ast::tuple::TupleError),
+    TypeError(ast::type_node::TypeError),
+    PackageError(package::PackageError),
+    IoError(std::io::Error),
+    /// Every mismatch found by the type checker before evaluation, and where.
+    TypeCheckError(Vec<(checker::checker::TypeCheckError, Option<lexer::Span>)>),
+    /// Unwinds to the enclosing function call, which returns the value.
+    Return(Value),
+    /// Unwinds to the enclosing function call, which makes this call instead.
+    TailCall(Box<environment::function::TailCall>),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::LexerError(e) => write!(f, "{}", e),
+            Error::ParserError(e) => write!(f, "{}", e),
+            Error::ASTError(e) => write!(f, "{}", e),
+            Error::RuntimeError(e) => write!(f, "{}", e),
+            Error::TupleError(e) => write!(f, "{}", e),
+            Error::TypeError(e) => write!(f, "{}", e),
+            Error::PackageError(e) => write!(f, "{}", e),
+            Error::IoError(e) => write!(f, "{}", e),
+            Error::TypeCheckError(errors) => {
+                let errors = errors.iter().map(|(e, _)| e.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", errors.join("\n"))
+            }
+            Error::Return(value) => write!(f, "returned {} outside of a function", value),
+            Error::TailCall(call) => write!(f, "tail call of `{}` outside of a function", call.name),
+        }
+    }
+}
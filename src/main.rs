@@ -1,32 +1,39 @@
-pub mod lexer;
-pub mod repl;
-mod parser;
-mod ast;
-pub mod environment;
-mod interpreter;
-
+use loop_rs::environment::environment::DEFAULT_MAX_DEPTH;
+use loop_rs::{interpreter, package, repl};
 use std::env;
+use std::io::IsTerminal;
 use std::path::Path;
 
-#[derive(Debug)]
-pub enum Error {
-    LexerError(lexer::LexerError),
-    ParserError(parser::parser::ParseError),
-    ASTError(ast::ASTError),
-    RuntimeError(environment::environment::RuntimeError),
-    TupleError(ast::tuple::TupleError),
-    TypeError(ast::type_node::TypeError),
-}
-
 fn main() {
     let args: Vec<String> = env::args().collect();
+    if let Some(command) = args.get(1) {
+        if command == "fetch" || command == "update" {
+            package::cli::run(command, args.get(2).map(|s| s.as_str()));
+            return;
+        }
+    }
     let mut print_ast = false;
     let mut print_tokens = false;
+    let mut strict_float = false;
+    let mut bytecode = false;
+    // errors go to stderr, which is colored only when a person reads it
+    let mut color = std::io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let mut max_depth = DEFAULT_MAX_DEPTH;
     let mut file_path: Option<String> = None;
     for arg in &args[1..] {
         match arg.as_str() {
             "--ast" => print_ast = true,
             "--tokens" => print_tokens = true,
+            "--strict-float" => strict_float = true,
+            "--bytecode" => bytecode = true,
+            "--no-color" => color = false,
+            arg if arg.starts_with("--max-depth=") => match arg["--max-depth=".len()..].parse() {
+                Ok(depth) => max_depth = depth,
+                Err(_) => {
+                    eprintln!("Invalid {}", arg);
+                    return;
+                }
+            },
             _ => {
                 if !arg.starts_with("--") && file_path.is_none() {
                     file_path = Some(arg.clone());
@@ -36,8 +43,8 @@ fn main() {
     }
     if let Some(path_str) = file_path {
         let path = Path::new(&path_str);
-        interpreter::interpreter::run_file(path);
+        interpreter::interpreter::run_file(path, strict_float, bytecode, max_depth, color);
     } else {
-        repl::repl(print_ast, print_tokens);
+        repl::repl(print_ast, print_tokens, strict_float, bytecode, max_depth, color);
     }
 }
@@ -0,0 +1,26 @@
+use crate::package::Project;
+use std::env;
+
+/// Runs `loop fetch` or `loop update [dependency]` for the project around the current directory.
+pub fn run(command: &str, dependency: Option<&str>) {
+    let cwd = env::current_dir().expect("Unable to read current directory");
+    let project = match Project::discover(&cwd) {
+        Some(project) => project,
+        None => {
+            eprintln!("Error: no {} found in {} or its parents", super::MANIFEST_FILE, cwd.display());
+            return;
+        }
+    };
+    let result = match command {
+        "update" => project.update(dependency),
+        _ => project.fetch(),
+    };
+    match result {
+        Ok(lockfile) => {
+            for package in lockfile.packages.iter() {
+                println!("{} {} ({})", package.name, &package.commit[..package.commit.len().min(12)], package.git);
+            }
+        }
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
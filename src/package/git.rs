@@ -0,0 +1,116 @@
+use crate::package::manifest::GitReference;
+use crate::package::PackageError;
+use crate::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn run_git(args: &[&str]) -> Result<String, Error> {
+    let output = Command::new("git").args(args).output()
+        .map_err(|e| Error::PackageError(PackageError::GitFailed(args.join(" "), e.to_string())))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(Error::PackageError(PackageError::GitFailed(args.join(" "), stderr)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `github.com/user/repo` is fetched over https, anything with a scheme or a local path is used as is.
+pub fn clone_url(git: &str) -> String {
+    if git.contains("://") || git.starts_with("git@") || Path::new(git).is_absolute() || git.starts_with('.') {
+        git.to_string()
+    } else {
+        format!("https://{}", git)
+    }
+}
+
+/// Form used to match an import path against a dependency url.
+pub fn normalize_url(git: &str) -> String {
+    let url = git.split_once("://").map_or(git, |(_, rest)| rest);
+    let url = url.trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url).to_string()
+}
+
+fn slug(git: &str) -> String {
+    normalize_url(git).chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect()
+}
+
+fn mirror_dir(cache: &Path, git: &str) -> PathBuf {
+    cache.join("repos").join(format!("{}.git", slug(git)))
+}
+
+pub fn checkout_dir(cache: &Path, git: &str, commit: &str) -> PathBuf {
+    cache.join("checkouts").join(slug(git)).join(commit)
+}
+
+fn path_str(path: &Path) -> String {
+    path.display().to_string()
+}
+
+/// Clones the mirror if needed, or fetches it again when `refresh` is set.
+fn sync_mirror(cache: &Path, git: &str, refresh: bool) -> Result<PathBuf, Error> {
+    let mirror = mirror_dir(cache, git);
+    if !mirror.exists() {
+        std::fs::create_dir_all(cache.join("repos")).map_err(|e| Error::PackageError(PackageError::Io(e.to_string())))?;
+        run_git(&["clone", "--quiet", "--mirror", &clone_url(git), &path_str(&mirror)])?;
+    } else if refresh {
+        run_git(&["--git-dir", &path_str(&mirror), "fetch", "--quiet", "--prune", "origin"])?;
+    }
+    Ok(mirror)
+}
+
+fn rev_parse(mirror: &Path, revision: &str) -> Result<String, Error> {
+    run_git(&["--git-dir", &path_str(mirror), "rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", revision)])
+}
+
+pub fn resolve_reference(cache: &Path, git: &str, reference: &GitReference) -> Result<String, Error> {
+    let mirror = sync_mirror(cache, git, true)?;
+    let revision = match reference {
+        GitReference::Tag(tag) => format!("refs/tags/{}", tag),
+        GitReference::Branch(branch) => format!("refs/heads/{}", branch),
+        GitReference::Rev(rev) => rev.clone(),
+        GitReference::DefaultBranch => "HEAD".to_string(),
+    };
+    rev_parse(&mirror, &revision)
+}
+
+/// Makes sure a locked commit is available, only touching the remote when the mirror lacks it.
+pub fn ensure_commit(cache: &Path, git: &str, commit: &str) -> Result<(), Error> {
+    if checkout_dir(cache, git, commit).exists() {
+        return Ok(());
+    }
+    let mirror = sync_mirror(cache, git, false)?;
+    if rev_parse(&mirror, commit).is_err() {
+        sync_mirror(cache, git, true)?;
+        rev_parse(&mirror, commit)?;
+    }
+    Ok(())
+}
+
+/// Checks the commit out into a temporary directory and moves it into place
+/// once it is complete, so an interrupted or failed checkout is never reused.
+pub fn checkout(cache: &Path, git: &str, commit: &str) -> Result<PathBuf, Error> {
+    let dir = checkout_dir(cache, git, commit);
+    if dir.exists() {
+        return Ok(dir);
+    }
+    let partial = dir.with_extension(format!("partial-{}", std::process::id()));
+    let io = |e: std::io::Error| Error::PackageError(PackageError::Io(e.to_string()));
+    if partial.exists() {
+        std::fs::remove_dir_all(&partial).map_err(io)?;
+    }
+    let mirror = mirror_dir(cache, git);
+    let result = run_git(&["clone", "--quiet", "--no-checkout", &path_str(&mirror), &path_str(&partial)])
+        .and_then(|_| run_git(&["-C", &path_str(&partial), "checkout", "--quiet", "--detach", commit]));
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(&partial);
+        return Err(e);
+    }
+    if let Err(e) = std::fs::rename(&partial, &dir) {
+        let _ = std::fs::remove_dir_all(&partial);
+        // another process may have finished the same checkout first
+        if !dir.exists() {
+            return Err(io(e));
+        }
+    }
+    Ok(dir)
+}
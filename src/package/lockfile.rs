@@ -0,0 +1,58 @@
+use crate::package::git::normalize_url;
+use crate::package::manifest::{parse_sections, Entry, GitReference};
+use crate::package::PackageError;
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub git: String,
+    pub reference: GitReference,
+    pub commit: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Lockfile {
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let mut lockfile = Lockfile::default();
+        for section in parse_sections(source)? {
+            if section.name.is_empty() {
+                continue;
+            }
+            let field = |key: &str| section.entries.iter().find_map(|(k, entry)| match entry {
+                Entry::Value(value) if k == key => Some(value.clone()),
+                _ => None,
+            });
+            let git = field("git").ok_or_else(|| Error::PackageError(PackageError::MissingGitUrl(section.name.clone())))?;
+            let commit = field("commit").ok_or_else(|| Error::PackageError(PackageError::DependencyNotLocked(section.name.clone())))?;
+            let reference = GitReference::from_fields(&section.name, field("tag"), field("branch"), field("rev"))?;
+            lockfile.packages.push(LockedPackage { name: section.name.clone(), git, reference, commit });
+        }
+        Ok(lockfile)
+    }
+
+    pub fn find(&self, url: &str) -> Option<&LockedPackage> {
+        let url = normalize_url(url);
+        self.packages.iter().find(|p| normalize_url(&p.git) == url)
+    }
+}
+
+impl std::fmt::Display for Lockfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# Generated by `loop fetch`. Do not edit by hand.")?;
+        for package in self.packages.iter() {
+            writeln!(f)?;
+            writeln!(f, "[{}]", package.name)?;
+            writeln!(f, "git = \"{}\"", package.git)?;
+            if let Some((key, value)) = package.reference.to_field() {
+                writeln!(f, "{} = \"{}\"", key, value)?;
+            }
+            writeln!(f, "commit = \"{}\"", package.commit)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use crate::package::PackageError;
+use crate::Error;
+
+/// Which commit of a git dependency to use.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitReference {
+    Tag(String),
+    Branch(String),
+    Rev(String),
+    DefaultBranch,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub git: String,
+    pub reference: GitReference,
+}
+
+#[derive(Debug, Default)]
+pub struct Manifest {
+    pub name: Option<String>,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// One `[section]` of the small TOML subset used by `loop.toml` and `loop.lock`.
+#[derive(Debug, Default)]
+pub struct Section {
+    pub name: String,
+    pub entries: Vec<(String, Entry)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Entry {
+    Value(String),
+    Table(Vec<(String, String)>),
+}
+
+impl Manifest {
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let mut manifest = Manifest::default();
+        for section in parse_sections(source)? {
+            if section.name == "package" {
+                for (key, entry) in section.entries {
+                    if let ("name", Entry::Value(name)) = (key.as_str(), entry) {
+                        manifest.name = Some(name);
+                    }
+                }
+            } else if section.name == "dependencies" {
+                for (name, entry) in section.entries {
+                    let fields = match entry {
+                        Entry::Table(fields) => fields,
+                        Entry::Value(git) => vec![("git".to_string(), git)],
+                    };
+                    manifest.dependencies.push(Dependency::from_fields(name, &fields)?);
+                }
+            } else if let Some(name) = section.name.strip_prefix("dependencies.") {
+                let fields: Vec<(String, String)> = section.entries.into_iter().filter_map(|(key, entry)| match entry {
+                    Entry::Value(value) => Some((key, value)),
+                    Entry::Table(_) => None,
+                }).collect();
+                manifest.dependencies.push(Dependency::from_fields(name.to_string(), &fields)?);
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+impl Dependency {
+    pub fn from_fields(name: String, fields: &[(String, String)]) -> Result<Self, Error> {
+        let field = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+        let git = match field("git") {
+            Some(git) => git,
+            None => return Err(Error::PackageError(PackageError::MissingGitUrl(name))),
+        };
+        let reference = GitReference::from_fields(&name, field("tag"), field("branch"), field("rev"))?;
+        Ok(Self { name, git, reference })
+    }
+}
+
+impl GitReference {
+    pub fn from_fields(name: &str, tag: Option<String>, branch: Option<String>, rev: Option<String>) -> Result<Self, Error> {
+        match (tag, branch, rev) {
+            (None, None, None) => Ok(GitReference::DefaultBranch),
+            (Some(tag), None, None) => Ok(GitReference::Tag(tag)),
+            (None, Some(branch), None) => Ok(GitReference::Branch(branch)),
+            (None, None, Some(rev)) => Ok(GitReference::Rev(rev)),
+            _ => Err(Error::PackageError(PackageError::ConflictingReferences(name.to_string()))),
+        }
+    }
+
+    pub fn to_field(&self) -> Option<(&str, &str)> {
+        match self {
+            GitReference::Tag(tag) => Some(("tag", tag)),
+            GitReference::Branch(branch) => Some(("branch", branch)),
+            GitReference::Rev(rev) => Some(("rev", rev)),
+            GitReference::DefaultBranch => None,
+        }
+    }
+}
+
+pub fn parse_sections(source: &str) -> Result<Vec<Section>, Error> {
+    let mut sections = vec![Section::default()];
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push(Section { name: name.trim().to_string(), entries: Vec::new() });
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim().to_string(), value.trim()),
+            None => return Err(invalid(line_number, line)),
+        };
+        let entry = if let Some(table) = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+            let mut fields = Vec::new();
+            for field in split_outside_quotes(table, ',') {
+                if field.trim().is_empty() {
+                    continue;
+                }
+                match field.split_once('=') {
+                    Some((k, v)) => fields.push((k.trim().to_string(), parse_string(v.trim()).ok_or_else(|| invalid(line_number, line))?)),
+                    None => return Err(invalid(line_number, line)),
+                }
+            }
+            Entry::Table(fields)
+        } else {
+            Entry::Value(parse_string(value).ok_or_else(|| invalid(line_number, line))?)
+        };
+        sections.last_mut().unwrap().entries.push((key, entry));
+    }
+    Ok(sections)
+}
+
+fn invalid(line_number: usize, line: &str) -> Error {
+    Error::PackageError(PackageError::InvalidManifest(line_number, line.to_string()))
+}
+
+fn parse_string(value: &str) -> Option<String> {
+    value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).map(|v| v.to_string())
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn split_outside_quotes(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            in_string = !in_string;
+        } else if c == separator && !in_string {
+            parts.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
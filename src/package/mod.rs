@@ -0,0 +1,157 @@
+pub mod manifest;
+pub mod lockfile;
+pub mod git;
+pub mod cli;
+pub mod test_package;
+
+use crate::package::lockfile::{LockedPackage, Lockfile};
+use crate::package::manifest::Manifest;
+use crate::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "loop.toml";
+pub const LOCK_FILE: &str = "loop.lock";
+pub const MODULE_ENTRY: &str = "lib.lp";
+
+#[derive(Debug)]
+pub enum PackageError {
+    NoProject(String),
+    ManifestNotFound(PathBuf),
+    InvalidManifest(usize, String),
+    MissingGitUrl(String),
+    ConflictingReferences(String),
+    UnknownDependency(String),
+    DependencyNotLocked(String),
+    DependencyNotFetched(String),
+    GitFailed(String, String),
+    Io(String),
+}
+
+impl std::fmt::Display for PackageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackageError::NoProject(dir) => write!(f, "no {} found in {} or its parents", MANIFEST_FILE, dir),
+            PackageError::ManifestNotFound(path) => write!(f, "{} not found", path.display()),
+            PackageError::InvalidManifest(line, text) => write!(f, "invalid {} line {}: {}", MANIFEST_FILE, line, text),
+            PackageError::MissingGitUrl(name) => write!(f, "dependency `{}` has no git url", name),
+            PackageError::ConflictingReferences(name) => write!(f, "dependency `{}` sets more than one of branch, tag and rev", name),
+            PackageError::UnknownDependency(name) => write!(f, "no dependency named `{}`", name),
+            PackageError::DependencyNotLocked(name) => write!(f, "dependency `{}` is not in the lockfile", name),
+            PackageError::DependencyNotFetched(name) => write!(f, "dependency `{}` is not fetched", name),
+            PackageError::GitFailed(command, output) => write!(f, "`git {}` failed: {}", command, output.trim()),
+            PackageError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A directory holding a `loop.toml`, with the cache its dependencies are checked out into.
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub root: PathBuf,
+    pub cache: PathBuf,
+}
+
+impl Project {
+    pub fn new(root: PathBuf, cache: PathBuf) -> Self {
+        Self { root, cache }
+    }
+
+    pub fn discover(start: &Path) -> Option<Self> {
+        let mut dir = Some(start);
+        while let Some(current) = dir {
+            if current.join(MANIFEST_FILE).is_file() {
+                return Some(Self::new(current.to_path_buf(), default_cache_dir()));
+            }
+            dir = current.parent();
+        }
+        None
+    }
+
+    pub fn manifest(&self) -> Result<Manifest, Error> {
+        let path = self.root.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return Err(Error::PackageError(PackageError::ManifestNotFound(path)));
+        }
+        Manifest::parse(&read(&path)?)
+    }
+
+    pub fn lockfile(&self) -> Result<Lockfile, Error> {
+        let path = self.root.join(LOCK_FILE);
+        if !path.is_file() {
+            return Ok(Lockfile::default());
+        }
+        Lockfile::parse(&read(&path)?)
+    }
+
+    /// Checks out every dependency, keeping the commits already recorded in the lockfile.
+    pub fn fetch(&self) -> Result<Lockfile, Error> {
+        self.resolve(|_| false)
+    }
+
+    /// Re-resolves the given dependency (or all of them) against the remote before checking out.
+    pub fn update(&self, only: Option<&str>) -> Result<Lockfile, Error> {
+        let manifest = self.manifest()?;
+        if let Some(name) = only {
+            if !manifest.dependencies.iter().any(|d| d.name == name) {
+                return Err(Error::PackageError(PackageError::UnknownDependency(name.to_string())));
+            }
+        }
+        self.resolve(|name| only.is_none_or(|only| only == name))
+    }
+
+    fn resolve(&self, refresh: impl Fn(&str) -> bool) -> Result<Lockfile, Error> {
+        let manifest = self.manifest()?;
+        let previous = self.lockfile()?;
+        let mut lockfile = Lockfile::default();
+        for dependency in manifest.dependencies.iter() {
+            let locked = previous.packages.iter()
+                .find(|p| p.name == dependency.name && p.git == dependency.git && p.reference == dependency.reference);
+            let commit = match locked {
+                Some(locked) if !refresh(&dependency.name) => {
+                    git::ensure_commit(&self.cache, &dependency.git, &locked.commit)?;
+                    locked.commit.clone()
+                }
+                _ => git::resolve_reference(&self.cache, &dependency.git, &dependency.reference)?,
+            };
+            git::checkout(&self.cache, &dependency.git, &commit)?;
+            lockfile.packages.push(LockedPackage {
+                name: dependency.name.clone(),
+                git: dependency.git.clone(),
+                reference: dependency.reference.clone(),
+                commit,
+            });
+        }
+        fs::write(self.root.join(LOCK_FILE), lockfile.to_string())
+            .map_err(|e| Error::PackageError(PackageError::Io(e.to_string())))?;
+        Ok(lockfile)
+    }
+
+    /// Source of the module imported as `'git:<url>'`, read from the cache only.
+    pub fn module_source(&self, url: &str) -> Result<String, Error> {
+        let lockfile = self.lockfile()?;
+        let package = match lockfile.find(url) {
+            Some(package) => package,
+            None => return Err(Error::PackageError(PackageError::DependencyNotLocked(url.to_string()))),
+        };
+        let entry = git::checkout_dir(&self.cache, &package.git, &package.commit).join(MODULE_ENTRY);
+        if !entry.is_file() {
+            return Err(Error::PackageError(PackageError::DependencyNotFetched(package.name.clone())));
+        }
+        read(&entry)
+    }
+}
+
+pub fn default_cache_dir() -> PathBuf {
+    if let Ok(cache) = std::env::var("LOOP_CACHE") {
+        return PathBuf::from(cache);
+    }
+    match std::env::var("HOME") {
+        Ok(home) => PathBuf::from(home).join(".loop").join("cache"),
+        Err(_) => std::env::temp_dir().join("loop-cache"),
+    }
+}
+
+fn read(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|e| Error::PackageError(PackageError::Io(format!("{}: {}", path.display(), e))))
+}
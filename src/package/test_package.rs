@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod test_package {
+    use crate::ast::Value;
+    use crate::package::git;
+    use crate::package::manifest::{GitReference, Manifest};
+    use crate::package::{Project, PackageError, LOCK_FILE};
+    use crate::{Error, Interpreter};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::rc::Rc;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=loop", "-c", "user.email=loop@example.com", "-c", "init.defaultBranch=main"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("loop-package-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Creates a bare repository whose `lib.lp` defines `version()`, tagged `v1`.
+    fn make_remote(root: &Path) -> PathBuf {
+        let work = root.join("work");
+        fs::create_dir_all(&work).unwrap();
+        git(&work, &["init", "--quiet"]);
+        fs::write(work.join("lib.lp"), "fn version() -> i32 { 1 }\n").unwrap();
+        git(&work, &["add", "lib.lp"]);
+        git(&work, &["commit", "--quiet", "-m", "v1"]);
+        git(&work, &["tag", "v1"]);
+        git(root, &["clone", "--quiet", "--bare", "work", "utils.git"]);
+        git(&work, &["remote", "add", "origin", root.join("utils.git").to_str().unwrap()]);
+        root.join("utils.git")
+    }
+
+    fn publish_v2(root: &Path) {
+        let work = root.join("work");
+        fs::write(work.join("lib.lp"), "fn version() -> i32 { 2 }\n").unwrap();
+        git(&work, &["commit", "--quiet", "-am", "v2"]);
+        git(&work, &["push", "--quiet", "origin", "main"]);
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(r#"
+            [package]
+            name = "app" # trailing comment
+
+            [dependencies]
+            mymodule = { git = "github.com/anonx3247/mymodule", tag = "v1.0" }
+            other = "file:///srv/other.git"
+
+            [dependencies.pinned]
+            git = "github.com/someone/pinned"
+            rev = "0123abc"
+        "#).unwrap();
+        assert_eq!(manifest.name, Some("app".to_string()));
+        assert_eq!(manifest.dependencies.len(), 3);
+        assert_eq!(manifest.dependencies[0].reference, GitReference::Tag("v1.0".to_string()));
+        assert_eq!(manifest.dependencies[1].reference, GitReference::DefaultBranch);
+        assert_eq!(manifest.dependencies[2].reference, GitReference::Rev("0123abc".to_string()));
+        assert!(matches!(
+            Manifest::parse("[dependencies]\nbad = { git = \"x\", tag = \"a\", rev = \"b\" }"),
+            Err(Error::PackageError(PackageError::ConflictingReferences(_)))
+        ));
+    }
+
+    #[test]
+    fn test_fetch_and_import_from_local_repository() {
+        let root = temp_dir("fetch");
+        let remote = make_remote(&root);
+        let url = format!("file://{}", remote.display());
+        let project_dir = root.join("project");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("loop.toml"), format!("[dependencies]\nutils = {{ git = \"{}\", tag = \"v1\" }}\n", url)).unwrap();
+
+        let project = Project::new(project_dir.clone(), root.join("cache"));
+        let lockfile = project.fetch().unwrap();
+        assert_eq!(lockfile.packages[0].commit, git(&root.join("work"), &["rev-parse", "v1"]));
+        assert!(fs::read_to_string(project_dir.join(LOCK_FILE)).unwrap().contains(&lockfile.packages[0].commit));
+
+        let mut interpreter = Interpreter::new();
+        interpreter.env.project = Some(Rc::new(project));
+        interpreter.eval_str(&format!("import 'git:{}'", url)).unwrap();
+        assert_eq!(interpreter.eval_str("utils.version()").unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn test_lockfile_pins_until_update() {
+        let root = temp_dir("update");
+        let remote = make_remote(&root);
+        let project_dir = root.join("project");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("loop.toml"), format!("[dependencies]\nutils = {{ git = \"{}\", branch = \"main\" }}\n", remote.display())).unwrap();
+        let project = Project::new(project_dir, root.join("cache"));
+
+        let first = project.fetch().unwrap().packages[0].commit.clone();
+        publish_v2(&root);
+        assert_eq!(project.fetch().unwrap().packages[0].commit, first);
+
+        let updated = project.update(Some("utils")).unwrap().packages[0].commit.clone();
+        assert_ne!(updated, first);
+        assert_eq!(updated, git(&root.join("work"), &["rev-parse", "HEAD"]));
+        assert!(matches!(project.update(Some("missing")), Err(Error::PackageError(PackageError::UnknownDependency(_)))));
+
+        let mut interpreter = Interpreter::new();
+        interpreter.env.project = Some(Rc::new(project));
+        interpreter.eval_str(&format!("import 'git:{}' as utils", remote.display())).unwrap();
+        assert_eq!(interpreter.eval_str("utils.version()").unwrap(), Value::Int(2));
+    }
+
+    #[test]
+    fn test_failed_checkout_leaves_nothing_behind() {
+        let root = temp_dir("checkout");
+        let remote = make_remote(&root);
+        let git_url = remote.display().to_string();
+        let cache = root.join("cache");
+        let commit = git(&root.join("work"), &["rev-parse", "v1"]);
+        git::ensure_commit(&cache, &git_url, &commit).unwrap();
+
+        let missing = "0".repeat(40);
+        assert!(matches!(git::checkout(&cache, &git_url, &missing), Err(Error::PackageError(PackageError::GitFailed(_, _)))));
+        let checkouts = git::checkout_dir(&cache, &git_url, &missing).parent().unwrap().to_path_buf();
+        assert_eq!(fs::read_dir(&checkouts).unwrap().count(), 0);
+
+        let dir = git::checkout(&cache, &git_url, &commit).unwrap();
+        assert!(dir.join("lib.lp").exists());
+        assert_eq!(fs::read_dir(&checkouts).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_import_requires_locked_dependency() {
+        let root = temp_dir("unlocked");
+        fs::write(root.join("loop.toml"), "[dependencies]\n").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.env.project = Some(Rc::new(Project::new(root, std::env::temp_dir())));
+        assert!(matches!(
+            interpreter.eval_str("import 'git:github.com/anonx3247/mymodule'"),
+            Err(Error::PackageError(PackageError::DependencyNotLocked(_)))
+        ));
+    }
+}
@@ -1,24 +1,25 @@
 use crate::{lexer::token};
 use crate::ast::*;
-use super::parser::{Parser, ParseError};
+use super::parser::{Parser, ParseError, Tokens};
 use crate::Error;
+use std::rc::Rc;
 
 impl Parser {
-    pub fn parse_binary_operator_expr(&mut self, tokens: &[token::Token], 
+    pub fn parse_binary_operator_expr(&mut self, tokens: Tokens, 
         operators: &[token::Token], 
-        left_parser: Option<fn(&mut Self, &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize)>, 
-        right_parser: Option<fn(&mut Self, &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize)>
-        ) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
+        left_parser: Option<fn(&mut Self, Tokens) -> (Result<Rc<Node>, Error>, usize)>, 
+        right_parser: Option<fn(&mut Self, Tokens) -> (Result<Rc<Node>, Error>, usize)>
+        ) -> (Result<Rc<Node>, Error>, usize) {
         
         for op in operators.iter() {
-            if let Ok(Some(pos)) = self.find_first_token_skip_brackets(&op, &tokens) {
+            if let Ok(Some(pos)) = self.find_binary_operator(op, &tokens) {
                 let (left, left_pos) = match left_parser {
-                    Some(parser) => parser(self, &tokens[..pos]),
-                    _ => self.parse_expr(&tokens[..pos]),
+                    Some(parser) => parser(self, tokens.slice(..pos)),
+                    _ => self.parse_expr(tokens.slice(..pos)),
                 };
                 let (right, right_pos) = match right_parser {
-                    Some(parser) => parser(self, &tokens[pos + 1..]),
-                    _ => self.parse_expr(&tokens[pos + 1..]),
+                    Some(parser) => parser(self, tokens.slice(pos + 1..)),
+                    _ => self.parse_expr(tokens.slice(pos + 1..)),
                 };
                 let node = binary_operation::BinaryOperation {
                     left: match left {
@@ -34,30 +35,70 @@ impl Parser {
                         _ => return (Err(Error::ParserError(ParseError::UnexpectedToken(op.clone()))), pos)
                     },
                 };
-                return (Ok(Box::new(node)), right_pos + pos + 1);
+                return (Ok(Node::new(node)), right_pos + pos + 1);
             }
         }
 
-        match tokens[0] {
-            token::Token::Identifier(_) => {
-                let identifier = identifier::Identifier::from_token(tokens[0].clone());
-                return (match identifier {
-                    Ok(identifier) => Ok(Box::new(identifier)),
-                    Err(e) => Err(e),
-                }, 1);
+        if tokens[0] == token::Token::Operator(token::Operator::Sub) {
+            return self.parse_unary_operator_expr(tokens, token::Operator::Sub);
+        }
+
+        if tokens[0] == token::Token::Function(token::Function::Await) {
+            return self.parse_await_expr(tokens);
+        }
+
+        if tokens[0] == token::Token::Bracket(token::Bracket::OpenParen) {
+            if let Ok(end) = self.find_matching_bracket(&tokens, 0) {
+                // a parenthesized operand followed by postfix operators or a cast
+                let (node, _) = self.parse_expr(tokens.slice(..end + 1));
+                return self.parse_postfix(tokens, node, end + 1);
             }
-            _ => {
-                let literal = literal::Literal::from_token(tokens[0].clone());
-                return (match literal {
-                    Ok(literal) => Ok(Box::new(literal)),
-                    Err(e) => Err(e),
-                }, 1);
+        }
+
+        if let token::Token::Bracket(bracket @ (token::Bracket::OpenBracket | token::Bracket::OpenBrace)) = &tokens[0] {
+            // braces in place of an operand are a map rather than a block
+            return match self.find_matching_bracket(&tokens, 0) {
+                Ok(end) => {
+                    let collection = match bracket {
+                        token::Bracket::OpenBracket => self.parse_list_expr(tokens.slice(..end + 1)),
+                        _ => self.parse_map_expr(tokens.slice(..end + 1)),
+                    };
+                    self.parse_postfix(tokens, collection, end + 1)
+                }
+                Err(e) => (Err(e), 0),
+            };
+        }
+
+        let leaf: Result<Rc<Node>, Error> = match tokens[0] {
+            token::Token::Identifier(_) => match identifier::Identifier::from_token(tokens[0].clone()) {
+                Ok(identifier) => Ok(Node::new(identifier)),
+                Err(e) => Err(e),
+            },
+            _ => match literal::Literal::from_token(tokens[0].clone()) {
+                Ok(literal) => Ok(Node::new(literal)),
+                Err(e) => Err(e),
+            },
+        };
+        self.parse_postfix(tokens, leaf, 1)
+    }
+
+    /// The first `op` in `tokens` outside brackets that has an operand on its
+    /// left, so that a `-` starting an operand negates it instead.
+    fn find_binary_operator(&mut self, op: &token::Token, tokens: &[token::Token]) -> Result<Option<usize>, Error> {
+        let mut start = 0;
+        while let Some(pos) = self.find_first_token_skip_brackets(op, &tokens[start..])? {
+            let pos = start + pos;
+            let unary = *op == token::Token::Operator(token::Operator::Sub)
+                && (pos == 0 || matches!(tokens[pos - 1], token::Token::Operator(_)));
+            if !unary {
+                return Ok(Some(pos));
             }
+            start = pos + 1;
         }
-        
+        Ok(None)
     }
 
-    pub fn parse_math_expr(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
+    pub fn parse_math_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
         let operators = [
             token::Token::Operator(token::Operator::Sub),
             token::Token::Operator(token::Operator::Add),
@@ -72,15 +113,14 @@ impl Parser {
             token::Token::Operator(token::Operator::BitShiftRight),
         ];
 
-        if tokens[0] == token::Token::Operator(token::Operator::Sub) {
-            let (node, pos) = self.parse_unary_operator_expr(&tokens, token::Operator::Sub);
-            return (node, pos);
+        if let Err(e) = self.check_bounds(&tokens, 0) {
+            return (Err(e), 0);
         }
 
         self.parse_binary_operator_expr(tokens, &operators, None, None)
     }
 
-    pub fn parse_bool_expr(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
+    pub fn parse_bool_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
         let operators = [
             token::Token::Operator(token::Operator::And),
             token::Token::Operator(token::Operator::Or),
@@ -94,15 +134,21 @@ impl Parser {
         ];
 
         if tokens[0] == token::Token::Operator(token::Operator::Not) {
-            let (node, pos) = self.parse_unary_operator_expr(&tokens, token::Operator::Not);
+            let (node, pos) = self.parse_unary_operator_expr(tokens, token::Operator::Not);
             return (node, pos);
         }
 
         self.parse_binary_operator_expr(tokens, &operators, None, None)
     }
 
-    pub fn parse_unary_operator_expr(&mut self, tokens: &[token::Token], operator: token::Operator) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
-        let (node, pos) = self.parse_expr(&tokens[1..]);
+    pub fn parse_unary_operator_expr(&mut self, tokens: Tokens, operator: token::Operator) -> (Result<Rc<Node>, Error>, usize) {
+        // a negative literal, so that `-9223372036854775808` is `i64::MIN` rather than the negation of a `u64`
+        if let (token::Operator::Sub, [_, token::Token::Literal(token::Literal::Int(value))]) = (&operator, &*tokens) {
+            if let Ok(value) = i64::try_from(-value) {
+                return (Ok(Node::new(literal::Literal(Value::Int(value)))), 2);
+            }
+        }
+        let (node, pos) = self.parse_expr(tokens.slice(1..));
         let node = match node {
             Ok(node) => node,
             Err(e) => return (Err(e), pos),
@@ -111,6 +157,6 @@ impl Parser {
             operand: node,
             operator: operator,
         };
-        return (Ok(Box::new(node)), pos + 1);
+        return (Ok(Node::new(node)), pos + 1);
     }
 } 
\ No newline at end of file
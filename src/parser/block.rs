@@ -1,13 +1,14 @@
 use std::fmt::Debug;
-use crate::{ast::ASTNode, lexer::{token}};
-use super::parser::{Parser, ParseError};
+use crate::ast::Node;
+use super::parser::{Parser, ParseError, Tokens};
 use crate::Error;
+use std::rc::Rc;
 
 impl Parser {
     pub fn parse_block_expr<K: Debug>(
-        &mut self, tokens: &[token::Token],
-        parse_expr: Option<fn(&mut Self, &[token::Token]) -> (Result<K, Error>, usize)>
-    ) -> (Result<(Box<dyn ASTNode>, Option<K>), Error>, usize) {
+        &mut self, tokens: Tokens,
+        parse_expr: Option<fn(&mut Self, Tokens) -> (Result<K, Error>, usize)>
+    ) -> (Result<(Rc<Node>, Option<K>), Error>, usize) {
         let brace_loc = self.find_opening_brace_for(&tokens, tokens[0].clone());
         let brace_loc = match brace_loc {
             Ok(loc) => loc,
@@ -15,7 +16,7 @@ impl Parser {
         };
 
         let expr = if brace_loc > 1 && parse_expr.is_some() {
-            let (expr, new_pos) = parse_expr.unwrap()(self, &tokens[1..brace_loc]);
+            let (expr, new_pos) = parse_expr.unwrap()(self, tokens.slice(1..brace_loc));
             let expr = match expr {
                 Ok(e) => e,
                 Err(e) => return (Err(e), new_pos)
@@ -33,7 +34,7 @@ impl Parser {
             Ok(loc) => loc,
             Err(e) => return (Err(e), brace_loc)
         };
-        let (content, _) = match self.parse_tokens(&tokens[brace_loc+1..matching_loc]) {
+        let (content, _) = match self.parse_tokens(tokens.slice(brace_loc+1..matching_loc)) {
             (Ok(c), loc) => (c, loc),
             (Err(e), loc) => return (Err(e), brace_loc + loc)
         };
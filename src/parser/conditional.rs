@@ -1,12 +1,13 @@
-use crate::{ast::{conditional::{Conditional, ElifBlock, ElseBlock, IfBlock}, scope::Scope}, lexer::{token}};
-use super::parser::{Parser, ParseError};
+use crate::{ast::{conditional::{ElifBlock, ElseBlock, IfBlock}, scope::Scope, ASTNode, Node}, lexer::{token}};
+use super::parser::{Parser, ParseError, Tokens};
 use crate::Error;
+use std::rc::Rc;
 
 
 
 impl Parser {
 
-    pub fn parse_conditional_expr(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn Conditional>, Error>, usize) {
+    pub fn parse_conditional_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
         match &tokens[0] {
             token::Token::Conditional(c) => {
                 if let token::Conditional::Match = c {
@@ -21,12 +22,12 @@ impl Parser {
                 };
 
                 let mut new_pos = matching_loc + 1;
-                let next: Option<Box<dyn Conditional>>;
+                let next: Option<Rc<Node>>;
                 if new_pos < tokens.len() && c != &token::Conditional::Else {
                     next = match tokens[new_pos] {
                         token::Token::Conditional(token::Conditional::Elif)
                         |token::Token::Conditional(token::Conditional::Else) => {
-                            let (next, next_loc) = match self.parse_conditional_expr(&tokens[new_pos..]) {
+                            let (next, next_loc) = match self.parse_conditional_expr(tokens.slice(new_pos..)) {
                                 (Ok(n), l) => (n, l),
                                 (Err(e), l) => return (Err(e), l)
                             };
@@ -45,26 +46,23 @@ impl Parser {
                             Some(c) => c,
                             _ => return (Err(Error::ParserError(ParseError::NoConditionForConditional)), 0)
                         };
-                        (Ok(Box::new(IfBlock::new(condition, Scope::new(content.children()), next))), new_pos)
+                        (Ok(Node::new(IfBlock::new(condition, Scope::new(content.children()), next))), new_pos)
                     },
                     token::Conditional::Elif => {
                         let condition = match condition {
                             Some(c) => c,
                             _ => return (Err(Error::ParserError(ParseError::NoConditionForConditional)), 0)
                         };
-                        (Ok(Box::new(ElifBlock::new(condition, Scope::new(content.children()), next))), new_pos)
+                        (Ok(Node::new(ElifBlock::new(condition, Scope::new(content.children()), next))), new_pos)
                     },
                     token::Conditional::Else => {
-                        (Ok(Box::new(ElseBlock::new(Scope::new(content.children())))), new_pos)
+                        (Ok(Node::new(ElseBlock::new(Scope::new(content.children())))), new_pos)
                     },
                     token::Conditional::Match => 
                         (Err(Error::ParserError(ParseError::Unimplimented)), new_pos)
                 }
             },
-            _ => {
-                println!("no conditional found: {:?}", tokens[0]);
-                (Err(Error::ParserError(ParseError::NoConditionalFound)), 0)
-            }
+            _ => (Err(Error::ParserError(ParseError::NoConditionalFound)), 0),
 
         }
     }
@@ -1,14 +1,19 @@
 use crate::ast::tuple::{Clonable, Tuple};
 use crate::lexer::token;
 use crate::ast::*;
-use super::parser::{Parser, ParseError};
+use super::parser::{Parser, ParseError, Tokens};
 use crate::ast::identifier::Identifier;
 use crate::ast::type_node::Type;
 use crate::Error;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// The variable assigned to by `name[i][j] :=`, and its indexes.
+type IndexTarget = (String, Vec<Rc<Node>>);
 
 impl Parser {
 
-    pub fn parse_assignment_or_declaration_expr(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
+    pub fn parse_assignment_or_declaration_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
         let mut start = 0;
         let mut mutable = false;
         let mut is_decl = false;
@@ -48,7 +53,30 @@ impl Parser {
             return (Err(Error::ParserError(ParseError::InvalidExpression)), 0);
         }
 
-        let identifier_tuple = match self.make_tuple(&tokens[start..pos]) {
+        if !is_decl && !colon_first {
+            if let Some((name, path)) = self.tuple_element_target(&tokens[..pos]) {
+                let (value, new_pos) = match self.parse_expr(tokens.slice(pos+1..)) {
+                    (Ok(value), new_pos) => (value, new_pos),
+                    (Err(e), new_pos) => return (Err(e), new_pos+pos)
+                };
+                let node = assignment::TupleElementAssignment { name, path, expr: value };
+                return (Ok(Node::new(node)), pos + new_pos + 1);
+            }
+            let target = match self.index_target(tokens.slice(..pos)) {
+                Ok(target) => target,
+                Err(e) => return (Err(e), pos),
+            };
+            if let Some((name, indexes)) = target {
+                let (value, new_pos) = match self.parse_expr(tokens.slice(pos+1..)) {
+                    (Ok(value), new_pos) => (value, new_pos),
+                    (Err(e), new_pos) => return (Err(e), new_pos+pos)
+                };
+                let node = assignment::IndexAssignment { name, indexes, expr: value };
+                return (Ok(Node::new(node)), pos + new_pos + 1);
+            }
+        }
+
+        let identifier_tuple = match self.make_tuple(tokens.slice(start..pos)) {
             Ok(tuple) => tuple,
             Err(e) => return (Err(e), pos)
         };
@@ -65,7 +93,7 @@ impl Parser {
         };
         
         if colon_first {
-            let (types, new_pos) = self.make_left_matching_tuple(&tokens[pos+1..], identifier_tuple.clone_element());
+            let (types, new_pos) = self.make_left_matching_tuple(tokens.slice(pos+1..), identifier_tuple.clone_element());
             let types = match types {
                 Ok(types) => types,
                 Err(e) => return (Err(e), new_pos+pos)
@@ -82,7 +110,7 @@ impl Parser {
 
             let mut value = None;
             if pos < tokens.len() && matches!(tokens[pos], token::Token::Operator(token::Operator::EqualSign)) {
-                let (the_value, new_pos) = match self.parse_expr(&tokens[pos+1..]) {
+                let (the_value, new_pos) = match self.parse_expr(tokens.slice(pos+1..)) {
                     (Ok(value), new_pos) => (value, new_pos),
                     (Err(e), new_pos) => return (Err(e), new_pos+pos)
                 };
@@ -100,7 +128,7 @@ impl Parser {
                     name: identifier_tuple.map(&|i| i.element()),
                     expr: value,
                 };
-                return (Ok(Box::new(node)), pos);
+                return (Ok(Node::new(node)), pos);
             } else {
                     let node = variable_declaration::VariableDeclaration {
                         mutable,
@@ -110,10 +138,10 @@ impl Parser {
                         },
                     name: identifier_tuple.map(&|i| i.element()),
                 };
-                return (Ok(Box::new(node)), pos);
+                return (Ok(Node::new(node)), pos);
             }
         } else {
-            let (value, new_pos) = match self.parse_expr(&tokens[pos+1..]) {
+            let (value, new_pos) = match self.parse_expr(tokens.slice(pos+1..)) {
                 (Ok(value), new_pos) => (value, new_pos),
                 (Err(e), new_pos) => return (Err(e), new_pos+pos)
             };
@@ -126,15 +154,100 @@ impl Parser {
                     name: identifier_tuple.map(&|i| i.element()),
                     expr: value,
                 };
-                return (Ok(Box::new(node)), pos);
+                return (Ok(Node::new(node)), pos);
             } else {
                 let node = assignment::VariableAssignment {
                     name: identifier_tuple.map(&|i| i.element()),
                     expr: value,
+                    address: Cell::default(),
                 };
-                return (Ok(Box::new(node)), pos);
+                return (Ok(Node::new(node)), pos);
             }
         }
     }
 
+    /// Parses `target += expr` and the other compound assignments, with the
+    /// operator at `pos`.
+    pub fn parse_compound_assignment_expr(&mut self, tokens: Tokens, pos: usize) -> (Result<Rc<Node>, Error>, usize) {
+        let operator = match &tokens[pos] {
+            token::Token::Operator(operator) => assignment::compound_operator(operator),
+            _ => None,
+        };
+        let operator = match operator {
+            Some(operator) => operator,
+            None => return (Err(Error::ParserError(ParseError::UnexpectedToken(tokens[pos].clone()))), pos),
+        };
+        let (name, path) = match self.tuple_element_target(&tokens[..pos]) {
+            Some((name, path)) => (Tuple::Element(name), path),
+            None => {
+                let mut target = tokens.slice(..pos);
+                // `(a, b) += ...` names the same variables as `a, b += ...`
+                if matches!(target.first(), Some(token::Token::Bracket(token::Bracket::OpenParen)))
+                    && matches!(self.find_matching_bracket(&target, 0), Ok(end) if end + 1 == target.len()) {
+                    target = target.slice(1..target.len() - 1);
+                }
+                let identifier_tuple = match self.make_tuple(target) {
+                    Ok(tuple) => tuple,
+                    Err(e) => return (Err(e), pos)
+                };
+                if !self.is_identifier_tuple(identifier_tuple.clone()) {
+                    return (Err(Error::ParserError(ParseError::AssignmentTupleNotIdentifier)), pos);
+                }
+                let identifier_tuple: Tuple<Identifier> = match self.parse_tuple(identifier_tuple, |_s, tok| {
+                    Identifier::from_token(tok[0].clone())
+                }) {
+                    Ok(tuple) => tuple,
+                    Err(e) => return (Err(e), pos)
+                };
+                (identifier_tuple.map(&|i| i.element()), Vec::new())
+            }
+        };
+        let (value, new_pos) = match self.parse_expr(tokens.slice(pos+1..)) {
+            (Ok(value), new_pos) => (value, new_pos),
+            (Err(e), new_pos) => return (Err(e), new_pos+pos)
+        };
+        let node = assignment::CompoundAssignment { name, path, operator, expr: value, address: Cell::default() };
+        (Ok(Node::new(node)), pos + new_pos + 1)
+    }
+
+    /// The variable and the indexes of `name.0.1` in `tokens`.
+    fn tuple_element_target(&self, tokens: &[token::Token]) -> Option<(String, Vec<usize>)> {
+        let name = match tokens.first() {
+            Some(token::Token::Identifier(name)) => name.clone(),
+            _ => return None,
+        };
+        let mut path = Vec::new();
+        for pair in tokens[1..].chunks(2) {
+            match pair {
+                [token::Token::Punctuation(token::Punctuation::Dot), token::Token::Literal(token::Literal::Int(index))] => path.push(*index as usize),
+                _ => return None,
+            }
+        }
+        (!path.is_empty()).then_some((name, path))
+    }
+
+    /// The variable and indexes of a `name[i][j] :=`, if `tokens` are one.
+    fn index_target(&mut self, tokens: Tokens) -> Result<Option<IndexTarget>, Error> {
+        let last = tokens.iter().rev().find(|token| !matches!(token, token::Token::Whitespace(_)));
+        if !matches!(last, Some(token::Token::Bracket(token::Bracket::CloseBracket))) {
+            return Ok(None);
+        }
+        let mut node = self.parse_expr(tokens).0?;
+        let mut indexes = Vec::new();
+        loop {
+            let value = match &node.kind {
+                NodeKind::Index(index) => {
+                    indexes.push(index.index.clone());
+                    index.value.clone()
+                }
+                NodeKind::Identifier(identifier) => {
+                    indexes.reverse();
+                    return Ok(Some((identifier.name.clone(), indexes)));
+                }
+                _ => return Ok(None),
+            };
+            node = value;
+        }
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,126 @@
+use crate::ast::error::{Except, Handler, Raise};
+use crate::ast::scope::Scope;
+use crate::ast::*;
+use crate::lexer::token;
+use super::parser::{Parser, ParseError, Tokens};
+use crate::Error;
+use std::rc::Rc;
+
+impl Parser {
+    pub fn parse_raise_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
+        // assumes tokens[0] is `error`
+        match tokens.get(1) {
+            Some(token::Token::Type(token::Type::UserDefined(kind))) => {
+                let kind = Some(kind.clone());
+                if !matches!(tokens.get(2), Some(token::Token::Bracket(token::Bracket::OpenParen))) {
+                    return (Ok(Node::new(Raise { kind, payload: None })), 2);
+                }
+                let matching_loc = match self.find_matching_bracket(&tokens, 2) {
+                    Ok(loc) => loc,
+                    Err(e) => return (Err(e), 2),
+                };
+                let payload = match self.parse_expr(tokens.slice(3..matching_loc)) {
+                    (Ok(payload), _) => payload,
+                    (Err(e), pos) => return (Err(e), 3 + pos),
+                };
+                (Ok(Node::new(Raise { kind, payload: Some(payload) })), matching_loc + 1)
+            }
+            None | Some(token::Token::Whitespace(token::Whitespace::Newline)) => (Ok(Node::new(Raise { kind: None, payload: None })), 1),
+            Some(_) => {
+                let length = match self.find_expr_possible_boundary(&tokens[1..], false, false, false) {
+                    Ok(length) => length,
+                    Err(e) => return (Err(e), 1),
+                };
+                match self.parse_expr(tokens.slice(1..1 + length)) {
+                    (Ok(payload), pos) => (Ok(Node::new(Raise { kind: None, payload: Some(payload) })), 1 + pos),
+                    (Err(e), pos) => (Err(e), 1 + pos),
+                }
+            }
+        }
+    }
+
+    /// Parses `body except default` or `body except { Kind(e) => ... }` where
+    /// `pos` is the location of `except`.
+    pub fn parse_except_expr(&mut self, tokens: Tokens, pos: usize) -> (Result<Rc<Node>, Error>, usize) {
+        let body = match self.parse_block_or_expr(tokens.slice(..pos)) {
+            (Ok(body), _) => body,
+            (Err(e), body_pos) => return (Err(e), body_pos),
+        };
+        let cursor = pos + 1;
+        if !matches!(tokens.get(cursor), Some(token::Token::Bracket(token::Bracket::OpenBrace))) {
+            return match self.parse_expr(tokens.slice(cursor..)) {
+                (Ok(default), default_pos) => {
+                    let handler = Handler { kind: None, binding: None, body: Scope::new(vec![default]) };
+                    (Ok(Node::new(Except { body, handlers: vec![handler] })), cursor + default_pos)
+                }
+                (Err(e), default_pos) => (Err(e), cursor + default_pos),
+            };
+        }
+        let matching_loc = match self.find_matching_bracket(&tokens, cursor) {
+            Ok(loc) => loc,
+            Err(e) => return (Err(e), cursor),
+        };
+        let handlers = match self.parse_handlers(tokens.slice(cursor + 1..matching_loc)) {
+            Ok(handlers) => handlers,
+            Err(e) => return (Err(e), cursor),
+        };
+        (Ok(Node::new(Except { body, handlers })), matching_loc + 1)
+    }
+
+    fn parse_handlers(&mut self, tokens: Tokens) -> Result<Vec<Handler>, Error> {
+        let mut handlers = Vec::new();
+        let mut cursor = 0;
+        while cursor < tokens.len() {
+            let kind = match &tokens[cursor] {
+                token::Token::Whitespace(_) | token::Token::Punctuation(token::Punctuation::Comma) => {
+                    cursor += 1;
+                    continue;
+                }
+                token::Token::Type(token::Type::UserDefined(kind)) => Some(kind.clone()),
+                token::Token::Error(token::Error::Error) => None,
+                tok => return Err(Error::ParserError(ParseError::UnexpectedToken(tok.clone()))),
+            };
+            cursor += 1;
+            let mut binding = None;
+            if matches!(tokens.get(cursor), Some(token::Token::Bracket(token::Bracket::OpenParen))) {
+                match (tokens.get(cursor + 1), tokens.get(cursor + 2)) {
+                    (Some(token::Token::Identifier(name)), Some(token::Token::Bracket(token::Bracket::CloseParen))) => binding = Some(name.clone()),
+                    (Some(tok), _) => return Err(Error::ParserError(ParseError::UnexpectedToken(tok.clone()))),
+                    (None, _) => return Err(Error::ParserError(ParseError::UnexpectedEndOfInput)),
+                }
+                cursor += 3;
+            }
+            match tokens.get(cursor) {
+                Some(token::Token::Punctuation(token::Punctuation::DoubleArrow)) => cursor += 1,
+                Some(tok) => return Err(Error::ParserError(ParseError::UnexpectedToken(tok.clone()))),
+                None => return Err(Error::ParserError(ParseError::UnexpectedEndOfInput)),
+            }
+            let length = if matches!(tokens.get(cursor), Some(token::Token::Bracket(token::Bracket::OpenBrace))) {
+                self.find_matching_bracket(&tokens, cursor)? + 1 - cursor
+            } else {
+                self.find_expr_possible_boundary(&tokens[cursor..], false, false, false)?
+            };
+            let body = self.parse_block_or_expr(tokens.slice(cursor..cursor + length)).0?;
+            handlers.push(Handler { kind, binding, body });
+            cursor += length;
+        }
+        Ok(handlers)
+    }
+
+    pub fn parse_block_or_expr(&mut self, tokens: Tokens) -> (Result<Scope, Error>, usize) {
+        if matches!(tokens.first(), Some(token::Token::Bracket(token::Bracket::OpenBrace))) {
+            if let Ok(matching_loc) = self.find_matching_bracket(&tokens, 0) {
+                if matching_loc == tokens.len() - 1 && !self.is_map_literal(&tokens, matching_loc) {
+                    return match self.parse_tokens(tokens.slice(1..matching_loc)) {
+                        (Ok(content), _) => (Ok(Scope::new(content.children())), tokens.len()),
+                        (Err(e), pos) => (Err(e), 1 + pos),
+                    };
+                }
+            }
+        }
+        match self.parse_expr(tokens) {
+            (Ok(expr), pos) => (Ok(Scope::new(vec![expr])), pos),
+            (Err(e), pos) => (Err(e), pos),
+        }
+    }
+}
@@ -1,20 +1,33 @@
 use crate::{ast::tuple::TupleASTNode, lexer::token};
 use crate::ast::*;
-use super::parser::Parser;
+use super::parser::{Parser, Tokens};
 use crate::Error;
+use std::rc::Rc;
 
 impl Parser {
-    pub fn parse_expr(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
+    /// The expression at the start of `tokens` and how many tokens it takes,
+    /// with the span of those tokens.
+    pub fn parse_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
+        match self.parse_unspanned_expr(tokens) {
+            (Ok(mut node), pos) => {
+                self.attach_span(&mut node, tokens.slice(..pos.min(tokens.len())));
+                (Ok(node), pos)
+            }
+            (Err(e), pos) => (Err(e), pos),
+        }
+    }
+
+    fn parse_unspanned_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
 
         if tokens.is_empty() {
-            return (Ok(Box::new(EmptyASTNode::new())), 0);
+            return (Ok(Node::new(EmptyASTNode::new())), 0);
         }
 
         if !tokens.is_empty() && tokens[0] == token::Token::Bracket(token::Bracket::OpenParen) {
             if let Ok(pos) = self.find_matching_bracket(&tokens, 0) {
                 if pos == tokens.len() - 1 {
                     if self.is_tuple_expr(&tokens[1..tokens.len()-1]) {
-                        let tuple = match self.make_tuple(&tokens[1..tokens.len()-1]) {
+                        let tuple = match self.make_tuple(tokens.slice(1..tokens.len()-1)) {
                             Ok(t) => t,
                             Err(e) => return (Err(e), tokens.len())
                         };
@@ -26,7 +39,7 @@ impl Parser {
 
                         return (Ok(TupleASTNode::from_tuple(tuple)), tokens.len());
                     } else {
-                        return (self.parse_expr(&tokens[1..tokens.len()-1]).0, tokens.len());
+                        return (self.parse_expr(tokens.slice(1..tokens.len()-1)).0, tokens.len());
                     }
                 }
             }
@@ -37,14 +50,14 @@ impl Parser {
                 token::Token::Identifier(_) => {
                     let identifier = identifier::Identifier::from_token(tokens[0].clone());
                     return (match identifier {
-                        Ok(identifier) => Ok(Box::new(identifier)),
+                        Ok(identifier) => Ok(Node::new(identifier)),
                         Err(e) => Err(e),
                     }, 1);
                 }
                 _ => {
                     let literal = literal::Literal::from_token(tokens[0].clone());
                     return (match literal {
-                        Ok(literal) => Ok(Box::new(literal)),
+                        Ok(literal) => Ok(Node::new(literal)),
                         Err(e) => Err(e),
                     }, 1);
                 }
@@ -55,23 +68,43 @@ impl Parser {
         let mut offset = 0;
         while matches!(tokens[0], token::Token::Whitespace(_)) {
             offset += 1;
-            tokens = &tokens[1..];
+            tokens = tokens.slice(1..);
         }
 
-        if let token::Token::Identifier(_) = tokens[0] {
-            let pos = 1;
+        if let Some((_, pos)) = self.parse_qualified_name(&tokens) {
             if pos < tokens.len() && matches!(tokens[pos], token::Token::Bracket(token::Bracket::OpenParen)) {
-                return self.parse_fn_call(&tokens);
+                // a call followed by an operator is the operand of a larger expression
+                let call_end = self.find_matching_bracket(&tokens, pos).map(|end| self.skip_postfix(&tokens, end + 1));
+                if !matches!(call_end.map(|end| tokens.get(end)), Ok(Some(token::Token::Operator(_) | token::Token::Error(token::Error::Except)))) {
+                    let (node, pos) = self.parse_fn_call(tokens);
+                    let (node, pos) = self.parse_postfix(tokens, node, pos);
+                    return (node, pos + offset);
+                }
             }
         }
 
+        if let token::Token::Module(token::Module::Import) | token::Token::Module(token::Module::From) = tokens[0] {
+            let (node, pos) = self.parse_import_expr(tokens);
+            return (node, pos + offset);
+        }
+
+        if let token::Token::Error(token::Error::Error) = tokens[0] {
+            let (node, pos) = self.parse_raise_expr(tokens);
+            return (node, pos + offset);
+        }
+
+        if let token::Token::Function(token::Function::Async) = tokens[0] {
+            let (node, pos) = self.parse_async_expr(tokens);
+            return (node, pos + offset);
+        }
+
         if let token::Token::Function(token::Function::Fn) = tokens[0] {
             let max_expr_length= match self.find_expr_possible_boundary(&tokens, true, true, true) {
                 Ok(length) => length,
                 Err(e) => return (Err(e), offset)
             };
-            let tokens = &tokens[..max_expr_length];
-            let (node, pos) = self.parse_fn_declaration(&tokens);
+            let tokens = tokens.slice(..max_expr_length);
+            let (node, pos) = self.parse_fn_declaration(tokens);
             return (node, pos + offset);
         }
 
@@ -91,6 +124,14 @@ impl Parser {
             token::Token::Punctuation(token::Punctuation::Colon),
         ];
 
+        let compound_tokens = [
+            token::Token::Operator(token::Operator::PlusAssign),
+            token::Token::Operator(token::Operator::MinusAssign),
+            token::Token::Operator(token::Operator::MulAssign),
+            token::Token::Operator(token::Operator::DivAssign),
+            token::Token::Operator(token::Operator::ModAssign),
+        ];
+
         let bool_tokens = [
             token::Token::Operator(token::Operator::And),
             token::Token::Operator(token::Operator::Or),
@@ -113,11 +154,11 @@ impl Parser {
             Ok(length) => length,
             Err(e) => return (Err(e), offset)
         };
-        let tokens = &tokens[..max_expr_length];
+        let tokens = tokens.slice(..max_expr_length);
 
         for op in loop_tokens.iter() {
             if let Ok(Some(_)) = self.find_first_token_skip_brackets(&op, &tokens) {
-                let (node, pos) = self.parse_loop_expr(&tokens);
+                let (node, pos) = self.parse_loop_expr(tokens);
                 return (node, pos + offset);
             }
         }
@@ -126,12 +167,19 @@ impl Parser {
             Ok(length) => length,
             Err(e) => return (Err(e), offset)
         };
-        let tokens = &tokens[..max_expr_length];
+        let tokens = tokens.slice(..max_expr_length);
         
 
+        for op in compound_tokens.iter() {
+            if let Ok(Some(pos)) = self.find_first_token_skip_brackets(op, &tokens) {
+                let (node, i) = self.parse_compound_assignment_expr(tokens, pos);
+                return (node, i + offset);
+            }
+        }
+
         for op in decl_tokens.iter() {
             if let Ok(Some(_)) = self.find_first_token_skip_brackets(&op, &tokens) {
-                let (node, i) = self.parse_assignment_or_declaration_expr(&tokens);
+                let (node, i) = self.parse_assignment_or_declaration_expr(tokens);
                 let node = match node  {
                     Ok(n) => n,
                     Err(e) => return (Err(e), i + offset),
@@ -140,13 +188,18 @@ impl Parser {
             }
         }
 
+        if let Ok(Some(pos)) = self.find_first_token_skip_brackets(&token::Token::Error(token::Error::Except), &tokens) {
+            let (node, pos) = self.parse_except_expr(tokens, pos);
+            return (node, pos + offset);
+        }
+
         let max_expr_length= match self.find_expr_possible_boundary(&tokens, false, false, false) {
             Ok(length) => length,
             Err(e) => return (Err(e), offset)
         };
-        let tokens = &tokens[..max_expr_length];
+        let tokens = tokens.slice(..max_expr_length);
 
-        if self.is_tuple_expr(tokens) {
+        if self.is_tuple_expr(&tokens) {
             let tuple = match self.make_tuple(tokens) {
                 Ok(t) => t,
                 Err(e) => return (Err(e), max_expr_length)
@@ -157,15 +210,15 @@ impl Parser {
                 Err(e) => return (Err(e), max_expr_length)
             };
 
-            return (Ok(TupleASTNode::from_tuple(tuple)), max_expr_length)
+            return (Ok(TupleASTNode::from_tuple(tuple)), max_expr_length + offset)
         }
 
 
         for op in conditional_tokens.iter() {
             if let Ok(Some(_)) = self.find_first_token_skip_brackets(&op, &tokens) {
-                let (node, pos) = self.parse_conditional_expr(&tokens);
+                let (node, pos) = self.parse_conditional_expr(tokens);
                 let node = match node {
-                    Ok(node) =>  node.clone_to_node(),
+                    Ok(node) => node,
                     Err(e) => return (Err(e), pos),
                 };
                 return (Ok(node), pos + offset);
@@ -174,10 +227,20 @@ impl Parser {
 
         for op in bool_tokens.iter() {
             if let Ok(Some(_)) = self.find_first_token_skip_brackets(&op, &tokens) {
-                let (n, i) = self.parse_bool_expr(&tokens);
+                let (n, i) = self.parse_bool_expr(tokens);
                 return (n, i + offset);
             }
         }
-        self.parse_math_expr(tokens)
+        if let Ok(Some(pos)) = self.find_first_token_skip_brackets(&token::Token::Loop(token::Loop::In), &tokens) {
+            let (node, pos) = self.parse_contains_expr(tokens, pos);
+            return (node, pos + offset);
+        }
+        if let Ok(Some(pos)) = self.find_first_token_skip_brackets(&token::Token::Operator(token::Operator::Coalesce), &tokens) {
+            let (node, pos) = self.parse_coalesce_expr(tokens, pos);
+            return (node, pos + offset);
+        }
+
+        let (node, pos) = self.parse_math_expr(tokens);
+        (node, pos + offset)
     }
 } 
\ No newline at end of file
@@ -1,75 +1,80 @@
-use std::collections::HashMap;
-
 use crate::{lexer::token};
-use crate::ast::function::FnCall;
+use crate::ast::function::{Borrow, CallArgs, FnCall, MethodCall};
 use crate::ast::*;
-use super::parser::{Parser, ParseError};
+use super::parser::{Parser, ParseError, Tokens};
 use crate::Error;
-use crate::ast::tuple::Tuple;
+use std::cell::Cell;
+use std::rc::Rc;
 
 impl Parser {
-    pub fn parse_fn_call(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
-        let name = match tokens[0].clone() {
-            token::Token::Identifier(name) => name,
-            _ => return (Err(Error::ParserError(ParseError::UnexpectedToken(tokens[0].clone()))), 0),
+    pub fn parse_fn_call(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
+        let (name, pos) = match self.parse_qualified_name(&tokens) {
+            Some(qualified) => qualified,
+            None => return (Err(Error::ParserError(ParseError::UnexpectedToken(tokens[0].clone()))), 0),
         };
-        if 1 < tokens.len() && matches!(tokens[1], token::Token::Bracket(token::Bracket::OpenParen)) {
-            let matching_loc = match self.find_matching_bracket(&tokens, 1) {
+        if pos < tokens.len() && matches!(tokens[pos], token::Token::Bracket(token::Bracket::OpenParen)) {
+            let matching_loc = match self.find_matching_bracket(&tokens, pos) {
                 Ok(loc) => loc,
                 Err(e) => return (Err(e), 0),
             };
-            let params = match self.parse_params(&tokens[2..matching_loc]) {
+            let params = match self.parse_params(tokens.slice(pos+1..matching_loc)) {
                 Ok(params) => params,
                 Err(e) => return (Err(e), 0),
             };
-            return (Ok(Box::new(FnCall { name, params })), matching_loc + 1);
+            let position = self.position(tokens);
+            return (Ok(Node::new(FnCall { name, params, tail: Cell::default(), position, address: Cell::default() })), matching_loc + 1);
         }
         return (Err(Error::ParserError(ParseError::UnexpectedToken(tokens[0].clone()))), 0);
     }
 
-    fn parse_params(&mut self, tokens: &[token::Token]) -> Result<HashMap<Option<String>, Box<dyn ast::ASTNode>>, Error> {
-        let tuple = match self.make_tuple(tokens) {
-            Ok(tuple) => tuple,
-            Err(e) => return Err(e),
+    /// The call `.method(..)` of a method of `receiver` at the start of
+    /// `tokens`, with the index of the closing paren.
+    pub fn parse_method_call(&mut self, receiver: Rc<Node>, tokens: Tokens) -> Result<(Rc<Node>, usize), Error> {
+        let method = match tokens.get(1) {
+            Some(token::Token::Identifier(method)) => method.clone(),
+            Some(token) => return Err(Error::ParserError(ParseError::UnexpectedToken(token.clone()))),
+            None => return Err(Error::ParserError(ParseError::UnexpectedEndOfInput)),
         };
-        
-        let params = self.parse_tuple(tuple, |s, tok| {
-            if let Ok(Some(_)) = s.find_first_token_skip_brackets(&token::Token::Punctuation(token::Punctuation::Colon), tok) {
+        let end = self.find_matching_bracket(&tokens, 2)?;
+        let params = self.parse_params(tokens.slice(3..end))?;
+        Ok((Node::new(MethodCall { receiver, method, params }), end))
+    }
+
+    fn parse_params(&mut self, tokens: Tokens) -> Result<CallArgs, Error> {
+        // split on commas rather than read as a tuple, so that an argument may be a tuple
+        let mut params = Vec::new();
+        for tok in self.split_commas(tokens)? {
+            if let Ok(Some(_)) = self.find_first_token_skip_brackets(&token::Token::Punctuation(token::Punctuation::Colon), &tok) {
                 let name = match tok[0].clone() {
                     token::Token::Identifier(name) => name,
                     _ => return Err(Error::ParserError(ParseError::UnexpectedToken(tok[0].clone()))),
                 };
-    
+
                 if 1 >= tok.len() {
                     return Err(Error::ParserError(ParseError::UnexpectedEndOfInput));
-                }else if !matches!(tok[1], token::Token::Punctuation(token::Punctuation::Colon)) {
+                } else if !matches!(tok[1], token::Token::Punctuation(token::Punctuation::Colon)) {
                     return Err(Error::ParserError(ParseError::UnexpectedToken(tok[1].clone())));
                 } else {
-                    let value = s.parse_expr(&tok[2..]).0?;
-                    return Ok((Some(name), value));
+                    params.push((Some(name), self.parse_arg(tok.slice(2..))?));
                 }
             } else {
-                let value = s.parse_expr(&tok).0?;
-                return Ok((None, value));
+                params.push((None, self.parse_arg(tok)?));
             }
-        })?;
+        }
+        Ok(params)
+    }
 
-        match params {
-            Tuple::Empty => Ok(HashMap::new()),
-            Tuple::Element((name, value)) => Ok(HashMap::from([(name, value)])),
-            Tuple::List(elements) => {
-                let mut map = HashMap::new();
-                for element in elements {
-                    match element {
-                        Tuple::Element((name, value)) => {
-                            map.insert(name, value);
-                        }
-                        _ => return Err(Error::ParserError(ParseError::IncorrectFunctionCallSyntax)),
-                    }
-                }
-                Ok(map)
+    /// An argument, which is `mut x` to pass the variable `x` to a `mut` param.
+    fn parse_arg(&mut self, tokens: Tokens) -> Result<Rc<Node>, Error> {
+        match &*tokens {
+            [token::Token::VariableDeclaration(token::VariableDeclaration::Mut), token::Token::Identifier(name)] => {
+                Ok(Node::new(Borrow { name: name.clone() }))
             }
+            [token::Token::VariableDeclaration(token::VariableDeclaration::Mut)] => Err(Error::ParserError(ParseError::UnexpectedEndOfInput)),
+            [token::Token::VariableDeclaration(token::VariableDeclaration::Mut), token, ..] => {
+                Err(Error::ParserError(ParseError::UnexpectedToken(token.clone())))
+            }
+            _ => self.parse_expr(tokens).0,
         }
-
     }
-}
\ No newline at end of file
+}
@@ -1,20 +1,24 @@
-use std::collections::HashMap;
-
 use crate::ast::scope::Scope;
 use crate::ast::type_node::Type;
 use crate::{lexer::token};
 use crate::ast::function::{FnDeclaration, FnSignature};
 use crate::ast::*;
-use super::parser::{Parser, ParseError};
+use super::parser::{Parser, ParseError, Tokens};
 use crate::Error;
 use crate::ast::tuple::Tuple;
+use crate::ast::assignment::VariableDeclarationAssignment;
+use crate::ast::identifier::Identifier;
+use std::rc::Rc;
+
+/// The param names of the tuple params of a function, with their patterns.
+pub type ParamPatterns = Vec<(String, Tuple<String>)>;
 
 impl Parser {
 
-    pub fn parse_fn_signature(&mut self, tokens: &[token::Token]) -> (Result<(FnSignature, Option<String>), Error>, usize) {
+    pub fn parse_fn_signature(&mut self, tokens: Tokens) -> (Result<(FnSignature, Option<String>, ParamPatterns), Error>, usize) {
         // assumes that fn isn't part of the signature i.e. to parse fn + ... we only apply this function to ...
         let mut pos = 0;
-        match self.check_bounds(tokens, pos) {
+        match self.check_bounds(&tokens, pos) {
             Ok(_) => (),
             Err(e) => return (Err(e), 0),
         }
@@ -25,10 +29,12 @@ impl Parser {
         if name.is_some() {
             pos += 1;
         }
-        match self.check_bounds(tokens, pos) {
+        match self.check_bounds(&tokens, pos) {
             Ok(_) => (),
             Err(e) => return (Err(e), 0),
         }
+        let mut patterns = Vec::new();
+        let mut references = Vec::new();
         let params = if matches!(tokens[pos], token::Token::Bracket(token::Bracket::OpenParen)) {
             let matching_loc = match self.find_matching_bracket(&tokens, pos) {
                 Ok(loc) => loc,
@@ -36,97 +42,114 @@ impl Parser {
             };
 
             if matching_loc == pos + 1 {
-                pos += 1;
-                HashMap::new()
+                pos = matching_loc + 1;
+                Vec::new()
             } else {
             
-                let p = match self.parse_declaration_params(&tokens[pos+1..matching_loc]) {
+                let p = match self.parse_declaration_params(tokens.slice(pos+1..matching_loc)) {
                     Ok(params) => params,
                     Err(e) => return (Err(e), 0),
                 };
                 pos = matching_loc + 1;
-                p
+                p.into_iter().map(|(pattern, type_, reference)| match pattern {
+                    Tuple::Element(name) => {
+                        if reference {
+                            references.push(name.clone());
+                        }
+                        (name, type_)
+                    }
+                    // a tuple param is named after its pattern, which no argument can use
+                    pattern => {
+                        let name = pattern_name(&pattern);
+                        patterns.push((name.clone(), pattern));
+                        (name, type_)
+                    }
+                }).collect()
             }
         } else {
             return (Err(Error::ParserError(ParseError::UnexpectedToken(tokens[pos].clone()))), pos);
         };
-        match self.check_bounds(tokens, pos) {
-            Ok(_) => (),
-            Err(e) => return (Err(e), 0),
-        }
         let mut return_type = None;
-        if matches!(tokens[pos], token::Token::Function(token::Function::Arrow)) {
+        if matches!(tokens.get(pos), Some(token::Token::Function(token::Function::Arrow))) {
             pos += 1;
-            let (type_, new_pos) = match self.parse_type_expr(&tokens[pos..]) {
+            let (type_, new_pos) = match self.parse_type_expr(tokens.slice(pos..)) {
                 (Ok(type_), new_pos) => (type_, new_pos),
                 (Err(e), new_pos) => return (Err(e), new_pos+pos),
             };
             pos += new_pos;
             return_type = Some(type_);
         }
-        (Ok((FnSignature { params, return_type }, name)), pos)
+        (Ok((FnSignature { params, references, return_type }, name, patterns)), pos)
+    }
+
+    pub fn parse_fn_declaration(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
+        match self.parse_fn_parts(tokens) {
+            (Ok(declaration), pos) => (Ok(Node::new(declaration)), pos),
+            (Err(e), pos) => (Err(e), pos),
+        }
     }
 
-    pub fn parse_fn_declaration(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
+    pub fn parse_fn_parts(&mut self, tokens: Tokens) -> (Result<FnDeclaration, Error>, usize) {
 
         let ((block, expr), new_pos) = match self.parse_block_expr(tokens, Some(|s, tok| s.parse_fn_signature(tok))) {
             (Ok(k), new_pos) => (k, new_pos),
             (Err(e), new_pos) => return (Err(e), new_pos),
         };
 
-        println!("block: {:?}", block);
-        println!("expr: {:?}", expr);
-        println!("new_pos: {}", new_pos);
-
-        let (signature, name) = match expr {
-            Some((signature, name)) => (signature, name),
+        let (signature, name, patterns) = match expr {
+            Some(parts) => parts,
             None => return (Err(Error::ParserError(ParseError::UnexpectedToken(tokens[0].clone()))), 0),
         };
-        (Ok(Box::new(FnDeclaration::from_signature(name, signature, Scope::new(block.children())))), new_pos+1)
+        // tuple params are destructured before the body runs
+        let mut body: Vec<Rc<Node>> = Vec::new();
+        for (param, pattern) in patterns {
+            let type_ = signature.params.iter().find(|(p, _)| *p == param).map(|(_, type_)| type_.clone());
+            let expr = Node::new(Identifier::from_token(token::Token::Identifier(param)).unwrap());
+            body.push(Node::new(VariableDeclarationAssignment { mutable: false, type_, name: pattern, expr }));
+        }
+        body.extend(block.children());
+        (Ok(FnDeclaration::from_signature(name, signature, Scope::new(body))), new_pos+1)
     }
 
-    pub fn parse_declaration_params(&mut self, tokens: &[token::Token]) -> Result<HashMap<String, Type>, Error> {
-        let tuple = match self.make_tuple(tokens) {
-            Ok(tuple) => tuple,
-            Err(e) => return Err(e),
-        };
-        
-        let params = self.parse_tuple(tuple, |s, tok| {
-            if let Ok(Some(_)) = s.find_first_token_skip_brackets(&token::Token::Punctuation(token::Punctuation::Colon), tok) {
-                let name = match tok[0].clone() {
-                    token::Token::Identifier(name) => name,
-                    _ => return Err(Error::ParserError(ParseError::UnexpectedToken(tok[0].clone()))),
-                };
-    
-                if 1 >= tok.len() {
-                    return Err(Error::ParserError(ParseError::UnexpectedEndOfInput));
-                }else if !matches!(tok[1], token::Token::Punctuation(token::Punctuation::Colon)) {
-                    return Err(Error::ParserError(ParseError::UnexpectedToken(tok[1].clone())));
-                } else {
-                    let type_ = s.parse_type_expr(&tok[2..]).0?;
-                    return Ok((name, type_));
-                }
-            } else {
-                return Err(Error::ParserError(ParseError::UnexpectedToken(tok[0].clone())));
-            }
-        })?;
-
-        match params {
-            Tuple::Empty => Ok(HashMap::new()),
-            Tuple::Element((name, value)) => Ok(HashMap::from([(name, value)])),
-            Tuple::List(elements) => {
-                let mut map = HashMap::new();
-                for element in elements {
-                    match element {
-                        Tuple::Element((name, value)) => {
-                            map.insert(name, value);
-                        }
-                        _ => return Err(Error::ParserError(ParseError::IncorrectFunctionCallSyntax)),
+    /// The params of a signature, each a name or a tuple pattern like `(a, b)`,
+    /// and whether it is a `mut` param.
+    pub fn parse_declaration_params(&mut self, tokens: Tokens) -> Result<Vec<(Tuple<String>, Type, bool)>, Error> {
+        let mut params = Vec::new();
+        for param in self.split_commas(tokens)? {
+            let colon = match self.find_first_token_skip_brackets(&token::Token::Punctuation(token::Punctuation::Colon), &param)? {
+                Some(colon) => colon,
+                None => return Err(Error::ParserError(ParseError::UnexpectedToken(param[0].clone()))),
+            };
+            let (name, reference) = match &param[..colon] {
+                [token::Token::Identifier(name)] => (Tuple::Element(name.clone()), false),
+                [token::Token::VariableDeclaration(token::VariableDeclaration::Mut), token::Token::Identifier(name)] => (Tuple::Element(name.clone()), true),
+                [token::Token::Bracket(token::Bracket::OpenParen), .., token::Token::Bracket(token::Bracket::CloseParen)] => {
+                    let pattern = self.make_tuple(param.slice(1..colon - 1))?;
+                    if !self.is_identifier_tuple(pattern.clone()) {
+                        return Err(Error::ParserError(ParseError::AssignmentTupleNotIdentifier));
                     }
+                    let pattern = self.parse_tuple(pattern, |_, tok| match &tok[0] {
+                        token::Token::Identifier(name) => Ok(name.clone()),
+                        token => Err(Error::ParserError(ParseError::UnexpectedToken(token.clone()))),
+                    })?;
+                    (pattern, false)
                 }
-                Ok(map)
+                _ => return Err(Error::ParserError(ParseError::UnexpectedToken(param[0].clone()))),
+            };
+            if colon + 1 >= param.len() {
+                return Err(Error::ParserError(ParseError::UnexpectedEndOfInput));
             }
+            params.push((name, self.parse_type_expr(param.slice(colon + 1..)).0?, reference));
         }
+        Ok(params)
     }
 
-}
\ No newline at end of file
+}
+
+fn pattern_name(pattern: &Tuple<String>) -> String {
+    match pattern {
+        Tuple::Empty => "()".to_string(),
+        Tuple::Element(name) => name.clone(),
+        Tuple::List(names) => format!("({})", names.iter().map(pattern_name).collect::<Vec<_>>().join(", ")),
+    }
+}
@@ -0,0 +1,85 @@
+use crate::ast::import::Import;
+use crate::ast::*;
+use crate::lexer::token;
+use super::parser::{Parser, ParseError, Tokens};
+use crate::Error;
+use std::rc::Rc;
+
+impl Parser {
+    pub fn parse_import_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
+        match tokens[0] {
+            token::Token::Module(token::Module::Import) => {
+                if tokens.len() > 1 && tokens[1] == token::Token::Bracket(token::Bracket::OpenBrace) {
+                    let matching_loc = match self.find_matching_bracket(&tokens, 1) {
+                        Ok(loc) => loc,
+                        Err(e) => return (Err(e), 1),
+                    };
+                    let mut imports: Vec<Rc<Node>> = Vec::new();
+                    let mut cursor = 2;
+                    while cursor < matching_loc {
+                        match tokens[cursor] {
+                            token::Token::Whitespace(_) | token::Token::Punctuation(token::Punctuation::Comma) => cursor += 1,
+                            _ => {
+                                let (import, pos) = match self.parse_module_path(tokens.slice(cursor..matching_loc)) {
+                                    (Ok(import), pos) => (import, pos),
+                                    (Err(e), pos) => return (Err(e), cursor + pos),
+                                };
+                                imports.push(Node::new(import));
+                                cursor += pos;
+                            }
+                        }
+                    }
+                    (Ok(Node::new(MultiExpression { children: imports })), matching_loc + 1)
+                } else {
+                    let (import, pos) = self.parse_module_path(tokens.slice(1..));
+                    (import.map(Node::new), pos + 1)
+                }
+            }
+            token::Token::Module(token::Module::From) => {
+                let path = match tokens.get(1) {
+                    Some(token::Token::Literal(token::Literal::String(path, _))) => path.clone(),
+                    Some(tok) => return (Err(Error::ParserError(ParseError::UnexpectedToken(tok.clone()))), 1),
+                    None => return (Err(Error::ParserError(ParseError::UnexpectedEndOfInput)), 1),
+                };
+                match tokens.get(2) {
+                    Some(token::Token::Module(token::Module::Import)) => {}
+                    Some(tok) => return (Err(Error::ParserError(ParseError::UnexpectedToken(tok.clone()))), 2),
+                    None => return (Err(Error::ParserError(ParseError::UnexpectedEndOfInput)), 2),
+                }
+                let mut names = Vec::new();
+                let mut cursor = 3;
+                while cursor < tokens.len() {
+                    match &tokens[cursor] {
+                        token::Token::Identifier(name) => names.push(name.clone()),
+                        token::Token::Punctuation(token::Punctuation::Comma) => {}
+                        token::Token::Whitespace(token::Whitespace::Newline) => break,
+                        tok => return (Err(Error::ParserError(ParseError::UnexpectedToken(tok.clone()))), cursor),
+                    }
+                    cursor += 1;
+                }
+                if names.is_empty() {
+                    return (Err(Error::ParserError(ParseError::UnexpectedEndOfInput)), cursor);
+                }
+                (Ok(Node::new(Import { path, alias: None, names: Some(names) })), cursor)
+            }
+            _ => (Err(Error::ParserError(ParseError::UnexpectedToken(tokens[0].clone()))), 0),
+        }
+    }
+
+    fn parse_module_path(&mut self, tokens: Tokens) -> (Result<Import, Error>, usize) {
+        // parses `'scheme:path'` optionally followed by `as alias`
+        let path = match tokens.first() {
+            Some(token::Token::Literal(token::Literal::String(path, _))) => path.clone(),
+            Some(tok) => return (Err(Error::ParserError(ParseError::UnexpectedToken(tok.clone()))), 0),
+            None => return (Err(Error::ParserError(ParseError::UnexpectedEndOfInput)), 0),
+        };
+        if tokens.len() > 1 && tokens[1] == token::Token::Module(token::Module::As) {
+            return match tokens.get(2) {
+                Some(token::Token::Identifier(alias)) => (Ok(Import { path, alias: Some(alias.clone()), names: None }), 3),
+                Some(tok) => (Err(Error::ParserError(ParseError::UnexpectedToken(tok.clone()))), 2),
+                None => (Err(Error::ParserError(ParseError::UnexpectedEndOfInput)), 2),
+            };
+        }
+        (Ok(Import { path, alias: None, names: None }), 1)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::ast::list::{Contains, Index, List, Slice};
+use crate::ast::*;
+use crate::lexer::token;
+use super::parser::{Parser, ParseError, Tokens};
+use crate::Error;
+use std::rc::Rc;
+
+impl Parser {
+    /// Parses `[a, b, c]`, with `tokens` ending at the closing bracket.
+    pub fn parse_list_expr(&mut self, tokens: Tokens) -> Result<Rc<Node>, Error> {
+        let mut elements = Vec::new();
+        for element in self.split_commas(tokens.slice(1..tokens.len() - 1))? {
+            elements.push(self.parse_expr(element).0?);
+        }
+        Ok(Node::new(List { elements }))
+    }
+
+    /// Parses the `[index]` or `[start:end]` after `value`, with `tokens`
+    /// holding what is between the brackets.
+    pub fn parse_index_expr(&mut self, value: Rc<Node>, tokens: Tokens) -> Result<Rc<Node>, Error> {
+        let colon = token::Token::Punctuation(token::Punctuation::Colon);
+        match self.find_first_token_skip_brackets(&colon, &tokens)? {
+            Some(pos) => {
+                let start = if pos == 0 { None } else { Some(self.parse_expr(tokens.slice(..pos)).0?) };
+                let end = if pos + 1 == tokens.len() { None } else { Some(self.parse_expr(tokens.slice(pos + 1..)).0?) };
+                Ok(Node::new(Slice { value, start, end }))
+            }
+            None if tokens.is_empty() => Err(Error::ParserError(ParseError::UnexpectedToken(token::Token::Bracket(token::Bracket::CloseBracket)))),
+            None => Ok(Node::new(Index { value, index: self.parse_expr(tokens).0? })),
+        }
+    }
+
+    pub fn parse_contains_expr(&mut self, tokens: Tokens, pos: usize) -> (Result<Rc<Node>, Error>, usize) {
+        let value = match self.parse_expr(tokens.slice(..pos)) {
+            (Ok(value), _) => value,
+            (Err(e), value_pos) => return (Err(e), value_pos),
+        };
+        let (list, list_pos) = match self.parse_expr(tokens.slice(pos + 1..)) {
+            (Ok(list), list_pos) => (list, list_pos),
+            (Err(e), list_pos) => return (Err(e), pos + 1 + list_pos),
+        };
+        (Ok(Node::new(Contains { value, list })), pos + 1 + list_pos)
+    }
+
+    /// The comma separated parts of `tokens`, which may span lines and end
+    /// with a trailing comma.
+    pub fn split_commas<'a>(&mut self, tokens: Tokens<'a>) -> Result<Vec<Tokens<'a>>, Error> {
+        let comma = token::Token::Punctuation(token::Punctuation::Comma);
+        let mut parts = Vec::new();
+        let mut rest = tokens;
+        while !rest.is_empty() {
+            let (part, next) = match self.find_first_token_skip_brackets(&comma, &rest)? {
+                Some(pos) => (rest.slice(..pos), rest.slice(pos + 1..)),
+                None => (rest, rest.slice(rest.len()..)),
+            };
+            let is_whitespace = |t: &token::Token| matches!(t, token::Token::Whitespace(_));
+            if let (Some(first), Some(last)) = (part.iter().position(|t| !is_whitespace(t)), part.iter().rposition(|t| !is_whitespace(t))) {
+                parts.push(part.slice(first..=last));
+            }
+            rest = next;
+        }
+        Ok(parts)
+    }
+}
@@ -1,11 +1,13 @@
 use crate::{ast::{ASTNode, scope::Scope}, ast::loops::{Loop, For, While}, lexer::{token}};
-use super::parser::{Parser, ParseError};
+use crate::ast::Node;
+use super::parser::{Parser, ParseError, Tokens};
 use crate::Error;
+use std::rc::Rc;
 
 
 
 impl Parser {
-    pub fn parse_loop_expr(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ASTNode>, Error>, usize) {
+    pub fn parse_loop_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
         if matches!(tokens[0], token::Token::Loop(token::Loop::Loop)) 
         || matches!(tokens[0], token::Token::Loop(token::Loop::For))
         || matches!(tokens[0], token::Token::Loop(token::Loop::While)) {
@@ -21,10 +23,10 @@ impl Parser {
             };
 
 
-            let output: Result<Box<dyn ASTNode>, Error> = match tokens[0] {
-                token::Token::Loop(token::Loop::For) => Ok(Box::new(For::new(expr.unwrap(), Scope::new(content.children())))),
-                token::Token::Loop(token::Loop::While) => Ok(Box::new(While::new(expr.unwrap(), Scope::new(content.children())))),
-                token::Token::Loop(token::Loop::Loop) => Ok(Box::new(Loop::new(Scope::new(content.children())))),
+            let output: Result<Rc<Node>, Error> = match tokens[0] {
+                token::Token::Loop(token::Loop::For) => Ok(Node::new(For::new(expr.unwrap(), Scope::new(content.children())))),
+                token::Token::Loop(token::Loop::While) => Ok(Node::new(While::new(expr.unwrap(), Scope::new(content.children())))),
+                token::Token::Loop(token::Loop::Loop) => Ok(Node::new(Loop::new(Scope::new(content.children())))),
                 _ => Err(Error::ParserError(ParseError::NoLoopFound))
             };
 
@@ -34,7 +36,7 @@ impl Parser {
 
     }
 
-    pub fn parse_for_expr(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ASTNode>, Error>, usize) {
+    pub fn parse_for_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
         (Err(Error::ParserError(ParseError::Unimplimented)), 0)
     }
 }
\ No newline at end of file
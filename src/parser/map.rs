@@ -0,0 +1,41 @@
+use crate::ast::map::MapLiteral;
+use crate::ast::*;
+use crate::lexer::token;
+use super::parser::{Parser, ParseError, Tokens};
+use crate::Error;
+use std::rc::Rc;
+
+impl Parser {
+    /// Parses `{key: value, ...}`, with `tokens` ending at the closing brace.
+    pub fn parse_map_expr(&mut self, tokens: Tokens) -> Result<Rc<Node>, Error> {
+        let colon = token::Token::Punctuation(token::Punctuation::Colon);
+        let mut entries = Vec::new();
+        for entry in self.split_commas(tokens.slice(1..tokens.len() - 1))? {
+            let pos = match self.find_first_token_skip_brackets(&colon, &entry)? {
+                Some(pos) if pos > 0 && pos + 1 < entry.len() => pos,
+                _ => return Err(Error::ParserError(ParseError::ExpectedMapEntry)),
+            };
+            let key = self.parse_expr(entry.slice(..pos)).0?;
+            let value = self.parse_expr(entry.slice(pos + 1..)).0?;
+            entries.push((key, value));
+        }
+        Ok(Node::new(MapLiteral { entries }))
+    }
+
+    /// Whether the braces opening `tokens` and closing at `end` hold
+    /// `key: value` entries rather than the statements of a block.
+    pub fn is_map_literal(&mut self, tokens: &[token::Token], end: usize) -> bool {
+        let content = &tokens[1..end];
+        let start = content.iter().take_while(|t| matches!(t, token::Token::Whitespace(_))).count();
+        let content = &content[start..];
+        if matches!(content.first(), Some(token::Token::VariableDeclaration(_))) {
+            return false;
+        }
+        let first_of = |parser: &mut Self, token: token::Token| parser.find_first_token_skip_brackets(&token, content).ok().flatten();
+        let entry_end = [
+            first_of(self, token::Token::Whitespace(token::Whitespace::Newline)),
+            first_of(self, token::Token::Punctuation(token::Punctuation::Comma)),
+        ].into_iter().flatten().min().unwrap_or(content.len());
+        matches!(first_of(self, token::Token::Punctuation(token::Punctuation::Colon)), Some(pos) if pos > 0 && pos < entry_end)
+    }
+}
@@ -1,4 +1,5 @@
 pub mod parser;
+pub mod resolver;
 pub mod test_parser;
 
 mod declaration_assignment;
@@ -11,4 +12,10 @@ mod type_expr;
 mod utils;
 mod loops;
 mod block;
-mod tuple;
\ No newline at end of file
+mod tuple;
+mod list;
+mod map;
+mod import;
+mod optional;
+mod error;
+mod task;
\ No newline at end of file
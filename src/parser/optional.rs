@@ -0,0 +1,125 @@
+use crate::ast::cast::Cast;
+use crate::ast::optional::{Coalesce, Propagate, Unwrap};
+use crate::ast::tuple::TupleIndex;
+use crate::ast::type_node::Type;
+use crate::ast::*;
+use crate::lexer::token;
+use super::parser::{Parser, ParseError, Tokens};
+use crate::Error;
+use std::rc::Rc;
+
+impl Parser {
+    /// Wraps `node` in the postfix `?` and `!` operators, the `as` casts, the
+    /// indexing and slicing and the tuple indexes found at `pos`.
+    pub fn parse_postfix(&mut self, tokens: Tokens, node: Result<Rc<Node>, Error>, pos: usize) -> (Result<Rc<Node>, Error>, usize) {
+        let mut node = match node {
+            Ok(node) => node,
+            Err(e) => return (Err(e), pos),
+        };
+        let mut pos = pos;
+        while pos < tokens.len() {
+            node = match tokens[pos] {
+                token::Token::Punctuation(token::Punctuation::Question) => Node::new(Propagate { value: node }),
+                token::Token::Punctuation(token::Punctuation::Exclamation) => Node::new(Unwrap { value: node }),
+                token::Token::Module(token::Module::As) => match self.cast_length(&tokens[pos..]) {
+                    Some(length) => {
+                        let lossy = length == 3;
+                        let type_ = match &tokens[pos + length - 1] {
+                            token::Token::Type(type_) => Type::from_token_type(type_.clone()),
+                            token => return (Err(Error::ParserError(ParseError::UnexpectedToken(token.clone()))), pos),
+                        };
+                        pos += length - 1;
+                        Node::new(Cast { value: node, type_, lossy })
+                    }
+                    None => return (Err(Error::ParserError(ParseError::UnexpectedToken(tokens[pos].clone()))), pos),
+                },
+                token::Token::Bracket(token::Bracket::OpenBracket) => {
+                    let end = match self.find_matching_bracket(&tokens, pos) {
+                        Ok(end) => end,
+                        Err(e) => return (Err(e), pos),
+                    };
+                    let node = match self.parse_index_expr(node, tokens.slice(pos + 1..end)) {
+                        Ok(node) => node,
+                        Err(e) => return (Err(e), pos),
+                    };
+                    pos = end;
+                    node
+                }
+                token::Token::Punctuation(token::Punctuation::Dot) => match tokens.get(pos + 1) {
+                    Some(token::Token::Literal(token::Literal::Int(index))) => {
+                        pos += 1;
+                        Node::new(TupleIndex { value: node, index: *index as usize })
+                    }
+                    Some(token::Token::Identifier(_)) if self.is_method_call(&tokens[pos..]) => {
+                        match self.parse_method_call(node, tokens.slice(pos..)) {
+                            Ok((node, end)) => {
+                                pos += end;
+                                node
+                            }
+                            Err(e) => return (Err(e), pos),
+                        }
+                    }
+                    _ => break,
+                },
+                _ => break,
+            };
+            pos += 1;
+        }
+        (Ok(node), pos)
+    }
+
+    pub fn skip_postfix(&mut self, tokens: &[token::Token], pos: usize) -> usize {
+        let mut pos = pos;
+        while pos < tokens.len() {
+            pos += match tokens[pos] {
+                token::Token::Punctuation(token::Punctuation::Question) | token::Token::Punctuation(token::Punctuation::Exclamation) => 1,
+                token::Token::Module(token::Module::As) => match self.cast_length(&tokens[pos..]) {
+                    Some(length) => length,
+                    None => break,
+                },
+                token::Token::Bracket(token::Bracket::OpenBracket) => match self.find_matching_bracket(&tokens, pos) {
+                    Ok(end) => end + 1 - pos,
+                    Err(_) => break,
+                },
+                token::Token::Punctuation(token::Punctuation::Dot) if matches!(tokens.get(pos + 1), Some(token::Token::Literal(token::Literal::Int(_)))) => 2,
+                token::Token::Punctuation(token::Punctuation::Dot) if self.is_method_call(&tokens[pos..]) => match self.find_matching_bracket(tokens, pos + 2) {
+                    Ok(end) => end + 1 - pos,
+                    Err(_) => break,
+                },
+                _ => break,
+            };
+        }
+        pos
+    }
+
+    /// Whether `tokens` start with `.method(`.
+    fn is_method_call(&self, tokens: &[token::Token]) -> bool {
+        matches!(tokens, [
+            token::Token::Punctuation(token::Punctuation::Dot),
+            token::Token::Identifier(_),
+            token::Token::Bracket(token::Bracket::OpenParen),
+            ..
+        ])
+    }
+
+    /// The number of tokens of an `as type` or `as! type` cast at the start of `tokens`.
+    pub fn cast_length(&self, tokens: &[token::Token]) -> Option<usize> {
+        match tokens {
+            [token::Token::Module(token::Module::As), token::Token::Type(_), ..] => Some(2),
+            [token::Token::Module(token::Module::As), token::Token::Punctuation(token::Punctuation::Exclamation), token::Token::Type(_), ..] => Some(3),
+            _ => None,
+        }
+    }
+
+    pub fn parse_coalesce_expr(&mut self, tokens: Tokens, pos: usize) -> (Result<Rc<Node>, Error>, usize) {
+        let value = match self.parse_expr(tokens.slice(..pos)) {
+            (Ok(value), _) => value,
+            (Err(e), value_pos) => return (Err(e), value_pos),
+        };
+        let (default, default_pos) = match self.parse_expr(tokens.slice(pos + 1..)) {
+            (Ok(default), default_pos) => (default, default_pos),
+            (Err(e), default_pos) => return (Err(e), pos + 1 + default_pos),
+        };
+        (Ok(Node::new(Coalesce { value, default })), pos + 1 + default_pos)
+    }
+}
@@ -1,9 +1,65 @@
-use crate::lexer::{token, Lexer};
+use crate::lexer::{token, Lexer, Position, Span};
 use crate::ast::*;
+use crate::ast::tuple::Clonable;
 use crate::Error;
+use super::resolver::resolve;
+use std::ops::{Bound, Deref, RangeBounds};
+use std::rc::Rc;
 
 pub struct Parser {
     pub tokens: Vec<token::Token>,
+    /// Where each of the tokens is in the source, if known.
+    spans: Vec<Span>,
+    /// The token, or the start of the statement, that parsing failed at.
+    error_span: Option<Span>,
+}
+
+/// A run of the tokens being parsed, which knows the index in them it starts
+/// at so that what is parsed from it can be given a position.
+#[derive(Debug, Clone, Copy)]
+pub struct Tokens<'a> {
+    tokens: &'a [token::Token],
+    start: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(tokens: &'a [token::Token]) -> Self {
+        Self { tokens, start: 0 }
+    }
+
+    /// The tokens in `range`, which is relative to these tokens.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Tokens<'a> {
+        let from = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i + 1,
+            Bound::Unbounded => 0,
+        };
+        let to = match range.end_bound() {
+            Bound::Included(&i) => i + 1,
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.tokens.len(),
+        };
+        Tokens { tokens: &self.tokens[from..to], start: self.start + from }
+    }
+
+    /// The index of the first of these tokens in the tokens being parsed.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+}
+
+impl Deref for Tokens<'_> {
+    type Target = [token::Token];
+
+    fn deref(&self) -> &Self::Target {
+        self.tokens
+    }
+}
+
+impl Clonable for Tokens<'_> {
+    fn clone_element(&self) -> Self {
+        *self
+    }
 }
 
 #[derive(Debug)]
@@ -24,39 +80,131 @@ pub enum ParseError {
     UnexpectedBeginningOfBlock,
     AssignmentTupleNotIdentifier,
     IncorrectFunctionCallSyntax,
+    /// Something other than `key: value` between the braces of a map.
+    ExpectedMapEntry,
+    /// A `mut` param of an async fn, whose task only has copies of the
+    /// caller's variables.
+    AsyncReference(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedEndOfInput => write!(f, "unexpected end of input"),
+            ParseError::Unimplimented => write!(f, "this syntax is not implemented yet"),
+            ParseError::CannotBuildTupleType => write!(f, "invalid tuple type"),
+            ParseError::EmptyTokens => write!(f, "expected an expression"),
+            ParseError::InvalidExpression => write!(f, "invalid expression"),
+            ParseError::InvalidOperator => write!(f, "invalid operator"),
+            ParseError::UnexpectedToken(token) => write!(f, "unexpected `{}`", token.source()),
+            ParseError::NoMatchingBracket => write!(f, "unclosed bracket"),
+            ParseError::NoConditionalFound => write!(f, "expected `if`"),
+            ParseError::NoMatchingBraceForKeyword(keyword) => write!(f, "expected a `{{` block after `{}`", keyword.source()),
+            ParseError::NoConditionForConditional => write!(f, "expected a condition"),
+            ParseError::NoLoopFound => write!(f, "expected `loop`, `while` or `for`"),
+            ParseError::UnexpectedContentBeforeBlock => write!(f, "unexpected code before the block"),
+            ParseError::UnexpectedBeginningOfBlock => write!(f, "expected an expression before the block"),
+            ParseError::AssignmentTupleNotIdentifier => write!(f, "only names can be assigned to"),
+            ParseError::IncorrectFunctionCallSyntax => write!(f, "invalid function call"),
+            ParseError::ExpectedMapEntry => write!(f, "expected a `key: value` entry"),
+            ParseError::AsyncReference(param) => write!(f, "`async fn` cannot take `mut {}`", param),
+        }
+    }
 }
 
 impl Parser {
     pub fn new(tokens: Vec<token::Token>) -> Self {
-        Self { tokens: tokens.into_iter().filter(|t| !matches!(t, token::Token::Comment(_))).collect() }
+        Self { tokens: tokens.into_iter().filter(|t| !matches!(t, token::Token::Comment(_))).collect(), spans: Vec::new(), error_span: None }
+    }
+
+    /// A parser for tokens lexed at `spans`, which gives the nodes parsed
+    /// where they are in the source.
+    pub fn with_spans(tokens: Vec<token::Token>, spans: Vec<Span>) -> Self {
+        if tokens.len() != spans.len() {
+            return Self::new(tokens);
+        }
+        let (tokens, spans) = tokens.into_iter().zip(spans)
+            .filter(|(t, _)| !matches!(t, token::Token::Comment(_)))
+            .unzip();
+        Self { tokens, spans, error_span: None }
+    }
+
+    /// Where `tokens` start in the source.
+    pub fn position(&self, tokens: Tokens) -> Option<Position> {
+        self.spans.get(tokens.start()).map(|span| span.start)
     }
 
-    pub fn parse(&mut self) -> Result<Box<dyn ast::ASTNode>, Error> {
-        match self.parse_tokens(&self.tokens.clone()) {
-            (Ok(v), _) => Ok(v),
-            (Err(e), _) => Err(e)
+    /// Where `tokens` are in the source, without the whitespace around them.
+    pub fn span(&self, tokens: Tokens) -> Option<Span> {
+        let is_code = |t: &token::Token| !matches!(t, token::Token::Whitespace(_));
+        let first = tokens.iter().position(is_code)?;
+        let last = tokens.iter().rposition(is_code)?;
+        Some(self.spans.get(tokens.start() + first)?.to(*self.spans.get(tokens.start() + last)?))
+    }
+
+    /// Where the last `parse` failed, if the tokens have spans.
+    pub fn error_span(&self) -> Option<Span> {
+        self.error_span
+    }
+
+    /// The token `error` is about in the statement starting `tokens`, or else
+    /// the first token of the statement.
+    fn locate(&self, error: &Error, tokens: Tokens) -> Option<Span> {
+        let token = match error {
+            Error::ParserError(ParseError::UnexpectedToken(token) | ParseError::NoMatchingBraceForKeyword(token)) => Some(token),
+            _ => None,
+        };
+        let index = token.and_then(|token| tokens.iter().position(|t| t == token))
+            .or_else(|| tokens.iter().position(|t| !matches!(t, token::Token::Whitespace(_))))?;
+        self.spans.get(tokens.start() + index).copied()
+    }
+
+    /// Gives `node` the span of `tokens` unless it has one, such as the
+    /// expression in parentheses that was parsed from part of them.
+    pub(super) fn attach_span(&self, node: &mut Rc<Node>, tokens: Tokens) {
+        if let Some(node) = Rc::get_mut(node) {
+            if node.span.is_none() {
+                node.span = self.span(tokens);
+            }
         }
     }
 
-    pub fn parse_tokens(&mut self, tokens: &[token::Token]) -> (Result<Box<dyn ast::ASTNode>, Error>, usize) {
-        let mut tokens = tokens.to_vec();
+    pub fn parse(&mut self) -> Result<Rc<Node>, Error> {
+        self.error_span = None;
+        let tokens = std::mem::take(&mut self.tokens);
+        let result = self.parse_tokens(Tokens::new(&tokens)).0;
+        self.tokens = tokens;
+        let node = result?;
+        resolve(&node);
+        Ok(node)
+    }
+
+    pub fn parse_tokens(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
+        let mut tokens = tokens;
         let mut result = MultiExpression { children: Vec::new() };
         if tokens.is_empty() {
-            return (Ok(Box::new(EmptyASTNode::new())), 0);
+            return (Ok(Node::new(EmptyASTNode::new())), 0);
         }
         while !tokens.is_empty() {
-            let (node, new_pos) = self.parse_expr(&tokens);
+            let (node, new_pos) = self.parse_expr(tokens);
             
             match node {
                 Ok(node) => result.children.push(node),
-                Err(e) => return (Err(e), new_pos),
+                Err(e) => {
+                    if self.error_span.is_none() {
+                        self.error_span = self.locate(&e, tokens);
+                    }
+                    return (Err(e), new_pos)
+                }
             };
-            tokens = tokens[new_pos..].to_vec();
+            // an error an earlier statement recovered from is not where parsing failed
+            self.error_span = None;
+            tokens = tokens.slice(new_pos..);
         }
-        (Ok(Box::new(result)), tokens.len())
+        (Ok(Node::new(result)), tokens.len())
     }
 
-    pub fn parse_string(string: &String) -> Result<Box<dyn ast::ASTNode>, Error> {
+    pub fn parse_string(string: &String) -> Result<Rc<Node>, Error> {
         let mut lexer = Lexer::new(string.clone());
         lexer.tokenize();
         let mut parser = Parser::new(lexer.tokens);
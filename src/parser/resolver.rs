@@ -0,0 +1,191 @@
+use crate::ast::scope::Scope;
+use crate::ast::tuple::Tuple;
+use crate::ast::{ASTNode, Node, NodeKind};
+use crate::environment::frame::Address;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Gives the variables used in a block the address of their declaration,
+/// counting frames the way `Scope::eval` creates them. Variables and
+/// functions that are not declared in an enclosing block of the same
+/// function are globals, looked for in the outermost frame before the frames
+/// of the callers, which a function body can see. Names are only looked up
+/// by name in a task, which sees copies of its variables, and in a block
+/// with an import.
+pub fn resolve(node: &Rc<Node>) {
+    let mut resolver = Resolver { frames: vec![Frame::outermost()] };
+    resolver.node(node);
+}
+
+struct Frame {
+    slots: HashMap<String, usize>,
+    /// Whether names are declared in the frame at run time only, so that
+    /// the slots of the frame, and what it shadows, are not known.
+    dynamic: bool,
+    /// Whether the frame stands for the frames around a function or the
+    /// program, where a name not found in the blocks inside is a global.
+    outermost: bool,
+}
+
+impl Frame {
+    fn new() -> Self {
+        Self { slots: HashMap::new(), dynamic: false, outermost: false }
+    }
+
+    fn dynamic() -> Self {
+        Self { slots: HashMap::new(), dynamic: true, outermost: false }
+    }
+
+    fn outermost() -> Self {
+        Self { slots: HashMap::new(), dynamic: true, outermost: true }
+    }
+
+    /// Declares `name` like `environment::frame::Frame::insert`.
+    fn declare(&mut self, name: &str) {
+        let slot = self.slots.len();
+        self.slots.entry(name.to_string()).or_insert(slot);
+    }
+}
+
+/// Marks the calls whose value is the value of the block, through the
+/// branches of an `if` that ends it.
+fn mark_tail_calls(scope: &Scope) {
+    if let Some(last) = scope.nodes().last() {
+        mark_tail_call(last);
+    }
+}
+
+fn mark_tail_call(node: &Rc<Node>) {
+    match &node.kind {
+        NodeKind::FnCall(call) => call.tail.set(true),
+        NodeKind::IfBlock(block) => {
+            mark_tail_calls(&block.content);
+            block.next_conditional.iter().for_each(mark_tail_call);
+        }
+        NodeKind::ElifBlock(block) => {
+            mark_tail_calls(&block.content);
+            block.next_conditional.iter().for_each(mark_tail_call);
+        }
+        NodeKind::ElseBlock(block) => mark_tail_calls(&block.content),
+        _ => {}
+    }
+}
+
+/// Innermost frame last.
+struct Resolver {
+    frames: Vec<Frame>,
+}
+
+impl Resolver {
+    fn address(&self, name: &str) -> Option<Address> {
+        for (depth, frame) in self.frames.iter().rev().enumerate() {
+            if frame.dynamic {
+                return frame.outermost.then_some(Address::Global);
+            }
+            if let Some(slot) = frame.slots.get(name) {
+                return Some(Address::Local { depth, slot: *slot });
+            }
+        }
+        None
+    }
+
+    fn declare(&mut self, name: &Tuple<String>) {
+        match name {
+            Tuple::Element(name) => self.frames.last_mut().unwrap().declare(name),
+            Tuple::List(names) => names.iter().for_each(|name| self.declare(name)),
+            Tuple::Empty => {}
+        }
+    }
+
+    fn scope(&mut self, scope: &Scope) {
+        if !scope.declares() {
+            scope.nodes().iter().for_each(|node| self.node(node));
+            return;
+        }
+        self.frames.push(Frame::new());
+        scope.nodes().iter().for_each(|node| self.node(node));
+        self.frames.pop();
+    }
+
+    /// Resolves code that runs in a frame chain of its own, like the body of
+    /// a function, inside `around` and starting with a frame declaring `names`.
+    fn detached(&mut self, around: Frame, names: &[String], resolve: impl FnOnce(&mut Self)) {
+        let mut frame = Frame::new();
+        names.iter().for_each(|name| frame.declare(name));
+        let outer = std::mem::replace(&mut self.frames, vec![around, frame]);
+        resolve(self);
+        self.frames = outer;
+    }
+
+    fn node(&mut self, node: &Rc<Node>) {
+        match &node.kind {
+            NodeKind::Identifier(identifier) => identifier.address.set(self.address(&identifier.name)),
+            NodeKind::FnCall(call) => {
+                call.address.set(self.address(&call.name));
+                call.params.iter().for_each(|(_, arg)| self.node(arg));
+            }
+            NodeKind::VariableAssignment(assignment) => {
+                self.node(&assignment.expr);
+                if let Tuple::Element(name) = &assignment.name {
+                    assignment.address.set(self.address(name));
+                }
+            }
+            NodeKind::CompoundAssignment(assignment) => {
+                self.node(&assignment.expr);
+                if let Tuple::Element(name) = &assignment.name {
+                    assignment.address.set(self.address(name));
+                }
+            }
+            NodeKind::VariableDeclarationAssignment(declaration) => {
+                self.node(&declaration.expr);
+                self.declare(&declaration.name);
+            }
+            NodeKind::VariableDeclaration(declaration) => self.declare(&declaration.name),
+            NodeKind::FnDeclaration(declaration) => {
+                if let Some(name) = &declaration.name {
+                    self.frames.last_mut().unwrap().declare(name);
+                }
+                let params = declaration.params.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+                self.detached(Frame::outermost(), &params, |resolver| resolver.scope(&declaration.body));
+                mark_tail_calls(&declaration.body);
+            }
+            // a module declares names that are only known once it is loaded
+            NodeKind::Import(_) => self.frames.last_mut().unwrap().dynamic = true,
+            NodeKind::IfBlock(block) => {
+                self.node(&block.condition);
+                self.scope(&block.content);
+                block.next_conditional.iter().for_each(|next| self.node(next));
+            }
+            NodeKind::ElifBlock(block) => {
+                self.node(&block.condition);
+                self.scope(&block.content);
+                block.next_conditional.iter().for_each(|next| self.node(next));
+            }
+            NodeKind::ElseBlock(block) => self.scope(&block.content),
+            NodeKind::Loop(block) => self.scope(&block.content),
+            NodeKind::While(block) => {
+                self.node(&block.condition);
+                self.scope(&block.content);
+            }
+            NodeKind::For(block) => {
+                self.node(&block.range_expr);
+                self.scope(&block.content);
+            }
+            NodeKind::Except(except) => {
+                self.scope(&except.body);
+                for handler in &except.handlers {
+                    // handlers run in a frame holding their binding
+                    self.frames.push(Frame::new());
+                    if let Some(binding) = &handler.binding {
+                        self.frames.last_mut().unwrap().declare(binding);
+                    }
+                    self.scope(&handler.body);
+                    self.frames.pop();
+                }
+            }
+            // a task sees copies of the variables around it
+            NodeKind::Async(task) => self.detached(Frame::dynamic(), &[], |resolver| resolver.scope(&task.body)),
+            _ => node.children().iter().for_each(|child| self.node(child)),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::ast::function::FnDeclaration;
+use crate::ast::scope::Scope;
+use crate::ast::task::{Async, Await};
+use crate::ast::type_node::Type;
+use crate::ast::*;
+use crate::lexer::token;
+use super::parser::{Parser, ParseError, Tokens};
+use crate::Error;
+use std::rc::Rc;
+
+impl Parser {
+    /// `async fn ...`, `async { block }` or `async expr`, starting at `async`.
+    pub fn parse_async_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
+        let rest = tokens.slice(1..);
+        if let Err(e) = self.check_bounds(&rest, 0) {
+            return (Err(e), 1);
+        }
+        match rest[0] {
+            token::Token::Function(token::Function::Fn) => {
+                let length = match self.find_expr_possible_boundary(&rest, true, true, true) {
+                    Ok(length) => length,
+                    Err(e) => return (Err(e), 1),
+                };
+                let (declaration, pos) = match self.parse_fn_parts(rest.slice(..length)) {
+                    (Ok(declaration), pos) => (declaration, pos),
+                    (Err(e), pos) => return (Err(e), pos + 1),
+                };
+                if let Some(param) = declaration.references.first() {
+                    return (Err(Error::ParserError(ParseError::AsyncReference(param.clone()))), pos + 1);
+                }
+                // calling an async fn returns a task; the declared type is checked when it finishes
+                let body = Async { body: Rc::unwrap_or_clone(declaration.body), return_type: declaration.return_type.clone() };
+                let declaration = FnDeclaration {
+                    name: declaration.name,
+                    params: declaration.params,
+                    references: declaration.references,
+                    return_type: Some(Type::Task(Box::new(declaration.return_type.unwrap_or(Type::Any)))),
+                    body: Rc::new(Scope::new(vec![Node::new(body)])),
+                };
+                (Ok(Node::new(declaration)), pos + 1)
+            }
+            token::Token::Bracket(token::Bracket::OpenBrace) => {
+                let end = match self.find_matching_bracket(&rest, 0) {
+                    Ok(end) => end,
+                    Err(e) => return (Err(e), 1),
+                };
+                match self.parse_block_or_expr(rest.slice(..end + 1)) {
+                    (Ok(body), pos) => (Ok(Node::new(Async { body, return_type: None })), pos + 1),
+                    (Err(e), pos) => (Err(e), pos + 1),
+                }
+            }
+            _ => match self.parse_block_or_expr(rest) {
+                (Ok(body), pos) => (Ok(Node::new(Async { body, return_type: None })), pos + 1),
+                (Err(e), pos) => (Err(e), pos + 1),
+            },
+        }
+    }
+
+    pub fn parse_await_expr(&mut self, tokens: Tokens) -> (Result<Rc<Node>, Error>, usize) {
+        match self.parse_expr(tokens.slice(1..)) {
+            (Ok(value), pos) => (Ok(Node::new(Await { value })), pos + 1),
+            (Err(e), pos) => (Err(e), pos + 1),
+        }
+    }
+}
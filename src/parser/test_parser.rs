@@ -1,5 +1,9 @@
 #[cfg(test)]
 mod test_parser {
+    use crate::ast::{ASTNode, Node, NodeKind};
+    use crate::ast::tuple::Clonable;
+    use crate::environment::frame::Address;
+    use std::rc::Rc;
     use crate::parser::parser::*;
     use crate::lexer::*;
 
@@ -10,7 +14,7 @@ mod test_parser {
         let mut parser = Parser::new(lexer.tokens.clone());
         let ast = parser.parse().unwrap();
         println!("{}", ast.to_string());
-        let ast = ast.children()[0].clone_to_node();
+        let ast = ast.children()[0].clone();
         assert_eq!(ast.element(), "Add");
         assert_eq!(ast.children()[0].element(), "Int(1)");
         assert_eq!(ast.children()[1].element(), "Mul");
@@ -26,7 +30,7 @@ mod test_parser {
         let mut parser = Parser::new(lexer.tokens.clone());
         let ast = parser.parse().unwrap();
         println!("{}", ast.to_string());
-        let ast = ast.children()[0].clone_to_node();
+        let ast = ast.children()[0].clone();
         assert_eq!(ast.element(), "And");
         assert_eq!(ast.children()[0].element(), "Bool(true)");
         assert_eq!(ast.children()[1].element(), "Bool(false)");
@@ -42,7 +46,7 @@ mod test_parser {
             panic!("Error parsing expression");
         });
         println!("{}", ast.to_string());
-        let ast = ast.children()[0].clone_to_node();
+        let ast = ast.children()[0].clone();
         assert_eq!(ast.element(), "Or");
         assert_eq!(ast.children()[0].element(), "Gt");
         assert_eq!(ast.children()[0].children()[0].element(), "Float(32.5)");
@@ -60,7 +64,7 @@ mod test_parser {
         let mut parser = Parser::new(lexer.tokens.clone());
         let ast = parser.parse().unwrap();
         println!("{}", ast.to_string());
-        let ast = ast.children()[0].clone_to_node();
+        let ast = ast.children()[0].clone();
         assert_eq!(ast.element(), "Mul");
         assert_eq!(ast.children()[0].element(), "Add");
         assert_eq!(ast.children()[0].children()[0].element(), "Int(1)");
@@ -76,7 +80,7 @@ mod test_parser {
         let mut parser = Parser::new(lexer.tokens.clone());
         let ast = parser.parse().unwrap();
             println!("{}", ast.to_string());
-        let ast = ast.children()[0].clone_to_node();
+        let ast = ast.children()[0].clone();
         assert_eq!(ast.element(), "mut \"a\" : I32 =");
         assert_eq!(ast.children()[0].element(), "Int(1)");
     }
@@ -89,11 +93,171 @@ mod test_parser {
         let mut parser = Parser::new(lexer.tokens.clone());
         let ast = parser.parse().unwrap();
         println!("{}", ast.to_string());
-        let ast = ast.children()[0].clone_to_node();
+        let ast = ast.children()[0].clone();
         assert_eq!(ast.element(), "mut \"a\" : [inferred] =");
         assert_eq!(ast.children()[0].element(), "Int(1)");
     }
 
+    #[test]
+    fn test_parse_imports() {
+        let mut lexer = Lexer::new("import 'std:math' as m\nimport {\n'std:os'\n'std:geometry'\n}\nfrom 'std:math' import sqrt, floor".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        println!("{}", ast.to_string());
+        let children = ast.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].element(), "import 'std:math' as m");
+        assert_eq!(children[1].children()[0].element(), "import 'std:os'");
+        assert_eq!(children[1].children()[1].element(), "import 'std:geometry'");
+        assert_eq!(children[2].element(), "from 'std:math' import sqrt, floor");
+    }
+
+    #[test]
+    fn test_parse_qualified_fn_call() {
+        let mut lexer = Lexer::new("math.sqrt(x: 2)".to_string());
+        lexer.tokenize().unwrap();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        assert_eq!(ast.children()[0].element(), "math.sqrt (x)");
+    }
+
+    #[test]
+    fn test_parse_optional_operators() {
+        let mut lexer = Lexer::new("f(x)? + a! ?? 0\nlet b := c?\nb".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].element(), "??");
+        let sum = children[0].children()[0].clone();
+        assert_eq!(sum.element(), "Add");
+        assert_eq!(sum.children()[0].element(), "?");
+        assert_eq!(sum.children()[0].children()[0].element(), "f (<>)");
+        assert_eq!(sum.children()[1].element(), "!");
+        assert_eq!(children[0].children()[1].element(), "Int(0)");
+    }
+
+    #[test]
+    fn test_parse_error_and_except() {
+        let mut lexer = Lexer::new("error NotFound('x')\nlet y := f(a) except {\nNotFound(e) => 0\nerror => 1\n}\ny".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].element(), "error NotFound");
+        assert_eq!(children[1].children()[0].element(), "except NotFound, error");
+    }
+
+    #[test]
+    fn test_parse_async_and_await() {
+        let mut lexer = Lexer::new("async fn f(x: i64) -> i64 { x }\nlet t := async { f(1) }\nawait t + 1".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].element(), "fn f (x: I64) -> Some(Task(I64))");
+        assert_eq!(children[0].children()[0].element(), "async");
+        assert_eq!(children[1].children()[0].element(), "async");
+        assert_eq!(children[2].element(), "Add");
+        assert_eq!(children[2].children()[0].element(), "await");
+    }
+
+    #[test]
+    fn test_parse_reference_params() {
+        let mut lexer = Lexer::new("fn swap(mut a: i32, mut b: i32, c: i32) { a }\nswap(mut x, b: mut y, 3)".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let ast = Parser::new(lexer.tokens.clone()).parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children[0].element(), "fn swap (mut a: I32, mut b: I32, c: I32) -> None");
+        let NodeKind::FnCall(call) = &children[1].kind else { panic!("expected a call") };
+        assert!(matches!(&call.params[0].1.kind, NodeKind::Borrow(borrow) if borrow.name == "x"));
+        assert_eq!(call.params[1].0, Some("b".to_string()));
+        assert_eq!(call.params[1].1.element(), "mut y");
+        assert_eq!(call.params[2].1.element(), "Int(3)");
+
+        let mut lexer = Lexer::new("async fn f(mut x: i64) { x }".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        assert!(matches!(
+            Parser::new(lexer.tokens.clone()).parse(),
+            Err(crate::Error::ParserError(ParseError::AsyncReference(param))) if param == "x"
+        ));
+    }
+
+    #[test]
+    fn test_parse_method_call_on_expression() {
+        let mut lexer = Lexer::new("xs[0].replace(old: 'a', new: 'b').len() + f().len()".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let ast = Parser::new(lexer.tokens.clone()).parse().unwrap();
+        let sum = ast.children()[0].clone();
+        assert_eq!(sum.element(), "Add");
+        let NodeKind::MethodCall(len) = &sum.children()[0].kind else { panic!("expected a method call") };
+        assert_eq!(len.method, "len");
+        let NodeKind::MethodCall(replace) = &len.receiver.kind else { panic!("expected a method call") };
+        assert_eq!(replace.method, "replace");
+        assert_eq!(replace.params[1].0, Some("new".to_string()));
+        assert!(matches!(&replace.receiver.kind, NodeKind::Index(_)));
+        let NodeKind::MethodCall(len) = &sum.children()[1].kind else { panic!("expected a method call") };
+        assert!(matches!(&len.receiver.kind, NodeKind::FnCall(call) if call.name == "f"));
+    }
+
+    #[test]
+    fn test_nodes_share_subtrees() {
+        let mut lexer = Lexer::new("fn f(a: i32) -> i32 { a + 1 }\nf(2) + f(3)".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let ast = Parser::new(lexer.tokens.clone()).parse().unwrap();
+        let children = ast.children();
+        assert!(Rc::ptr_eq(&children[0], &ast.children()[0]));
+
+        let NodeKind::FnDeclaration(declaration) = &children[0].kind else { panic!("expected a declaration") };
+        let copy = declaration.clone_element();
+        assert!(Rc::ptr_eq(&copy.body, &declaration.body));
+        assert!(Rc::ptr_eq(&copy.children()[0], &declaration.children()[0]));
+
+        let NodeKind::BinaryOperation(sum) = &children[1].kind else { panic!("expected a sum") };
+        let operands = children[1].children();
+        assert!(Rc::ptr_eq(&operands[0], &sum.left));
+        assert!(Rc::ptr_eq(&operands[1], &sum.right));
+        assert!(Rc::ptr_eq(&operands[0].children()[0], &sum.left.children()[0]));
+    }
+
+    #[test]
+    fn test_mark_tail_calls() {
+        let source = "fn f(n: i32) -> i32 {\n    g(n)\n    if n > 0 { f(n - 1) } elif n < 0 { h(n) + 1 } else { g(n) }\n}\nf(3)";
+        let mut lexer = Lexer::new(source.to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let ast = Parser::new(lexer.tokens.clone()).parse().unwrap();
+        fn calls(node: &Rc<Node>, found: &mut Vec<(String, bool)>) {
+            if let NodeKind::FnCall(call) = &node.kind {
+                found.push((call.name.clone(), call.tail.get()));
+            }
+            for child in node.children() {
+                calls(&child, found);
+            }
+            match &node.kind {
+                NodeKind::IfBlock(block) => block.next_conditional.iter().for_each(|next| calls(next, found)),
+                NodeKind::ElifBlock(block) => block.next_conditional.iter().for_each(|next| calls(next, found)),
+                _ => {}
+            }
+        }
+        let mut found = Vec::new();
+        calls(&ast, &mut found);
+        let expected = [("g", false), ("f", true), ("h", false), ("g", true), ("f", false)];
+        assert_eq!(found, expected.map(|(name, tail)| (name.to_string(), tail)));
+    }
+
     #[test]
     fn test_parse_if_else() {
         let mut lexer = Lexer::new("if a > 10 { a } else { b }".to_string());
@@ -103,4 +267,161 @@ mod test_parser {
         println!("{}", ast.to_string());
     }
     
+
+    #[test]
+    fn test_parse_casts() {
+        let mut lexer = Lexer::new("(a + 1) as u16 * 2\nlet b := f(x) as! u8\nb as string".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].element(), "Mul");
+        let cast = children[0].children()[0].clone();
+        assert_eq!(cast.element(), "as U16");
+        assert_eq!(cast.children()[0].element(), "Add");
+        assert_eq!(children[1].children()[0].element(), "as! U8");
+        assert_eq!(children[1].children()[0].children()[0].element(), "f (<>)");
+        assert_eq!(children[2].element(), "as String");
+    }
+
+    #[test]
+    fn test_parse_lists() {
+        let mut lexer = Lexer::new("[1, -2][0] + 1\nxs[1:]\nlet found := 3 in xs[:-1]\nfn f(xs: [i32]) -> [i32]? { xs }".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children.len(), 4);
+        assert_eq!(children[0].element(), "Add");
+        let index = children[0].children()[0].clone();
+        assert_eq!(index.element(), "index");
+        assert_eq!(index.children()[0].element(), "[]");
+        assert_eq!(index.children()[0].children()[1].element(), "Int(-2)");
+        assert_eq!(children[1].element(), "slice");
+        assert_eq!(children[1].children().len(), 2);
+        assert_eq!(children[2].children()[0].element(), "in");
+        assert_eq!(children[2].children()[0].children()[1].element(), "slice");
+        assert_eq!(children[3].element(), "fn f (xs: List(I32)) -> Some(Option(List(I32)))");
+    }
+
+    #[test]
+    fn test_parse_maps() {
+        let mut lexer = Lexer::new("let m: {string: [i32]} = {\n'a': [1],\n'b': [2, 3]\n}\nfn keys(m: {string: i32}) -> {string: bool}? { none }\n{'x': 1}['x'] + 1".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children.len(), 3);
+        let map = children[0].children()[0].clone();
+        assert_eq!(map.element(), "{}");
+        assert_eq!(map.children().len(), 4);
+        assert_eq!(map.children()[3].element(), "[]");
+        assert_eq!(children[1].element(), "fn keys (m: Map(String, I32)) -> Some(Option(Map(String, Bool)))");
+        assert_eq!(children[2].element(), "Add");
+        assert_eq!(children[2].children()[0].element(), "index");
+    }
+
+    #[test]
+    fn test_parse_tuple_access() {
+        let mut lexer = Lexer::new("t.1.0 + 1\np.0 := pair().1\nfn f((a, b): (i32, i32), c: i32) { a }".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].element(), "Add");
+        let access = children[0].children()[0].clone();
+        assert_eq!(access.element(), ".0");
+        assert_eq!(access.children()[0].element(), ".1");
+        assert_eq!(children[1].element(), "p.0 :=");
+        assert_eq!(children[1].children()[0].element(), ".1");
+        assert_eq!(children[2].element(), "fn f ((a, b): Tuple([I32, I32]), c: I32) -> None");
+        // the tuple param is destructured first
+        assert_eq!(children[2].children()[0].element(), "let (\"a\", \"b\") : Tuple([I32, I32]) =");
+    }
+
+    #[test]
+    fn test_parse_compound_assignment() {
+        let mut lexer = Lexer::new("i += 1 + 2\np.1 -= 1\n(a, b) *= (2, 3)".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].element(), "\"i\" Add=");
+        assert_eq!(children[0].children()[0].element(), "Add");
+        assert_eq!(children[1].element(), "p.1 Sub=");
+        assert_eq!(children[2].element(), "(\"a\", \"b\") Mul=");
+    }
+
+    #[test]
+    fn test_parse_to_node_enum() {
+        let mut lexer = Lexer::new("1 + x\nfn f() { 1 }".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let mut parser = Parser::new(lexer.tokens.clone());
+        let ast = parser.parse().unwrap();
+        let children = ast.children();
+        match &children[0].kind {
+            NodeKind::BinaryOperation(operation) => {
+                assert!(matches!(&operation.left.kind, NodeKind::Literal(_)));
+                assert!(matches!(&operation.right.kind, NodeKind::Identifier(_)));
+                // children are shared with the tree, not copied out of it
+                assert!(Rc::ptr_eq(&operation.left, &children[0].children()[0]));
+            }
+            node => panic!("expected a binary operation, got {:?}", node),
+        }
+        assert!(matches!(&children[1].kind, NodeKind::FnDeclaration(_)));
+        assert!(Rc::ptr_eq(&children[1], &ast.children()[1]));
+    }
+
+    #[test]
+    fn test_resolve_block_variables() {
+        let source = "let g := 1\nfn f(a: i32) -> i32 {\n    let b := a\n    if b > g {\n        let c := b\n        c + a\n    }\n}";
+        let mut lexer = Lexer::new(source.to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let ast = Parser::new(lexer.tokens.clone()).parse().unwrap();
+        let address = |node: &Rc<Node>| match &node.kind {
+            NodeKind::Identifier(identifier) => identifier.address.get(),
+            node => panic!("expected an identifier, got {:?}", node),
+        };
+        let body = match &ast.children()[1].kind {
+            NodeKind::FnDeclaration(declaration) => declaration.body.nodes().to_vec(),
+            node => panic!("expected a function, got {:?}", node),
+        };
+        // `a` is a param, one frame out from the body
+        assert_eq!(address(&body[0].children()[0]), Some(Address::Local { depth: 1, slot: 0 }));
+        let (condition, content) = match &body[1].kind {
+            NodeKind::IfBlock(block) => (block.condition.clone(), block.content.nodes().to_vec()),
+            node => panic!("expected an if block, got {:?}", node),
+        };
+        assert_eq!(address(&condition.children()[0]), Some(Address::Local { depth: 0, slot: 0 }));
+        // globals are looked for in the outermost frame
+        assert_eq!(address(&condition.children()[1]), Some(Address::Global));
+        assert_eq!(address(&content[0].children()[0]), Some(Address::Local { depth: 1, slot: 0 }));
+        let sum = content[1].children();
+        assert_eq!(address(&sum[0]), Some(Address::Local { depth: 0, slot: 0 }));
+        assert_eq!(address(&sum[1]), Some(Address::Local { depth: 2, slot: 0 }));
+
+        // a function calling itself finds itself without going through its callers
+        let mut lexer = Lexer::new("fn down(n: i32) -> i32 { down(n: n - 1) }".to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        let ast = Parser::new(lexer.tokens.clone()).parse().unwrap();
+        let body = match &ast.children()[0].kind {
+            NodeKind::FnDeclaration(declaration) => declaration.body.nodes().to_vec(),
+            node => panic!("expected a function, got {:?}", node),
+        };
+        match &body[0].kind {
+            NodeKind::FnCall(call) => assert_eq!(call.address.get(), Some(Address::Global)),
+            node => panic!("expected a call, got {:?}", node),
+        }
+    }
 }
@@ -1,13 +1,13 @@
 use crate::{ast::tuple::{Clonable, Tuple, TupleError}, lexer::token};
-use super::parser::Parser;
+use super::parser::{Parser, Tokens};
 use crate::Error;
 
 impl Parser {
-    pub fn parse_tuple<K: Clonable>(&mut self, tuple: Tuple<Vec<token::Token>>, parse_expr: fn(&mut Self, &[token::Token]) -> Result<K, Error>) -> Result<Tuple<K>, Error> {
+    pub fn parse_tuple<K: Clonable>(&mut self, tuple: Tuple<Tokens>, parse_expr: fn(&mut Self, Tokens) -> Result<K, Error>) -> Result<Tuple<K>, Error> {
         match tuple {
             Tuple::Empty => return Ok(Tuple::Empty),
             Tuple::Element(tokens) => {
-                let node = parse_expr(self, &tokens)?;
+                let node = parse_expr(self, tokens)?;
                 return Ok(Tuple::Element(node));
             }
             Tuple::List(elements) => {
@@ -21,7 +21,7 @@ impl Parser {
     }
 
 
-    pub fn is_identifier_tuple(&mut self, tuple: Tuple<Vec<token::Token>>) -> bool {
+    pub fn is_identifier_tuple(&mut self, tuple: Tuple<Tokens>) -> bool {
         match tuple {
             Tuple::Empty => return false,
             Tuple::Element(tokens) => {
@@ -41,7 +41,7 @@ impl Parser {
         }
     }
 
-    pub fn make_tuple(&mut self, tokens: &[token::Token]) -> Result<Tuple<Vec<token::Token>>, Error> {
+    pub fn make_tuple<'a>(&mut self, tokens: Tokens<'a>) -> Result<Tuple<Tokens<'a>>, Error> {
         // note this function expects the entire token list to be the tuple
 
         if tokens.is_empty() {
@@ -57,16 +57,16 @@ impl Parser {
         if next_comma.is_none() {
             /*
             if tokens.len() >= 2 && tokens[0] == token::Token::Bracket(token::Bracket::OpenParen) && tokens[tokens.len()-1] == token::Token::Bracket(token::Bracket::CloseParen) {
-                let inner_tuple = self.make_tuple(&tokens[1..tokens.len()-1])?;
+                let inner_tuple = self.make_tuple(tokens.slice(1..tokens.len()-1))?;
                 return Ok(Tuple::List(vec![inner_tuple]));
             } else {
-                return Ok(Tuple::Element(tokens.to_vec()));
+                return Ok(Tuple::Element(tokens));
             }
             */
-            return Ok(Tuple::Element(tokens.to_vec()));
+            return Ok(Tuple::Element(tokens));
         }
 
-        let mut tuple = vec![tokens[..next_comma.unwrap()].to_vec()];
+        let mut tuple = vec![tokens.slice(..next_comma.unwrap())];
         let mut cursor = next_comma.unwrap() + 1;
         while cursor < tokens.len() {
             let next_comma = match self.find_first_token_skip_brackets(&token::Token::Punctuation(token::Punctuation::Comma), &tokens[cursor..]) {
@@ -75,11 +75,11 @@ impl Parser {
             };
 
             if next_comma.is_none() {
-                tuple.push(tokens[cursor..].to_vec());
+                tuple.push(tokens.slice(cursor..));
                 break;
             }
 
-            tuple.push(tokens[cursor..next_comma.unwrap()+cursor].to_vec());
+            tuple.push(tokens.slice(cursor..next_comma.unwrap()+cursor));
             cursor += next_comma.unwrap() + 1;
         }
 
@@ -88,15 +88,15 @@ impl Parser {
                 return Ok(Tuple::Empty);
             }
             1 => {
-                return Ok(Tuple::Element(tuple[0].to_vec()));
+                return Ok(Tuple::Element(tuple[0]));
             }
             _ => {
                 let mut out_tuple = vec![];
                 for t in tuple.iter() {
                     if t.len() >= 2 && t.first().unwrap() == &token::Token::Bracket(token::Bracket::OpenParen) && t.last().unwrap() == &token::Token::Bracket(token::Bracket::CloseParen) {
-                        out_tuple.push(self.make_tuple(&t[1..t.len()-1])?);
+                        out_tuple.push(self.make_tuple(t.slice(1..t.len()-1))?);
                     } else {
-                        out_tuple.push(Tuple::Element(t.to_vec()));
+                        out_tuple.push(Tuple::Element(*t));
                     }
                 }
                 return Ok(Tuple::List(out_tuple));
@@ -104,18 +104,18 @@ impl Parser {
         }
     }
 
-    pub fn make_left_matching_tuple<T: Clonable>(&mut self, tokens: &[token::Token], structure: Tuple<T>) -> (Result<Tuple<Vec<token::Token>>, Error>, usize) {
+    pub fn make_left_matching_tuple<'a, T: Clonable>(&mut self, tokens: Tokens<'a>, structure: Tuple<T>) -> (Result<Tuple<Tokens<'a>>, Error>, usize) {
         let expr_lim = match self.find_expr_possible_boundary(&tokens, false, false, false) {
             Ok(length) => length,
             Err(e) => return (Err(e), 0)
         };
         let tokens = if expr_lim >= tokens.len() {
-            &tokens
+            tokens
         } else {
-            &tokens[..expr_lim]
+            tokens.slice(..expr_lim)
         };
 
-        let tuple = match self.make_tuple(&tokens) {
+        let tuple = match self.make_tuple(tokens) {
             Ok(tuple) => tuple,
             Err(e) => return (Err(e), expr_lim+1)
         };
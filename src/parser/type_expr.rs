@@ -1,15 +1,13 @@
-use crate::parser::parser::{Parser, ParseError};
+use crate::parser::parser::{Parser, ParseError, Tokens};
 use crate::lexer::token;
 use crate::ast::type_node::Type;
 use crate::Error;
 
 
 impl Parser {
-    pub fn parse_type_expr(&mut self, tokens: &[token::Token]) -> (Result<Type, Error>, usize) {
-        let tokens = tokens.to_vec();
-
+    pub fn parse_type_expr(&mut self, tokens: Tokens) -> (Result<Type, Error>, usize) {
         if let token::Token::Function(token::Function::Fn) = tokens[0] {
-            let ((signature, name), new_pos) = match self.parse_fn_signature(&tokens) {
+            let ((signature, name, _), new_pos) = match self.parse_fn_signature(tokens) {
                 (Ok(k), new_pos) => (k, new_pos),
                 (Err(e), new_pos) => return (Err(e), new_pos),
             };
@@ -19,13 +17,41 @@ impl Parser {
             return (Ok(Type::FnType(Box::new(signature))), new_pos);
         }
 
+        if let token::Token::Bracket(bracket @ (token::Bracket::OpenBracket | token::Bracket::OpenBrace)) = tokens[0].clone() {
+            let end = match self.find_matching_bracket(&tokens, 0) {
+                Ok(end) => end,
+                Err(e) => return (Err(e), 0),
+            };
+            let collection = match bracket {
+                token::Bracket::OpenBracket => self.parse_type_expr(tokens.slice(1..end)).0.map(|element| Type::List(Box::new(element))),
+                _ => self.parse_map_type(tokens.slice(1..end)),
+            };
+            let collection = match collection {
+                Ok(collection) => collection,
+                Err(e) => return (Err(e), end),
+            };
+            return match tokens.get(end + 1) {
+                Some(token::Token::Punctuation(token::Punctuation::Question)) => (Ok(Type::Option(Box::new(collection))), end + 2),
+                _ => (Ok(collection), end + 1),
+            };
+        }
+
+        if tokens[0] == token::Token::Bracket(token::Bracket::OpenParen) {
+            if let Ok(end) = self.find_matching_bracket(&tokens, 0) {
+                // a whole parenthesized type, such as a tuple whose first element is a tuple
+                if tokens.get(end + 1) != Some(&token::Token::Punctuation(token::Punctuation::Comma)) {
+                    return (self.parse_type_expr(tokens.slice(1..end)).0, end + 1);
+                }
+            }
+        }
+
         let max_expr_length= match self.find_expr_possible_boundary(&tokens, false, false, false) {
             Ok(length) => length,
             Err(e) => return (Err(e), 0)
         };
-        let tokens = &tokens[..max_expr_length];
+        let tokens = tokens.slice(..max_expr_length);
 
-        if let Ok(Some(_)) = self.find_first_token_skip_brackets(&token::Token::Punctuation(token::Punctuation::Comma), tokens) {
+        if let Ok(Some(_)) = self.find_first_token_skip_brackets(&token::Token::Punctuation(token::Punctuation::Comma), &tokens) {
             let tuple = match self.make_tuple(tokens) {
                 Ok(t) => t,
                 Err(e) => return (Err(e), max_expr_length)
@@ -43,8 +69,8 @@ impl Parser {
 
             return (Ok(tuple), max_expr_length)
         } else {
-            if self.is_in_parenthesis(tokens) {
-                return match self.parse_type_expr(&tokens[1..tokens.len()-1]).0 {
+            if self.is_in_parenthesis(&tokens) {
+                return match self.parse_type_expr(tokens.slice(1..tokens.len()-1)).0 {
                     Ok(t) => (Ok(Type::Tuple(vec![t])), max_expr_length),
                     Err(e) => (Err(e), max_expr_length)
                 };
@@ -57,4 +83,17 @@ impl Parser {
             }
         }
     }
+
+    /// Parses the `K: V` between the braces of a map type.
+    fn parse_map_type(&mut self, tokens: Tokens) -> Result<Type, Error> {
+        let colon = token::Token::Punctuation(token::Punctuation::Colon);
+        match self.find_first_token_skip_brackets(&colon, &tokens)? {
+            Some(pos) if pos > 0 && pos + 1 < tokens.len() => {
+                let key = self.parse_type_expr(tokens.slice(..pos)).0?;
+                let value = self.parse_type_expr(tokens.slice(pos + 1..)).0?;
+                Ok(Type::Map(Box::new(key), Box::new(value)))
+            }
+            _ => Err(Error::ParserError(ParseError::ExpectedMapEntry)),
+        }
+    }
 }
\ No newline at end of file
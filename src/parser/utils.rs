@@ -71,6 +71,14 @@ impl Parser {
                 while brace_open_counter > 0 && cursor < tokens.len() {
                     match tokens[cursor] {
                         token::Token::Bracket(token::Bracket::OpenBrace) => brace_open_counter -= 1,
+                        // params and bracketed expressions or types hold no block
+                        token::Token::Bracket(token::Bracket::OpenParen | token::Bracket::OpenBracket) => {
+                            cursor = self.find_matching_bracket(tokens, cursor)?;
+                        }
+                        // the braces of a map return type
+                        token::Token::Function(token::Function::Arrow) if matches!(tokens.get(cursor + 1), Some(token::Token::Bracket(token::Bracket::OpenBrace))) => {
+                            cursor = self.find_matching_bracket(tokens, cursor + 1)?;
+                        }
                         token::Token::Conditional(_) 
                         | token::Token::Loop(token::Loop::For) 
                         | token::Token::Loop(token::Loop::Loop) 
@@ -93,6 +101,26 @@ impl Parser {
         Err(Error::ParserError(ParseError::NoMatchingBraceForKeyword(keyword)))
     }
 
+    pub fn parse_qualified_name(&self, tokens: &[token::Token]) -> Option<(String, usize)> {
+        // reads `name` or `module.name` and returns the joined name with the number of tokens used
+        let mut name = match tokens.first() {
+            Some(token::Token::Identifier(name)) => name.clone(),
+            _ => return None,
+        };
+        let mut cursor = 1;
+        while cursor + 1 < tokens.len() && tokens[cursor] == token::Token::Punctuation(token::Punctuation::Dot) {
+            match &tokens[cursor + 1] {
+                token::Token::Identifier(member) => {
+                    name.push('.');
+                    name.push_str(member);
+                    cursor += 2;
+                }
+                _ => break,
+            }
+        }
+        Some((name, cursor))
+    }
+
     pub fn find_next_non_whitespace_token(&mut self, tokens: &[token::Token]) -> Option<token::Token> {
         for token in tokens {
             if !matches!(token, token::Token::Whitespace(_)) {
@@ -102,11 +130,32 @@ impl Parser {
         None
     }
 
-    pub fn is_type_expr(&self, tokens: &[token::Token]) -> bool {
-        if tokens.len() > 0 && matches!(tokens[0], token::Token::Type(_)) {
-            return true;
+    /// Whether the expression goes on with `tokens` after an operand.
+    fn operand_continues(&mut self, tokens: &[token::Token]) -> bool {
+        if self.starts_new_block(tokens) {
+            return false;
         }
-        false
+        matches!(
+            self.find_next_non_whitespace_token(tokens),
+            None | Some(token::Token::Operator(_)
+            | token::Token::Punctuation(_)
+            | token::Token::Bracket(_)
+            | token::Token::Error(token::Error::Except)
+            | token::Token::Module(token::Module::As)
+            | token::Token::Loop(token::Loop::In))
+        )
+    }
+
+    fn starts_new_block(&mut self, tokens: &[token::Token]) -> bool {
+        // a bracket on the next line opens a new expression rather than calling or indexing
+        matches!(tokens.first(), Some(token::Token::Whitespace(token::Whitespace::Newline)))
+            && matches!(self.find_next_non_whitespace_token(tokens), Some(token::Token::Bracket(token::Bracket::OpenBrace | token::Bracket::OpenParen | token::Bracket::OpenBracket)))
+    }
+
+    pub fn is_type_expr(&self, tokens: &[token::Token]) -> bool {
+        // `((i32, i32), string)` is a type too
+        let first = tokens.iter().find(|token| **token != token::Token::Bracket(token::Bracket::OpenParen));
+        matches!(first, Some(token::Token::Type(_)))
     }
 
     pub fn is_in_parenthesis(&self, tokens: &[token::Token]) -> bool {
@@ -132,11 +181,18 @@ impl Parser {
         let mut cursor = 0;
         while cursor < tokens.len() {
             match tokens[cursor].clone() {
+                // `value in list`
+                token::Token::Loop(token::Loop::In) => {
+                    cursor += 1;
+                }
                 token::Token::Loop(_) => if !loop_mode {
                     return Ok(cursor);
                 } else {
                     cursor += 1;
                 },
+                token::Token::Function(token::Function::Async | token::Function::Await) => {
+                    cursor += 1;
+                }
                 token::Token::Function(f) => {
                     if !fn_mode {
                         return Ok(cursor);
@@ -161,6 +217,19 @@ impl Parser {
                                             complete = true;
                                         }
                                     }
+                                    token::Token::Bracket(token::Bracket::OpenBracket | token::Bracket::OpenBrace) if passed_arrow => {
+                                        // a list or map return type, optionally `[T]?`
+                                        cursor = match self.find_matching_bracket(tokens, cursor) {
+                                            Ok(new_pos) => new_pos + 1,
+                                            Err(_) => {
+                                                return Err(Error::ParserError(ParseError::NoMatchingBracket));
+                                            }
+                                        };
+                                        if matches!(tokens.get(cursor), Some(token::Token::Punctuation(token::Punctuation::Question))) {
+                                            cursor += 1;
+                                        }
+                                        complete = true;
+                                    }
                                     token::Token::Function(token::Function::Arrow) => {
                                         passed_arrow = true;
                                         cursor += 1;
@@ -179,19 +248,12 @@ impl Parser {
                                 }
                             }
                             if cursor < tokens.len() && matches!(tokens[cursor], token::Token::Bracket(token::Bracket::OpenBrace)) {
-                                println!("cursor: {}", cursor);
-                                println!("tokens: {:?}", tokens);
                                 cursor = match self.find_matching_bracket(&tokens, cursor) {
                                     Ok(new_pos) => new_pos + 1,
                                     Err(_) => {
                                         return Err(Error::ParserError(ParseError::NoMatchingBracket));
                                     }
                                 };
-                                println!("new cursor: {}", cursor);
-                            } else {
-                                println!("no open brace");
-                                println!("cursor: {}", cursor);
-                                println!("tokens: {:?}", tokens);
                             }
                             return Ok(cursor);
                         }
@@ -200,6 +262,14 @@ impl Parser {
                         }
                     }
                 }
+                token::Token::Module(token::Module::As) if self.cast_length(&tokens[cursor..]).is_some() => {
+                    // the cast's type ends the operand like a literal would
+                    cursor += self.cast_length(&tokens[cursor..]).unwrap_or(1) - 1;
+                    if !self.operand_continues(&tokens[cursor + 1..]) {
+                        return Ok(cursor + 1);
+                    }
+                    cursor += 1;
+                }
                 token::Token::TypeDeclaration(_)
                 | token::Token::Module(_)
                 | token::Token::Debug
@@ -245,22 +315,14 @@ impl Parser {
                     }
                     cursor += 1;
                 },
-                token::Token::Literal(_) | token::Token::Identifier(_) => {
-                    match self.find_next_non_whitespace_token(&tokens[cursor+1..]) {
-                        Some(tok) => match tok {
-                            token::Token::Operator(_) 
-                            | token::Token::Punctuation(_)
-                            | token::Token::Bracket(_) => {
-                                cursor += 1;
-                            },
-                            _ => {
-                                return Ok(cursor+1);
-                            }
-                        },
-                        _ => {
-                            cursor += 1;
-                        }
+                token::Token::Literal(_)
+                | token::Token::Identifier(_)
+                | token::Token::Punctuation(token::Punctuation::Question)
+                | token::Token::Punctuation(token::Punctuation::Exclamation) => {
+                    if !self.operand_continues(&tokens[cursor+1..]) {
+                        return Ok(cursor+1);
                     }
+                    cursor += 1;
                 },
                 token::Token::Bracket(_) => {
                     cursor = match self.find_matching_bracket(&tokens, cursor) {
@@ -268,6 +330,20 @@ impl Parser {
                         Err(_) => {
                             return Ok(cursor); // If we can't find a matching bracket, we stop here
                         }
+                    };
+                    if self.starts_new_block(&tokens[cursor..]) {
+                        return Ok(cursor);
+                    }
+                    match self.find_next_non_whitespace_token(&tokens[cursor..]) {
+                        Some(token::Token::Operator(_))
+                        | Some(token::Token::Punctuation(_))
+                        | Some(token::Token::Bracket(_))
+                        | Some(token::Token::Conditional(_))
+                        | Some(token::Token::Error(token::Error::Except))
+                        | Some(token::Token::Module(token::Module::As))
+                        | Some(token::Token::Loop(token::Loop::In))
+                        | None => {},
+                        Some(_) => return Ok(cursor),
                     }
                 },
                 token::Token::Operator(token::Operator::EqualSign)
@@ -1,13 +1,13 @@
-use crate::ast::Value;
-use crate::{environment::heap::Heap, lexer::Lexer};
+use crate::{environment::heap::Heap, lexer::{Lexer, Span}};
 use crate::parser::parser::*;
 use crate::environment::environment::Environment;
 use crate::environment::heap::VariableHeap;
-use crate::ast::function::FnDeclaration;
-use crate::ast::type_node::Type;
-use crate::ast::scope::Scope;
-use std::collections::HashMap;
-use crate::ast::literal::Literal;
+use crate::diagnostics::diagnostic::report;
+use crate::package::Project;
+use crate::stdlib;
+use crate::checker::checker;
+use crate::Error;
+use crate::ast::ASTNode;
 use std::rc::Rc;
 use std::cell::RefCell;
     use colored::Colorize;
@@ -18,17 +18,6 @@ use nu_ansi_term::{Color, Style};
 
 struct LoopHighlighter;
 
-fn print_fn() -> FnDeclaration {
-    FnDeclaration {
-        name: Some("print".to_string()),
-        params: HashMap::from([("value".to_string(), Type::Any)]),
-        return_type: None,
-        body: Scope::new(vec![
-            Box::new(Literal(Value::String("{value}".to_string(), false))),
-        ]),
-    }
-}
-
 impl Highlighter for LoopHighlighter {
     fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
         let mut styled_parts: Vec<(Style, String)> = Vec::new();
@@ -76,7 +65,7 @@ impl Highlighter for LoopHighlighter {
     }
 }
 
-pub fn repl(print_ast: bool, print_tokens: bool) {
+pub fn repl(print_ast: bool, print_tokens: bool, strict_float: bool, bytecode: bool, max_depth: usize, color: bool) {
     let mut keybindings = default_emacs_keybindings();
     keybindings.add_binding(
         KeyModifiers::SHIFT,
@@ -94,8 +83,12 @@ pub fn repl(print_ast: bool, print_tokens: bool) {
     let heap = VariableHeap::new();
     let heap_rc = Rc::new(RefCell::new(heap));
     let mut env = Environment::new(None, Some(heap_rc.clone()));
+    env.strict_float = strict_float;
+    env.bytecode = bytecode;
+    env.max_depth = max_depth;
+    env.project = std::env::current_dir().ok().and_then(|cwd| Project::discover(&cwd)).map(Rc::new);
 
-    env.declare_function(print_fn()).unwrap();
+    stdlib::prelude::register(&mut env);
 
     loop {
         let sig = line_editor.read_line(&prompt);
@@ -114,12 +107,16 @@ pub fn repl(print_ast: bool, print_tokens: bool) {
                     let type_ = env.get_type(identifier);
                     match type_ {
                         Ok(type_) => println!("{:?}", type_),
-                        Err(e) => eprintln!("{} {:?}", "Error:".red(), e),
+                        Err(e) => eprintln!("{}", report(&e, &buffer, None, &[], color)),
                     }
                     continue;
                 } else if buffer.trim().starts_with("#heap") {
                     heap_rc.borrow().print();
                     continue;
+                } else if buffer.trim() == "#gc" {
+                    let stats = heap_rc.borrow_mut().collect();
+                    println!("{}", stats);
+                    continue;
                 }
                 
                 let mut lexer = Lexer::new(buffer.clone());
@@ -129,24 +126,32 @@ pub fn repl(print_ast: bool, print_tokens: bool) {
                         if print_tokens {
                             println!("Tokens: {:?}", lexer.tokens);
                         }
-                        let mut parser = Parser::new(lexer.tokens.clone());
+                        let mut parser = Parser::with_spans(lexer.tokens.clone(), lexer.spans.clone());
                         match parser.parse() {
                             Ok(ast) => {
                                 if print_ast {
-                                    println!("{}", ast.to_string());
+                                    println!("{}", ASTNode::to_string(ast.as_ref()));
+                                }
+                                if let Err(e @ Error::TypeCheckError(_)) = checker::check(&env, ast.as_ref()) {
+                                    eprintln!("{}", report(&e, &buffer, None, &[], color));
+                                    continue;
                                 }
-                                match ast.eval(&mut env) {
-                                    Ok(value) => println!("{}", value.to_string().green()),
-                                    Err(e) => eprintln!("{} {:?}", "Error:".red(), e),
+                                match env.evaluate(&ast) {
+                                    Ok(value) | Err(Error::Return(value)) => println!("{}", value.to_string().green()),
+                                    Err(e) => {
+                                        let span = env.error_span.get();
+                                        let trace = env.take_trace();
+                                        eprintln!("{}", report(&e, &buffer, span, &trace, color));
+                                    }
                                 }
                             }
                             Err(e) => {
-                                eprintln!("{} {:?}", "Error:".red(), e);
+                                eprintln!("{}", report(&e, &buffer, parser.error_span(), &[], color));
                             }
                         }
                     }
                     Err(e) => {
-                        eprintln!("{} {:?}", "Error:".red(), e);
+                        eprintln!("{}", report(&Error::LexerError(e), &buffer, Some(Span::at(lexer.position(), 1)), &[], color));
                     }
                 }
             }
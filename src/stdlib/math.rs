@@ -0,0 +1,30 @@
+use crate::ast::cast::cast;
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::Environment;
+use crate::stdlib::{as_float, signature};
+use crate::Error;
+
+pub fn register(env: &mut Environment) {
+    env.register_native("sqrt", signature(&[("x", Type::F64)], Some(Type::F64)), |args| Ok(Value::Float(as_float(&args[0])?.sqrt())));
+    env.register_native("sin", signature(&[("x", Type::F64)], Some(Type::F64)), |args| Ok(Value::Float(as_float(&args[0])?.sin())));
+    env.register_native("cos", signature(&[("x", Type::F64)], Some(Type::F64)), |args| Ok(Value::Float(as_float(&args[0])?.cos())));
+    env.register_native("floor", signature(&[("x", Type::F64)], Some(Type::I64)), |args| to_i64(as_float(&args[0])?.floor()));
+    env.register_native("ceil", signature(&[("x", Type::F64)], Some(Type::I64)), |args| to_i64(as_float(&args[0])?.ceil()));
+    env.register_native("round", signature(&[("x", Type::F64)], Some(Type::I64)), |args| to_i64(as_float(&args[0])?.round()));
+    env.register_native("pi", signature(&[], Some(Type::F64)), |_| Ok(Value::Float(std::f64::consts::PI)));
+    env.register_native("min", signature(&[("a", Type::F64), ("b", Type::F64)], Some(Type::F64)), |args| {
+        let (a, b) = (as_float(&args[0])?, as_float(&args[1])?);
+        Ok(Value::Float(if b < a { b } else { a }))
+    });
+    env.register_native("max", signature(&[("a", Type::F64), ("b", Type::F64)], Some(Type::F64)), |args| {
+        let (a, b) = (as_float(&args[0])?, as_float(&args[1])?);
+        Ok(Value::Float(if b > a { b } else { a }))
+    });
+}
+
+/// A whole float as an `i64`, failing like `as` does for NaN, infinities and
+/// values out of range.
+fn to_i64(f: f64) -> Result<Value, Error> {
+    cast(Value::Float(f), &Type::I64, false)
+}
@@ -0,0 +1,52 @@
+pub mod math;
+pub mod os;
+pub mod prelude;
+pub mod task;
+pub mod test_stdlib;
+
+use crate::ast::function::FnSignature;
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::Error;
+
+/// A module importable as `'std:<name>'`.
+pub enum StdModule {
+    /// Native functions registered into the module's environment.
+    Rust(fn(&mut Environment)),
+    /// Loop source evaluated into the module's environment on import.
+    Loop(&'static str),
+}
+
+pub fn get_module(name: &str) -> Option<StdModule> {
+    match name {
+        "math" => Some(StdModule::Rust(math::register)),
+        "os" => Some(StdModule::Rust(os::register)),
+        "geometry" => Some(StdModule::Loop(include_str!("geometry.lp"))),
+        _ => None,
+    }
+}
+
+pub fn signature(params: &[(&str, Type)], return_type: Option<Type>) -> FnSignature {
+    FnSignature {
+        params: params.iter().map(|(param, type_)| (param.to_string(), type_.clone())).collect(),
+        references: Vec::new(),
+        return_type,
+    }
+}
+
+pub fn as_float(value: &Value) -> Result<f64, Error> {
+    match value {
+        Value::Int(i) => Ok(*i as f64),
+        Value::TypedInt(i, _) => Ok(*i as f64),
+        Value::Float(f) => Ok(*f),
+        value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::F64))),
+    }
+}
+
+pub fn as_string(value: &Value) -> Result<String, Error> {
+    match value {
+        Value::String(s, _) => Ok(s.clone()),
+        value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::String))),
+    }
+}
@@ -0,0 +1,18 @@
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::Environment;
+use crate::stdlib::{as_string, signature};
+
+pub fn register(env: &mut Environment) {
+    env.register_native("platform", signature(&[], Some(Type::String)), |_| Ok(Value::String(std::env::consts::OS.to_string(), false)));
+    env.register_native("cwd", signature(&[], Some(Type::String)), |_| {
+        let cwd = std::env::current_dir().map(|p| p.display().to_string()).unwrap_or_default();
+        Ok(Value::String(cwd, false))
+    });
+    env.register_native("env", signature(&[("name", Type::String)], Some(Type::Option(Box::new(Type::String)))), |args| {
+        Ok(match std::env::var(as_string(&args[0])?) {
+            Ok(value) => Value::String(value, false),
+            Err(_) => Value::None,
+        })
+    });
+}
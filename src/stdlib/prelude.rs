@@ -0,0 +1,27 @@
+use crate::ast::integer;
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::Environment;
+use crate::lexer::token::Operator;
+use crate::stdlib::{signature, task};
+
+/// Functions available without an import.
+pub fn register(env: &mut Environment) {
+    env.register_native("print", signature(&[("value", Type::Any)], None), |args| {
+        println!("{}", args[0]);
+        Ok(Value::None)
+    });
+    let operations = [
+        ("wrapping_add", Operator::Add, false),
+        ("wrapping_sub", Operator::Sub, false),
+        ("wrapping_mul", Operator::Mul, false),
+        ("saturating_add", Operator::Add, true),
+        ("saturating_sub", Operator::Sub, true),
+        ("saturating_mul", Operator::Mul, true),
+    ];
+    for (name, operator, saturating) in operations {
+        let integers = signature(&[("a", Type::Generic('T')), ("b", Type::Generic('T'))], Some(Type::Generic('T')));
+        env.register_native(name, integers, move |args| integer::bounded(&operator, &args[0], &args[1], saturating));
+    }
+    task::register(env);
+}
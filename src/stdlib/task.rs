@@ -0,0 +1,65 @@
+use crate::ast::type_node::Type;
+use crate::ast::Value;
+use crate::environment::environment::{Environment, RuntimeError};
+use crate::environment::task;
+use crate::stdlib::signature;
+use crate::Error;
+use std::rc::Rc;
+
+/// `sleep`, `now`, `join_all`, `select` and channels, registered in the prelude.
+pub fn register(env: &mut Environment) {
+    let tasks = Rc::clone(&env.tasks);
+    env.register_native("sleep", signature(&[("ms", Type::I64)], None), move |args| {
+        let ms = i64::try_from(args[0].clone())?;
+        task::sleep(&tasks, ms.max(0) as u64)?;
+        Ok(Value::None)
+    });
+    let tasks = Rc::clone(&env.tasks);
+    env.register_native("now", signature(&[], Some(Type::I64)), move |_| {
+        Ok(Value::Int(tasks.borrow().now() as i64))
+    });
+    let tasks = Rc::clone(&env.tasks);
+    env.register_native("join_all", signature(&[("tasks", Type::Any)], None), move |args| {
+        let mut values = Vec::new();
+        for id in task_ids(&args[0])? {
+            values.push(task::await_task(&tasks, id)?);
+        }
+        Ok(Value::Tuple(values))
+    });
+    let tasks = Rc::clone(&env.tasks);
+    env.register_native("select", signature(&[("tasks", Type::Any)], None), move |args| {
+        let (index, value) = task::select(&tasks, &task_ids(&args[0])?)?;
+        Ok(Value::Tuple(vec![Value::Int(index as i64), value]))
+    });
+    let tasks = Rc::clone(&env.tasks);
+    env.register_native("channel", signature(&[], Some(Type::Channel)), move |_| {
+        Ok(Value::Channel(tasks.borrow_mut().channel()))
+    });
+    let tasks = Rc::clone(&env.tasks);
+    env.register_native("send", signature(&[("channel", Type::Channel), ("value", Type::Any)], None), move |args| {
+        tasks.borrow_mut().send(channel_id(&args[0])?, args[1].clone())?;
+        Ok(Value::None)
+    });
+    let tasks = Rc::clone(&env.tasks);
+    env.register_native("recv", signature(&[("channel", Type::Channel)], None), move |args| {
+        task::recv(&tasks, channel_id(&args[0])?)
+    });
+}
+
+fn task_ids(value: &Value) -> Result<Vec<usize>, Error> {
+    let values = match value {
+        Value::Tuple(values) => values.clone(),
+        value => vec![value.clone()],
+    };
+    values.iter().map(|value| match value {
+        Value::Task(id) => Ok(*id),
+        value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::Task(Box::new(Type::Any))))),
+    }).collect()
+}
+
+fn channel_id(value: &Value) -> Result<usize, Error> {
+    match value {
+        Value::Channel(id) => Ok(*id),
+        value => Err(Error::RuntimeError(RuntimeError::ValueNotOfType(value.to_string(), Type::Channel))),
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod test_stdlib {
+    use crate::ast::type_node::Type;
+    use crate::ast::Value;
+    use crate::environment::environment::RuntimeError;
+    use crate::{Error, Interpreter};
+
+    #[test]
+    fn test_import_rust_module() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("import 'std:math'").unwrap();
+        assert_eq!(interpreter.eval_str("math.sqrt(16)").unwrap(), Value::Float(4.0));
+        assert_eq!(interpreter.eval_str("math.max(a: 3, b: 7)").unwrap(), Value::Float(7.0));
+        match interpreter.env.get_type("math.floor").unwrap() {
+            Type::FnType(signature) => {
+                assert_eq!(signature.params, vec![("x".to_string(), Type::F64)]);
+                assert_eq!(signature.return_type, Some(Type::I64));
+            }
+            t => panic!("expected a function type, got {:?}", t),
+        }
+    }
+
+    #[test]
+    fn test_math_rounding_and_min_max() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("import 'std:math'").unwrap();
+        assert_eq!(interpreter.eval_str("math.floor(-1.5)").unwrap(), Value::TypedInt(-2, Type::I64));
+        assert_eq!(interpreter.eval_str("math.round(2.5)").unwrap(), Value::TypedInt(3, Type::I64));
+        for source in ["math.floor(1e30)", "math.ceil(-1e30)", "math.round(0.0 / 0.0)", "math.floor(1.0 / 0.0)"] {
+            assert!(matches!(interpreter.eval_str(source), Err(Error::RuntimeError(RuntimeError::LossyCast(_, Type::I64)))), "{}", source);
+        }
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("import 'std:math'\nlet a: i32 = 3\nlet b: i32 = 7").unwrap();
+        assert!(matches!(interpreter.eval_str("math.min(a: a, b: b)").unwrap(), Value::Float(f) if f == 3.0));
+        assert!(matches!(interpreter.eval_str("math.max(a: a, b: 2.5)").unwrap(), Value::Float(f) if f == 3.0));
+    }
+
+    #[test]
+    fn test_import_block_and_alias() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("import {\n'std:os' as system\n'std:math'\n}").unwrap();
+        assert_eq!(interpreter.eval_str("system.platform()").unwrap(), Value::String(std::env::consts::OS.to_string(), false));
+        assert!(interpreter.env.get_type("math.pi").is_ok());
+        assert!(interpreter.env.get_type("os.platform").is_err());
+    }
+
+    #[test]
+    fn test_from_import() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("from 'std:math' import floor, ceil").unwrap();
+        assert_eq!(interpreter.eval_str("ceil(1.2)").unwrap(), Value::Int(2));
+        assert!(interpreter.env.get_type("sqrt").is_err());
+        assert!(matches!(
+            interpreter.eval_str("from 'std:math' import tan"),
+            Err(Error::RuntimeError(RuntimeError::ModuleMemberNotFound(_, _)))
+        ));
+    }
+
+    #[test]
+    fn test_import_loop_source_module() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("import 'std:geometry'").unwrap();
+        assert_eq!(interpreter.eval_str("geometry.dot(a: (1, 2), b: (3, 4))").unwrap(), Value::Float(11.0));
+        assert_eq!(
+            interpreter.eval_str("geometry.translate(point: (1, 1), offset: (2, 3))").unwrap(),
+            Value::Tuple(vec![Value::Float(3.0), Value::Float(4.0)])
+        );
+    }
+
+    #[test]
+    fn test_unknown_module() {
+        let mut interpreter = Interpreter::new();
+        assert!(matches!(
+            interpreter.eval_str("import 'std:nothing'"),
+            Err(Error::RuntimeError(RuntimeError::ModuleNotFound(_)))
+        ));
+        assert!(matches!(
+            interpreter.eval_str("import 'ftp:somewhere'"),
+            Err(Error::RuntimeError(RuntimeError::UnsupportedImportScheme(_)))
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::ast::type_node::Type;
+use crate::ast::{Node, Value};
+use crate::environment::frame::Address;
+use crate::lexer::token::Operator;
+use crate::lexer::Span;
+use std::rc::Rc;
+
+/// An instruction of the stack machine. Every instruction leaves the value of
+/// what it evaluates on the stack, and jumps hold the index they go to.
+/// Constants, types, registers and calls are indices into the tables of the
+/// `Chunk`.
+#[derive(Debug)]
+pub enum Instruction {
+    Constant(usize),
+    /// Replaces the string on top of the stack with its interpolation.
+    Interpolate,
+    /// Loads the variable in a register, with the address the resolver
+    /// found it at where it is used.
+    Load(usize, Option<Address>),
+    /// Declares the variable of a register with the value on top of the
+    /// stack, of the type at the index when it has one.
+    Declare { register: usize, mutable: bool, type_: Option<usize> },
+    /// Declares the variable of a register without a value.
+    DeclareEmpty { register: usize, mutable: bool, type_: usize },
+    Assign(usize, Option<Address>),
+    /// `name op= value`, with the value on top of the stack.
+    Compound(usize, Option<Address>, Operator),
+    Binary(Operator),
+    Unary(Operator),
+    /// Replaces the value on top of the stack with it cast to a type.
+    Cast { type_: usize, lossy: bool },
+    /// Replaces the elements on top of the stack, last on top, with a tuple of them.
+    Tuple(usize),
+    List(usize),
+    /// Replaces a collection and an index on top of the stack with the element there.
+    Index,
+    /// Replaces the index on top of the stack with the element there of the
+    /// variable in the register, without copying the whole of it. Not
+    /// finding the variable raises at the span of its name.
+    IndexVariable(usize, Option<Address>, Option<Span>),
+    TupleIndex(usize),
+    /// Replaces a collection and a value with whether the value is in it.
+    Contains,
+    Pop,
+    Jump(usize),
+    /// Pops the condition and jumps unless it is `true`. A condition that is
+    /// not a bool raises.
+    JumpUnlessTrue(usize),
+    /// Finds the function of a call, keeping it in the register of its name.
+    /// The arguments are passed after it is found, in the order they are
+    /// written, and `Call` makes the call.
+    Callee(usize, usize),
+    /// Passes the value on top of the stack as the argument at the index.
+    Argument(usize, usize),
+    /// Passes the variable in the register as the argument at the index, by
+    /// reference.
+    Reference(usize, usize, usize),
+    /// Passes the variable in the register as `mut`.
+    Borrow(usize, usize, usize),
+    Call(usize),
+    /// Calls a method of the value below its arguments, which are on top of
+    /// the stack.
+    Method(usize),
+    /// Evaluates what follows in a frame of its own, until `ExitScope`.
+    EnterScope,
+    ExitScope,
+    /// A node without instructions of its own, evaluated by walking its tree.
+    Eval(Rc<Node>),
+}
+
+/// A variable of a chunk. It is kept once it is declared or first looked
+/// up, so that using it again does not search the frames for its name.
+#[derive(Debug)]
+pub struct Register {
+    pub name: String,
+    /// Whether the variable can be kept. It is not when code the chunk does
+    /// not compile may declare the name again in the same frame.
+    pub cached: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub instructions: Vec<Instruction>,
+    /// Where each instruction was compiled from, for the errors it raises.
+    pub spans: Vec<Option<Span>>,
+    pub constants: Vec<Value>,
+    pub types: Vec<Type>,
+    pub registers: Vec<Register>,
+    /// The `FnCall`s and `MethodCall`s made, for their names and how they
+    /// pass their arguments.
+    pub calls: Vec<Rc<Node>>,
+}
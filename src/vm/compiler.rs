@@ -0,0 +1,375 @@
+use crate::ast::scope::Scope;
+use crate::ast::tuple::{Tuple, TupleLike};
+use crate::ast::type_node::Type;
+use crate::ast::{Node, NodeKind, Value};
+use crate::lexer::Span;
+use crate::vm::bytecode::{Chunk, Instruction, Register};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The registers of the variables declared in a block so far.
+#[derive(Debug, Default)]
+struct Block {
+    registers: HashMap<String, usize>,
+    /// Set once the block imports, which declares names the compiler does
+    /// not know.
+    opaque: bool,
+}
+
+/// Compiles an AST to a `Chunk`. Nodes it has no instructions for are
+/// evaluated by walking their tree.
+#[derive(Debug)]
+pub struct Compiler {
+    chunk: Chunk,
+    /// The blocks being compiled, innermost last. The first is the one the
+    /// chunk runs in.
+    blocks: Vec<Block>,
+    /// The registers of the variables declared outside the chunk.
+    outer: HashMap<String, usize>,
+    /// The span of the node being compiled, or of the closest one around it.
+    span: Option<Span>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self { chunk: Chunk::default(), blocks: vec![Block::default()], outer: HashMap::new(), span: None }
+    }
+}
+
+pub fn compile(node: &Rc<Node>) -> Chunk {
+    let mut compiler = Compiler::default();
+    compiler.node(node);
+    compiler.finish()
+}
+
+/// Compiles the statements of `scope` in a frame of their own, like `Scope::eval`.
+pub fn compile_scope(scope: &Scope) -> Chunk {
+    let mut compiler = Compiler::default();
+    compiler.scope(scope);
+    compiler.finish()
+}
+
+impl Compiler {
+    pub fn finish(self) -> Chunk {
+        self.chunk
+    }
+
+    /// Emits `instruction` and returns its index.
+    pub fn emit(&mut self, instruction: Instruction) -> usize {
+        self.chunk.instructions.push(instruction);
+        self.chunk.spans.push(self.span);
+        self.chunk.instructions.len() - 1
+    }
+
+    /// Emits `instruction` for a node inside the one being compiled.
+    fn emit_at(&mut self, span: Option<Span>, instruction: Instruction) -> usize {
+        let outer = self.span;
+        self.span = span.or(outer);
+        let at = self.emit(instruction);
+        self.span = outer;
+        at
+    }
+
+    /// The index of the next instruction, for jumps back to it.
+    pub fn position(&self) -> usize {
+        self.chunk.instructions.len()
+    }
+
+    /// Makes the jump at `at` go to the next instruction.
+    pub fn patch(&mut self, at: usize) {
+        let next = self.position();
+        if let Instruction::Jump(target) | Instruction::JumpUnlessTrue(target) = &mut self.chunk.instructions[at] {
+            *target = next;
+        }
+    }
+
+    fn constant(&mut self, value: Value) -> usize {
+        self.chunk.constants.push(value);
+        self.chunk.constants.len() - 1
+    }
+
+    fn type_(&mut self, type_: Type) -> usize {
+        self.chunk.types.push(type_);
+        self.chunk.types.len() - 1
+    }
+
+    fn call(&mut self, node: &Rc<Node>) -> usize {
+        self.chunk.calls.push(Rc::clone(node));
+        self.chunk.calls.len() - 1
+    }
+
+    fn register(&mut self, name: &str, cached: bool) -> usize {
+        self.chunk.registers.push(Register { name: name.to_string(), cached });
+        self.chunk.registers.len() - 1
+    }
+
+    /// The register of the variable `name` refers to here.
+    fn variable(&mut self, name: &str) -> usize {
+        for block in self.blocks.iter().rev() {
+            if let Some(register) = block.registers.get(name) {
+                return *register;
+            }
+            if block.opaque {
+                // it may be one of the names the block imports
+                return self.register(name, false);
+            }
+        }
+        match self.outer.get(name) {
+            Some(register) => *register,
+            None => {
+                let register = self.register(name, true);
+                self.outer.insert(name.to_string(), register);
+                register
+            }
+        }
+    }
+
+    /// Declares `name` in the innermost block. Code the chunk does not
+    /// compile declares it in a register that is not kept.
+    fn declare(&mut self, name: &str, cached: bool) -> usize {
+        let register = self.register(name, cached);
+        self.blocks.last_mut().unwrap().registers.insert(name.to_string(), register);
+        register
+    }
+
+    /// Evaluates `node` by walking its tree.
+    pub fn fallback(&mut self, node: Rc<Node>) {
+        self.shadow(&node);
+        self.emit(Instruction::Eval(node));
+    }
+
+    /// Stops keeping the variables `node` declares when it is walked.
+    fn shadow(&mut self, node: &Node) {
+        match &node.kind {
+            NodeKind::FnDeclaration(declaration) => {
+                if let Some(name) = &declaration.name {
+                    self.declare(name, false);
+                }
+            }
+            NodeKind::VariableDeclaration(declaration) => self.shadow_names(&declaration.name),
+            NodeKind::VariableDeclarationAssignment(declaration) => self.shadow_names(&declaration.name),
+            NodeKind::Import(_) => self.blocks.last_mut().unwrap().opaque = true,
+            _ => {}
+        }
+    }
+
+    fn shadow_names(&mut self, names: &Tuple<String>) {
+        match names {
+            Tuple::Element(name) => {
+                self.declare(name, false);
+            }
+            Tuple::List(names) => names.iter().for_each(|name| self.shadow_names(name)),
+            Tuple::Empty => {}
+        }
+    }
+
+    /// Compiles `nodes` one after the other, leaving the value of the last.
+    pub fn sequence(&mut self, nodes: &[Rc<Node>]) {
+        if nodes.is_empty() {
+            let none = self.constant(Value::None);
+            self.emit(Instruction::Constant(none));
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 {
+                self.emit(Instruction::Pop);
+            }
+            self.node(node);
+        }
+    }
+
+    pub fn node(&mut self, node: &Rc<Node>) {
+        let outer = self.span;
+        self.span = node.span.or(outer);
+        self.kind(node);
+        self.span = outer;
+    }
+
+    fn kind(&mut self, node: &Rc<Node>) {
+        match &node.kind {
+            NodeKind::MultiExpression(multi) => self.sequence(&multi.children),
+            NodeKind::EmptyASTNode(_) => {
+                let none = self.constant(Value::None);
+                self.emit(Instruction::Constant(none));
+            }
+            NodeKind::Literal(literal) => {
+                let constant = self.constant(literal.0.clone());
+                self.emit(Instruction::Constant(constant));
+                if let Value::String(_, false) = literal.0 {
+                    self.emit(Instruction::Interpolate);
+                }
+            }
+            NodeKind::Identifier(identifier) => {
+                let register = self.variable(&identifier.name);
+                self.emit(Instruction::Load(register, identifier.address.get()));
+            }
+            NodeKind::FnCall(call) => {
+                let index = self.call(node);
+                let function = self.variable(&call.name);
+                self.emit(Instruction::Callee(index, function));
+                for (i, (_, arg)) in call.params.iter().enumerate() {
+                    match &arg.kind {
+                        NodeKind::Identifier(identifier) => {
+                            let register = self.variable(&identifier.name);
+                            self.emit_at(arg.span, Instruction::Reference(index, i, register));
+                        }
+                        NodeKind::Borrow(borrow) => {
+                            let register = self.variable(&borrow.name);
+                            self.emit_at(arg.span, Instruction::Borrow(index, i, register));
+                        }
+                        _ => {
+                            self.node(arg);
+                            self.emit(Instruction::Argument(index, i));
+                        }
+                    }
+                }
+                self.emit(Instruction::Call(index));
+            }
+            NodeKind::MethodCall(call) => {
+                self.node(&call.receiver);
+                for (_, arg) in &call.params {
+                    self.node(arg);
+                }
+                let index = self.call(node);
+                self.emit(Instruction::Method(index));
+            }
+            NodeKind::BinaryOperation(operation) => {
+                self.node(&operation.left);
+                self.node(&operation.right);
+                self.emit(Instruction::Binary(operation.operator.clone()));
+            }
+            NodeKind::UnaryOperation(operation) => {
+                self.node(&operation.operand);
+                self.emit(Instruction::Unary(operation.operator.clone()));
+            }
+            NodeKind::Cast(cast) => {
+                self.node(&cast.value);
+                let type_ = self.type_(cast.type_.clone());
+                self.emit(Instruction::Cast { type_, lossy: cast.lossy });
+            }
+            NodeKind::TupleASTNode(tuple) => {
+                tuple.children.iter().for_each(|child| self.node(child));
+                self.emit(Instruction::Tuple(tuple.children.len()));
+            }
+            NodeKind::List(list) => {
+                list.elements.iter().for_each(|element| self.node(element));
+                self.emit(Instruction::List(list.elements.len()));
+            }
+            NodeKind::Index(index) => match &index.value.kind {
+                // a variable is indexed where it is rather than copied whole
+                NodeKind::Identifier(identifier) => {
+                    self.node(&index.index);
+                    let register = self.variable(&identifier.name);
+                    self.emit(Instruction::IndexVariable(register, identifier.address.get(), index.value.span));
+                }
+                _ => {
+                    self.node(&index.value);
+                    self.node(&index.index);
+                    self.emit(Instruction::Index);
+                }
+            },
+            NodeKind::TupleIndex(index) => {
+                self.node(&index.value);
+                self.emit(Instruction::TupleIndex(index.index));
+            }
+            NodeKind::Contains(contains) => {
+                self.node(&contains.value);
+                self.node(&contains.list);
+                self.emit(Instruction::Contains);
+            }
+            NodeKind::VariableAssignment(assignment) => match &assignment.name {
+                Tuple::Element(name) => {
+                    self.node(&assignment.expr);
+                    let register = self.variable(name);
+                    self.emit(Instruction::Assign(register, assignment.address.get()));
+                }
+                _ => self.fallback(node.clone()),
+            },
+            NodeKind::CompoundAssignment(assignment) => match &assignment.name {
+                Tuple::Element(name) if assignment.path.is_empty() => {
+                    self.node(&assignment.expr);
+                    let register = self.variable(name);
+                    self.emit(Instruction::Compound(register, assignment.address.get(), assignment.operator.clone()));
+                }
+                _ => self.fallback(node.clone()),
+            },
+            NodeKind::VariableDeclarationAssignment(declaration) => match &declaration.name {
+                Tuple::Element(name) => {
+                    // the value is compiled first, as it sees what the name is before
+                    self.node(&declaration.expr);
+                    let register = self.declare(name, true);
+                    let type_ = declaration.type_.clone().map(|type_| self.type_(type_));
+                    self.emit(Instruction::Declare { register, mutable: declaration.mutable, type_ });
+                }
+                _ => self.fallback(node.clone()),
+            },
+            NodeKind::VariableDeclaration(declaration) => {
+                // `let t: (i32, i32)` declares one variable of the tuple type
+                let type_ = match declaration.type_.to_tuple() {
+                    Tuple::Empty => None,
+                    types => Type::from_tuple(types).ok(),
+                };
+                match (&declaration.name, type_) {
+                    (Tuple::Element(name), Some(type_)) => {
+                        let register = self.declare(name, true);
+                        let type_ = self.type_(type_);
+                        self.emit(Instruction::DeclareEmpty { register, mutable: declaration.mutable, type_ });
+                    }
+                    _ => self.fallback(node.clone()),
+                }
+            }
+            NodeKind::IfBlock(block) => self.branch(&block.condition, &block.content, block.next_conditional.as_ref()),
+            NodeKind::ElifBlock(block) => self.branch(&block.condition, &block.content, block.next_conditional.as_ref()),
+            NodeKind::ElseBlock(block) => self.scope(&block.content),
+            NodeKind::Loop(block) => {
+                let start = self.position();
+                self.scope(&block.content);
+                self.emit(Instruction::Pop);
+                self.emit(Instruction::Jump(start));
+            }
+            NodeKind::While(block) => {
+                // the value of the last run of the body, none when it never runs
+                let none = self.constant(Value::None);
+                self.emit(Instruction::Constant(none));
+                let start = self.position();
+                self.node(&block.condition);
+                let end = self.emit(Instruction::JumpUnlessTrue(usize::MAX));
+                self.emit(Instruction::Pop);
+                self.scope(&block.content);
+                self.emit(Instruction::Jump(start));
+                self.patch(end);
+            }
+            _ => self.fallback(node.clone()),
+        }
+    }
+
+    /// An `if` or `elif` and the rest of its chain.
+    fn branch(&mut self, condition: &Rc<Node>, content: &Scope, next: Option<&Rc<Node>>) {
+        self.node(condition);
+        let skip = self.emit(Instruction::JumpUnlessTrue(usize::MAX));
+        self.scope(content);
+        let end = self.emit(Instruction::Jump(usize::MAX));
+        self.patch(skip);
+        match next {
+            Some(next) => self.node(next),
+            None => {
+                let none = self.constant(Value::None);
+                self.emit(Instruction::Constant(none));
+            }
+        }
+        self.patch(end);
+    }
+
+    /// Compiles a block, in a frame of its own when it declares variables,
+    /// like `Scope::eval`.
+    pub fn scope(&mut self, scope: &Scope) {
+        if !scope.declares() {
+            self.sequence(scope.nodes());
+            return;
+        }
+        self.emit(Instruction::EnterScope);
+        self.blocks.push(Block::default());
+        self.sequence(scope.nodes());
+        self.blocks.pop();
+        self.emit(Instruction::ExitScope);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::ast::binary_operation::BinaryOperation;
+use crate::ast::cast::cast;
+use crate::ast::conditional::holds;
+use crate::ast::function::{Callee, FnCall};
+use crate::ast::integer;
+use crate::ast::type_node::Type;
+use crate::ast::list::{contains, element};
+use crate::ast::scope::Scope;
+use crate::ast::tuple::element_mut;
+use crate::ast::unary_operation::UnaryOperation;
+use crate::ast::{ASTNode, Node, NodeKind, Value};
+use crate::environment::environment::{Environment, ReferenceOrValue, RuntimeError};
+use crate::environment::frame::Address;
+use crate::environment::heap::Heap;
+use crate::environment::variable::Variable;
+use crate::vm::bytecode::{Chunk, Instruction};
+use crate::lexer::token::Operator;
+use crate::vm::compiler::{compile, compile_scope};
+use crate::Error;
+use std::rc::Rc;
+
+/// The state of a run of a chunk.
+struct Machine<'a> {
+    chunk: &'a Chunk,
+    stack: Vec<Value>,
+    /// The variables of the registers of the chunk found so far.
+    registers: Vec<Option<Variable>>,
+    /// The calls whose arguments are being passed, innermost last.
+    calls: Vec<Callee>,
+}
+
+/// Runs `chunk` in `env`, returning the value left by its last instruction.
+pub fn run(chunk: &Chunk, env: &mut Environment) -> Result<Value, Error> {
+    let frame = Rc::clone(&env.frame);
+    let mut machine = Machine { chunk, stack: Vec::new(), registers: vec![None; chunk.registers.len()], calls: Vec::new() };
+    let result = machine.run(env);
+    // an error leaves the blocks it is raised in without ending them
+    env.frame = frame;
+    result
+}
+
+impl Machine<'_> {
+    fn run(&mut self, env: &mut Environment) -> Result<Value, Error> {
+        let mut pc = 0;
+        while let Some(instruction) = self.chunk.instructions.get(pc) {
+            pc += 1;
+            match self.step(env, instruction) {
+                Ok(Some(target)) => pc = target,
+                Ok(None) => {}
+                Err(e) => return Err(env.locate(self.chunk.spans[pc - 1], e)),
+            }
+        }
+        Ok(self.pop())
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap_or(Value::None)
+    }
+
+    /// Runs `instruction`, returning where it jumps to.
+    fn step(&mut self, env: &mut Environment, instruction: &Instruction) -> Result<Option<usize>, Error> {
+        match instruction {
+            Instruction::Constant(index) => self.stack.push(self.chunk.constants[*index].clone()),
+            Instruction::Interpolate => {
+                let value = self.pop();
+                self.stack.push(env.interpolate(value)?);
+            }
+            Instruction::Load(register, address) => {
+                let value = self.inspect(env, *register, *address, Value::clone)?;
+                self.stack.push(value);
+            }
+            Instruction::Declare { register, mutable, type_ } => {
+                let value = self.pop();
+                let name = self.chunk.registers[*register].name.clone();
+                let type_ = type_.map(|type_| self.chunk.types[type_].clone());
+                self.registers[*register] = Some(env.declare_assign(name, value, *mutable, type_)?);
+                self.stack.push(Value::Bool(true));
+            }
+            Instruction::DeclareEmpty { register, mutable, type_ } => {
+                let name = self.chunk.registers[*register].name.clone();
+                self.registers[*register] = Some(env.declare(name, *mutable, self.chunk.types[*type_].clone())?);
+                self.stack.push(Value::Bool(true));
+            }
+            Instruction::Assign(register, address) => {
+                let value = self.pop();
+                self.assign(env, *register, *address, value)?;
+                self.stack.push(Value::Bool(true));
+            }
+            Instruction::Compound(register, address, operator) => {
+                let value = self.pop();
+                if self.compound_in_place(env, *register, operator, &value) {
+                    self.stack.push(Value::Bool(true));
+                    return Ok(None);
+                }
+                let current = self.inspect(env, *register, *address, Value::clone)?;
+                let result = BinaryOperation::apply(operator, current, value, env.strict_float)?;
+                self.assign(env, *register, *address, result)?;
+                self.stack.push(Value::Bool(true));
+            }
+            Instruction::Binary(operator) => {
+                let right = self.pop();
+                let left = self.pop();
+                let result = match integers(operator, &left, &right) {
+                    Some(result) => result,
+                    None => BinaryOperation::apply(operator, left, right, env.strict_float)?,
+                };
+                self.stack.push(result);
+            }
+            Instruction::Unary(operator) => {
+                let operand = self.pop();
+                self.stack.push(UnaryOperation::apply(operator, operand)?);
+            }
+            Instruction::Cast { type_, lossy } => {
+                let value = self.pop();
+                self.stack.push(cast(value, &self.chunk.types[*type_], *lossy)?);
+            }
+            Instruction::Tuple(count) => {
+                let values = self.stack.split_off(self.stack.len() - count);
+                self.stack.push(Value::Tuple(values));
+            }
+            Instruction::List(count) => {
+                let values = self.stack.split_off(self.stack.len() - count);
+                self.stack.push(Value::List(values));
+            }
+            Instruction::Index => {
+                let index = self.pop();
+                let value = self.pop();
+                self.stack.push(element(&value, index)?);
+            }
+            Instruction::IndexVariable(register, address, span) => {
+                let index = self.pop();
+                let value = self.inspect(env, *register, *address, |value| element(value, index)).map_err(|e| env.locate(*span, e))??;
+                self.stack.push(value);
+            }
+            Instruction::TupleIndex(index) => {
+                let mut value = self.pop();
+                let element = std::mem::replace(element_mut(&mut value, &[*index])?, Value::None);
+                self.stack.push(element);
+            }
+            Instruction::Contains => {
+                let list = self.pop();
+                let value = self.pop();
+                self.stack.push(contains(list, value)?);
+            }
+            Instruction::Pop => {
+                self.stack.pop();
+            }
+            Instruction::Jump(target) => return Ok(Some(*target)),
+            Instruction::JumpUnlessTrue(target) => {
+                if !holds(self.pop())? {
+                    return Ok(Some(*target));
+                }
+            }
+            Instruction::Callee(call, register) => {
+                let function = self.registers[*register].clone();
+                let callee = self.fn_call(*call).callee(env, function.clone())?;
+                if let (None, Callee::Function { function, .. }) = (function, &callee) {
+                    self.keep(*register, function);
+                }
+                self.calls.push(callee);
+            }
+            Instruction::Argument(call, i) => {
+                let value = ReferenceOrValue::Value(self.pop());
+                self.pass(env, *call, *i, value)?;
+            }
+            Instruction::Reference(call, i, register) => {
+                let variable = self.variable(env, *register, None)?;
+                let name = self.chunk.registers[*register].name.clone();
+                self.pass(env, *call, *i, ReferenceOrValue::Reference(variable.index, name))?;
+            }
+            Instruction::Borrow(call, i, register) => {
+                let variable = self.variable(env, *register, None)?;
+                let name = self.chunk.registers[*register].name.clone();
+                if !variable.mutable {
+                    return Err(Error::RuntimeError(RuntimeError::CannotAssignToImmutableVariable(name)));
+                }
+                self.pass(env, *call, *i, ReferenceOrValue::Borrow(variable, name))?;
+            }
+            Instruction::Call(call) => {
+                let callee = self.calls.pop().expect("`Callee` comes before `Call`");
+                self.stack.push(self.fn_call(*call).finish(env, callee)?);
+            }
+            Instruction::Method(call) => {
+                let NodeKind::MethodCall(call) = &self.chunk.calls[*call].kind else { unreachable!("only method calls compile to `Method`") };
+                let values = self.stack.split_off(self.stack.len() - call.params.len());
+                let args = call.params.iter().map(|(param, _)| param.clone()).zip(values).collect();
+                let receiver = self.pop();
+                self.stack.push(call.apply(receiver, args)?);
+            }
+            Instruction::EnterScope => env.enter_scope(),
+            Instruction::ExitScope => env.exit_scope(),
+            Instruction::Eval(node) => self.stack.push(node.eval(env)?),
+        }
+        Ok(None)
+    }
+
+    fn fn_call(&self, call: usize) -> &FnCall {
+        match &self.chunk.calls[call].kind {
+            NodeKind::FnCall(call) => call,
+            _ => unreachable!("only calls compile to `Callee` and `Call`"),
+        }
+    }
+
+    fn pass(&mut self, env: &mut Environment, call: usize, i: usize, value: ReferenceOrValue) -> Result<(), Error> {
+        let callee = self.calls.last_mut().expect("`Callee` comes before the arguments");
+        match &self.chunk.calls[call].kind {
+            NodeKind::FnCall(call) => call.pass(callee, env, i, value),
+            _ => unreachable!("only calls pass arguments"),
+        }
+    }
+
+    /// Keeps `variable` in `register` when it can be, and once it has a value.
+    fn keep(&mut self, register: usize, variable: &Variable) {
+        if self.chunk.registers[register].cached && variable.initialized {
+            self.registers[register] = Some(variable.clone());
+        }
+    }
+
+    /// The variable in `register`, looked up by its name at `address` the
+    /// first time.
+    fn variable(&mut self, env: &Environment, register: usize, address: Option<Address>) -> Result<Variable, Error> {
+        if let Some(variable) = &self.registers[register] {
+            if variable.initialized {
+                return Ok(variable.clone());
+            }
+        }
+        let variable = env.variable_at(&self.chunk.registers[register].name, address)?;
+        self.keep(register, &variable);
+        Ok(variable)
+    }
+
+    /// Applies `f` to the value of the variable in `register` where it is.
+    fn inspect<T>(&mut self, env: &Environment, register: usize, address: Option<Address>, f: impl FnOnce(&Value) -> T) -> Result<T, Error> {
+        let variable = match &self.registers[register] {
+            Some(variable) if variable.initialized => variable,
+            _ => {
+                let name = &self.chunk.registers[register].name;
+                let variable = env.variable_at(name, address)?;
+                if !variable.initialized {
+                    return Err(Error::RuntimeError(RuntimeError::VariableNotInitialized(name.clone())));
+                }
+                self.keep(register, &variable);
+                return env.inspect_variable(&variable, f);
+            }
+        };
+        env.inspect_variable(variable, f)
+    }
+
+    /// `name op= value` on the integer in `register` where it is, when the
+    /// result is an integer of its type. Returns whether it was.
+    fn compound_in_place(&self, env: &Environment, register: usize, operator: &Operator, value: &Value) -> bool {
+        let variable = match &self.registers[register] {
+            Some(variable) if variable.initialized && variable.mutable => variable,
+            _ => return false,
+        };
+        let mut heap = env.heap.borrow_mut();
+        let current = match heap.get_mut(variable.index) {
+            Some(current) => current,
+            None => return false,
+        };
+        match integers(operator, current, value) {
+            Some(result @ Value::TypedInt(_, _)) if matches!(&result, Value::TypedInt(_, type_) if *type_ == variable.type_) => {
+                *current = result;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn assign(&mut self, env: &mut Environment, register: usize, address: Option<Address>, value: Value) -> Result<(), Error> {
+        match &self.registers[register] {
+            Some(variable) if variable.initialized && variable.mutable => store(env, variable, value),
+            _ => {
+                let name = &self.chunk.registers[register].name;
+                env.assign_at(name, address, value)?;
+                if let Ok(variable) = env.variable_at(name, address) {
+                    self.keep(register, &variable);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// `left operator right` for two integers of one type, or an integer and an
+/// untyped one that fits its type, when it neither raises nor converts.
+/// `BinaryOperation::apply` gives the same result, and makes the rest.
+fn integers(operator: &Operator, left: &Value, right: &Value) -> Option<Value> {
+    let (l, r, type_) = match (left, right) {
+        (Value::TypedInt(l, a), Value::TypedInt(r, b)) if a == b => (*l, *r, Some(a)),
+        (Value::TypedInt(l, a), Value::Int(r)) if integer::fits(*r as i128, a) => (*l, *r as i128, Some(a)),
+        (Value::Int(l), Value::TypedInt(r, b)) if integer::fits(*l as i128, b) => (*l as i128, *r, Some(b)),
+        (Value::Int(l), Value::Int(r)) => (*l as i128, *r as i128, None),
+        _ => return None,
+    };
+    let result = match operator {
+        Operator::Lt => return Some(Value::Bool(l < r)),
+        Operator::Gt => return Some(Value::Bool(l > r)),
+        Operator::Lte => return Some(Value::Bool(l <= r)),
+        Operator::Gte => return Some(Value::Bool(l >= r)),
+        Operator::Eq => return Some(Value::Bool(l == r)),
+        Operator::Neq => return Some(Value::Bool(l != r)),
+        Operator::Add => l.checked_add(r)?,
+        Operator::Sub => l.checked_sub(r)?,
+        Operator::Mul => l.checked_mul(r)?,
+        Operator::Div if r != 0 => l.checked_div(r)?,
+        Operator::Mod if r != 0 => l.checked_rem(r)?,
+        _ => return None,
+    };
+    integer::fits(result, type_.unwrap_or(&Type::I64)).then(|| integer::from_integer(result, type_.cloned()))
+}
+
+/// Stores `value` in `variable` as `Environment::store` does, without
+/// checking a value of its type again.
+fn store(env: &Environment, variable: &Variable, value: Value) -> Result<(), Error> {
+    let checked = match (&variable.type_, &value) {
+        (type_, Value::TypedInt(_, found)) => type_ == found,
+        (Type::Bool, Value::Bool(_)) => true,
+        _ => false,
+    };
+    if !checked {
+        return env.store(variable, value);
+    }
+    match env.heap.borrow_mut().get_mut(variable.index) {
+        Some(current) => *current = value,
+        None => return Err(Error::RuntimeError(RuntimeError::NoVariableAtHeapIndex(variable.index))),
+    }
+    Ok(())
+}
+
+impl Environment {
+    /// Evaluates `node` with the bytecode VM when it is enabled, and by
+    /// walking its tree otherwise.
+    pub fn evaluate(&mut self, node: &Rc<Node>) -> Result<Value, Error> {
+        if self.bytecode {
+            run(&compile(node), self)
+        } else {
+            node.eval(self)
+        }
+    }
+
+    /// Like `evaluate`, for the body of a function.
+    pub fn evaluate_scope(&mut self, scope: &Scope) -> Result<Value, Error> {
+        if self.bytecode {
+            run(&scope.chunk(compile_scope), self)
+        } else {
+            scope.eval(self)
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod bytecode;
+pub mod compiler;
+pub mod machine;
+pub mod test_vm;
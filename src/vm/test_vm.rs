@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod test_vm {
+    use crate::lexer::Lexer;
+    use crate::parser::parser::Parser;
+    use crate::environment::frame::Address;
+    use crate::vm::bytecode::{Chunk, Instruction};
+    use crate::ast::{Node, NodeKind};
+    use crate::vm::compiler::{compile, compile_scope};
+    use crate::Interpreter;
+    use std::rc::Rc;
+
+    fn parse(source: &str) -> Rc<Node> {
+        let mut lexer = Lexer::new(source.to_string());
+        lexer.tokenize().unwrap();
+        lexer.clean_tokens();
+        Parser::new(lexer.tokens.clone()).parse().unwrap()
+    }
+
+    fn chunk(source: &str) -> Chunk {
+        compile(&parse(source))
+    }
+
+    /// The name of the variable in `register`.
+    fn name(chunk: &Chunk, register: usize) -> &str {
+        &chunk.registers[register].name
+    }
+
+    /// The result of `source` with either backend, errors included as they
+    /// are reported.
+    fn run(source: &str, bytecode: bool) -> String {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_bytecode(bytecode);
+        match interpreter.eval_str(source) {
+            Ok(value) => value.to_string(),
+            Err(e) => interpreter.render_error(&e, false),
+        }
+    }
+
+    #[test]
+    fn test_same_results_as_tree_walker() {
+        let sources = [
+            "mut i := 0\nmut total := 0\nwhile i < 10 {\n    i += 1\n    if i % 2 == 0 { total += i } elif i == 5 { total -= 100 } else { total += 1 }\n}\ntotal",
+            "fn fib(n: i32) -> i32 {\n    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }\n}\nfib(12)",
+            "mut i := 0\nwhile i < 3 {\n    let doubled := i * 2\n    i := i + 1\n}\ni",
+            "let name := 'loop'\nlet n := -3\n'{name} {n}'",
+            "mut xs := [1, 2]\nxs.push(3)\nlet t := (xs[2], 'a')\nt.0 * 2",
+            "let x: u8 = 200\nx + 100",
+            "let x := 1\nx := 2",
+            "mut a, b := 1, 2\n(a, b) += (10, 20)\nif a > b { a } else { b }",
+            "while false { 1 }",
+            "if false { 1 }",
+            "let x: i32\nmut i := 0\nwhile i < 3 {\n    i += 1\n    let j := i\n    if j == 2 { x := j }\n}\nx",
+            "mut v := 1\nif true {\n    let v := 2\n    v += 1\n}\nv",
+            "fn count(n: i32, acc: i32) -> i32 {\n    if n == 0 { acc } else {\n        let next := acc + n\n        count(n - 1, next)\n    }\n}\ncount(20, 0)",
+            "fn bump(mut n: i32) { n += 1 }\nmut i: i32 = 0\nwhile i < 3 { bump(mut i) }\ni",
+            "if 1 {}",
+            "mut i := 0\nwhile i { i += 1 }",
+            "fn area(width: i32, height: i32) -> i32 { width * height }\nlet height := 3\narea(height: 2 + 2, width: height) + area(1, 2)",
+            "mut xs := [3, 1]\nxs.push(xs.len() + 4)\nlet name := 'loop'\n'{xs[2]} {name.len()}'",
+            "fn half(n: i32) -> i32 { n / 2 }\nhalf(1 / 0)",
+            "let x := 1\nlet y := x / 0",
+            "nope(1 / 0)",
+            "mut i: u8 = 250\nwhile i > 0 { i += 3 }",
+            "mut i := 0\nwhile i < 5 { i += 2 * i + 1 }\ni % 4",
+            "fn f(n: i32) -> i32 { missing }\nf(1)",
+            "fn pair(n: i32) -> [i32] { [n, n] }\nlet words := ['ab']\npair(1).len() + words[0].upper().len() + 'abc'.find('c')!",
+        ];
+        for source in sources {
+            assert_eq!(run(source, true), run(source, false), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_compiles_loops_and_branches() {
+        let compiled = chunk("mut i := 0\nwhile i < 10 {\n    if i > 5 { i += 2 } else { i += 1 }\n}");
+        assert!(!compiled.instructions.iter().any(|instruction| matches!(instruction, Instruction::Eval(_))));
+        // blocks that declare nothing are run in the enclosing frame
+        assert!(!compiled.instructions.iter().any(|instruction| matches!(instruction, Instruction::EnterScope)));
+        // back to the condition, after `mut i := 0` and the initial value of the loop
+        assert!(matches!(compiled.instructions[4], Instruction::Load(register, Some(Address::Global)) if name(&compiled, register) == "i"));
+        assert_eq!(compiled.instructions.iter().filter(|instruction| matches!(instruction, Instruction::Jump(4))).count(), 1);
+        // every use of `i` is in the register it is declared in
+        assert_eq!(compiled.registers.len(), 1);
+        assert_eq!(compiled.spans.len(), compiled.instructions.len());
+
+        let compiled = chunk("while true {\n    let x := 1\n}");
+        assert_eq!(compiled.instructions.iter().filter(|instruction| matches!(instruction, Instruction::EnterScope)).count(), 1);
+        assert_eq!(compiled.instructions.iter().filter(|instruction| matches!(instruction, Instruction::ExitScope)).count(), 1);
+    }
+
+    #[test]
+    fn test_compiles_calls_and_registers() {
+        let compiled = chunk("fn add(a: i32, b: i32) -> i32 { a + b }\nlet x := 1\nadd(x, 2 * 3)");
+        // only the declaration of `add` is left to the tree walker
+        assert_eq!(compiled.instructions.iter().filter(|instruction| matches!(instruction, Instruction::Eval(_))).count(), 1);
+        // the function is found before its arguments are evaluated, `x` is
+        // passed by reference and `2 * 3` by value
+        let call: Vec<_> = compiled.instructions.iter().skip_while(|instruction| !matches!(instruction, Instruction::Callee(..))).collect();
+        assert!(matches!(call[..], [
+            Instruction::Callee(0, _),
+            Instruction::Reference(0, 0, x),
+            Instruction::Constant(_),
+            Instruction::Constant(_),
+            Instruction::Binary(_),
+            Instruction::Argument(0, 1),
+            Instruction::Call(0),
+        ] if name(&compiled, *x) == "x"));
+        // `add` is declared by the tree walker, so it is looked up every time
+        assert!(compiled.registers.iter().any(|register| register.name == "add" && !register.cached));
+
+        let body = match &parse("fn add(a: i32, b: i32) -> i32 {\n    let c := a\n    c := b\n    c + b\n}").kind {
+            NodeKind::MultiExpression(multi) => match &multi.children[0].kind {
+                NodeKind::FnDeclaration(declaration) => declaration.body.clone(),
+                _ => unreachable!(),
+            },
+            NodeKind::FnDeclaration(declaration) => declaration.body.clone(),
+            _ => unreachable!(),
+        };
+        let compiled = body.chunk(compile_scope);
+        assert!(compiled.instructions.iter().any(|instruction| matches!(instruction, Instruction::Declare { register, .. } if name(&compiled, *register) == "c")));
+        assert!(compiled.instructions.iter().any(|instruction| matches!(instruction, Instruction::Assign(register, _) if name(&compiled, *register) == "c")));
+        // one register for each variable, all of them kept
+        let mut names: Vec<_> = compiled.registers.iter().map(|register| register.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert!(compiled.registers.iter().all(|register| register.cached));
+        // compiled once, however many times it is called
+        assert!(Rc::ptr_eq(&compiled, &body.chunk(compile_scope)));
+    }
+}